@@ -15,6 +15,11 @@ const SAND: i32 = 2;
 const GRASSLAND: i32 = 3;
 const HILLS: i32 = 4;
 const MOUNTAINS: i32 = 5;
+const RIVER: i32 = 6;
+const DESERT: i32 = 7;
+const SNOW: i32 = 8;
+const SWAMP: i32 = 9;
+const FOREST: i32 = 10;
 
 @fragment
 fn fragment(
@@ -31,7 +36,7 @@ fn fragment(
     let kind = tiles[index];
     pbr_input.material.base_color = tile_kind_to_color(kind);
 
-    if (kind == DEEP_WATER || kind == WATER) {
+    if (kind == DEEP_WATER || kind == WATER || kind == RIVER) {
         let time = globals.time;
         let uv = in.uv * 5.0;
         let pos = in.world_position.xy;
@@ -99,6 +104,16 @@ fn tile_kind_to_color(kind: i32) -> vec4<f32> {
         return vec4<f32>(0.45, 0.4, 0.35, 1.0);
     } else if (kind == MOUNTAINS) {
         return vec4<f32>(0.45, 0.45, 0.45, 1.0);
+    } else if (kind == RIVER) {
+        return vec4<f32>(0.1, 0.4, 0.6, 1.0);
+    } else if (kind == DESERT) {
+        return vec4<f32>(0.9, 0.78, 0.55, 1.0);
+    } else if (kind == SNOW) {
+        return vec4<f32>(0.92, 0.94, 0.96, 1.0);
+    } else if (kind == SWAMP) {
+        return vec4<f32>(0.3, 0.4, 0.25, 1.0);
+    } else if (kind == FOREST) {
+        return vec4<f32>(0.22, 0.48, 0.2, 1.0);
     } else {
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
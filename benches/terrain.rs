@@ -0,0 +1,60 @@
+//! Benchmarks of the terrain generation.
+//!
+//! `chunk` samples the heights and rivers of a whole chunk with the noise graphs already built,
+//! which is what the chunk tasks do for every chunk but the first one of a seed. `rebuild` samples
+//! a single tile with a new seed every time, which is what every tile used to cost when the noise
+//! graphs were built for each tile.
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use survicraft::prelude::*;
+
+/// The chunk radius used by the terrain plugin.
+const CHUNK_RADIUS: i32 = 16;
+
+fn chunk_tiles() -> Vec<TileCoord> {
+    let mut tiles = Vec::new();
+    for x in -CHUNK_RADIUS..=CHUNK_RADIUS {
+        for y in -CHUNK_RADIUS..=CHUNK_RADIUS {
+            tiles.push(TileCoord(IVec2::new(1000 + x, -500 + y)));
+        }
+    }
+    tiles
+}
+
+fn terrain_benchmark(c: &mut Criterion) {
+    let generators = TerrainGenerators::default();
+    let preset = TerrainGenerationPreset::default();
+    let tiles = chunk_tiles();
+
+    let mut group = c.benchmark_group("terrain");
+
+    let generator = generators.create(42, &preset).unwrap();
+    group.throughput(Throughput::Elements(tiles.len() as u64));
+    group.bench_function("chunk", |b| {
+        b.iter(|| {
+            for tile in &tiles {
+                let height = generator.height(tile);
+                std::hint::black_box(generator.river(tile, height));
+            }
+        })
+    });
+
+    let mut seed = 0;
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("rebuild", |b| {
+        b.iter_batched(
+            || {
+                seed += 1;
+                generators.create(seed, &preset).unwrap()
+            },
+            |generator| std::hint::black_box(generator.height(&tiles[0])),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, terrain_benchmark);
+criterion_main!(benches);
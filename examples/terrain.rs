@@ -52,6 +52,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: None,
                 elevation_max: Some(0.25),
+                ..default()
             },
         },
         TileAsset {
@@ -60,6 +61,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: Some(0.25),
                 elevation_max: Some(0.5),
+                ..default()
             },
         },
         TileAsset {
@@ -68,6 +70,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: Some(0.5),
                 elevation_max: Some(0.55),
+                ..default()
             },
         },
         TileAsset {
@@ -76,6 +79,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: Some(0.55),
                 elevation_max: Some(0.75),
+                ..default()
             },
         },
         TileAsset {
@@ -84,6 +88,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: Some(0.75),
                 elevation_max: Some(0.9),
+                ..default()
             },
         },
         TileAsset {
@@ -92,6 +97,7 @@ fn setup(mut ev_discover: EventWriter<TileDiscoverEvent>, mut commands: Commands
             generation: TileGeneration {
                 elevation_min: Some(0.9),
                 elevation_max: None,
+                ..default()
             },
         },
     ]));
@@ -121,6 +127,7 @@ fn spawn_player_on_space(
 
         commands.spawn((
             PlayerController,
+            TerrainViewer,
             Transform::from_xyz(0.0, 3.0, 0.0),
             Name::new("Dynamic Player Character"),
             Position(Vec3::new(0.0, 3.0, 0.0)),
@@ -133,6 +140,7 @@ fn spawn_player_on_space(
 
         commands.spawn((
             PlayerController,
+            TerrainViewer,
             Transform::from_xyz(0.0, 3.0, 0.0),
             Name::new("Kinematic Player Character"),
             Position(Vec3::new(0.0, 3.0, 0.0)),
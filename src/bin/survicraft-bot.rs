@@ -0,0 +1,83 @@
+use std::{thread, time::Duration};
+
+use clap::Parser;
+use survicraft::prelude::*;
+
+#[derive(Parser)]
+#[command(name = "survicraft-bot")]
+#[command(version = "0.1")]
+#[command(about = "Headless bot clients for load testing a survicraft server", long_about = None)]
+struct Cli {
+    /// The number of bots to run.
+    #[arg(short, long, default_value_t = 10)]
+    bots: usize,
+    /// The address of the server.
+    #[arg(short, long, default_value = "127.0.0.1")]
+    address: String,
+    /// How long each bot stays connected, in seconds.
+    #[arg(short, long, default_value_t = 60)]
+    duration: u64,
+    /// The delay between starting two bots, in milliseconds.
+    #[arg(long, default_value_t = 100)]
+    spawn_interval: u64,
+    /// How long a bot waits to join before giving up, in seconds.
+    #[arg(long, default_value_t = 15)]
+    connect_timeout: u64,
+    /// The delay between two chat messages of a bot, in seconds.
+    #[arg(long, default_value_t = 5)]
+    chat_interval: u64,
+    /// Simulate bad network conditions for testing: none, good, average, poor or
+    /// <latency_ms>,<jitter_ms>,<loss>.
+    #[arg(long, value_name = "CONDITIONS")]
+    network_conditions: Option<NetworkConditions>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let (sender, receiver) = crossbeam_channel::unbounded();
+
+    let mut handles = Vec::with_capacity(cli.bots);
+    for index in 0..cli.bots {
+        let config = BotConfig {
+            index,
+            address: cli.address.clone(),
+            duration: Duration::from_secs(cli.duration),
+            connect_timeout: Duration::from_secs(cli.connect_timeout),
+            chat_interval: Duration::from_secs(cli.chat_interval),
+            report: sender.clone(),
+        };
+        let network_conditions = cli.network_conditions;
+
+        let handle = thread::Builder::new()
+            .name(format!("bot-{index}"))
+            .spawn(move || {
+                let mut app = new_bot_app(config);
+                app.add_plugins(LauncherPlugin {
+                    render: false,
+                    network_conditions,
+                });
+                app.run();
+            })
+            .expect("Failed to spawn bot thread");
+        handles.push(handle);
+
+        thread::sleep(Duration::from_millis(cli.spawn_interval));
+    }
+    drop(sender);
+
+    let mut summary = BotSummary::new(cli.bots);
+    for report in receiver {
+        if let BotReport::Failed { index, reason } = &report {
+            eprintln!("bot {index} failed: {reason}");
+        }
+        summary.record(&report);
+    }
+
+    for handle in handles {
+        if handle.join().is_err() {
+            eprintln!("a bot thread panicked");
+        }
+    }
+
+    println!("{summary}");
+}
@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use bevy::math::IRect;
+use clap::Parser;
+use image::{GrayImage, Luma, Rgb, RgbImage};
+use survicraft::prelude::*;
+
+#[derive(Parser)]
+#[command(name = "survicraft-mapgen")]
+#[command(version = "0.1")]
+#[command(about = "Export heightmaps, tile maps and feature maps of a survicraft world", long_about = None)]
+struct Cli {
+    /// The seed of the world.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u32,
+    /// The name of a builtin preset (default, archipelago, highlands or flat), or the path of a
+    /// RON file with a preset.
+    #[arg(short, long, default_value = "default")]
+    preset: String,
+    /// The first tile of the map along the x axis.
+    #[arg(short, long, default_value_t = -256, allow_negative_numbers = true)]
+    x: i32,
+    /// The first tile of the map along the y axis.
+    #[arg(short, long, default_value_t = -256, allow_negative_numbers = true)]
+    y: i32,
+    /// The number of tiles along the x axis.
+    #[arg(long, default_value_t = 512)]
+    width: u32,
+    /// The number of tiles along the y axis.
+    #[arg(long, default_value_t = 512)]
+    height: u32,
+    /// The number of tiles per pixel, used to preview large parts of the world.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    scale: u32,
+    /// The directory where the images are written.
+    #[arg(short, long, default_value = "mapgen")]
+    output: PathBuf,
+}
+
+/// The largest map that can be exported, in pixels along each axis.
+const MAX_MAP_PIXELS: u32 = 16384;
+
+/// The colors of the tile kinds, the same as `tile_kind_to_color` in `tile_chunk.wgsl`.
+const TILE_COLORS: [[f32; 3]; 11] = [
+    [0.0, 0.18, 0.35],  // deep water
+    [0.0, 0.3, 0.5],    // water
+    [0.85, 0.73, 0.5],  // sand
+    [0.4, 0.65, 0.3],   // grassland
+    [0.45, 0.4, 0.35],  // hills
+    [0.45, 0.45, 0.45], // mountains
+    [0.1, 0.4, 0.6],    // river
+    [0.9, 0.78, 0.55],  // desert
+    [0.92, 0.94, 0.96], // snow
+    [0.3, 0.4, 0.25],   // swamp
+    [0.22, 0.48, 0.2],  // forest
+];
+
+/// The colors of the features in the feature map, by the index of the feature.
+const FEATURE_COLORS: [[u8; 3]; 4] = [[0, 90, 0], [200, 40, 40], [230, 200, 40], [150, 60, 200]];
+
+fn tile_color(kind: Option<usize>) -> [u8; 3] {
+    let color = kind
+        .and_then(|kind| TILE_COLORS.get(kind))
+        .unwrap_or(&[0.0, 0.0, 0.0]);
+
+    color.map(|channel| (channel * 255.0).round() as u8)
+}
+
+fn load_preset(name: &str) -> anyhow::Result<TerrainGenerationPreset> {
+    let builtin = TerrainGenerationPreset::builtin()
+        .into_iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name));
+    if let Some(preset) = builtin {
+        return Ok(preset);
+    }
+
+    let content = std::fs::read_to_string(name)
+        .with_context(|| format!("{name} is not a builtin preset or a preset file"))?;
+    let preset: TerrainGenerationPreset =
+        ron::from_str(&content).with_context(|| format!("Could not parse preset file {name}"))?;
+    preset
+        .validate()
+        .map_err(|err| anyhow::anyhow!("Invalid preset file {name}: {err}"))?;
+
+    Ok(preset)
+}
+
+/// The tiles covered by the map. Fails when the map goes past the last tile coordinate or when
+/// the images would be too large.
+fn map_rect(cli: &Cli) -> anyhow::Result<IRect> {
+    for (axis, size) in [("x", cli.width), ("y", cli.height)] {
+        let pixels = size.div_ceil(cli.scale);
+        if pixels > MAX_MAP_PIXELS {
+            anyhow::bail!(
+                "The map is {pixels} pixels along the {axis} axis, at most {MAX_MAP_PIXELS} are \
+                 supported. Use a larger --scale or a smaller map"
+            );
+        }
+    }
+
+    let end = |axis: &str, start: i32, size: u32| {
+        i32::try_from(size)
+            .ok()
+            .and_then(|size| start.checked_add(size))
+            .with_context(|| format!("The map goes past the last tile along the {axis} axis"))
+    };
+
+    Ok(IRect::new(
+        cli.x,
+        cli.y,
+        end("x", cli.x, cli.width)?,
+        end("y", cli.y, cli.height)?,
+    ))
+}
+
+fn save(image: impl FnOnce(&Path) -> image::ImageResult<()>, path: PathBuf) -> anyhow::Result<()> {
+    image(&path).with_context(|| format!("Could not write {}", path.display()))?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let preset = load_preset(&cli.preset)?;
+
+    let config = WorldMapConfig {
+        seed: cli.seed,
+        rect: map_rect(&cli)?,
+        scale: cli.scale,
+        preset,
+    };
+    println!(
+        "Generating {}x{} tiles of seed {} with the {} preset",
+        cli.width, cli.height, config.seed, config.preset.name
+    );
+    let map = generate_world_map(&config)?;
+
+    std::fs::create_dir_all(&cli.output)
+        .with_context(|| format!("Could not create {}", cli.output.display()))?;
+    let prefix = format!("{}_{}", config.preset.name.to_lowercase(), config.seed);
+
+    let heightmap = GrayImage::from_fn(map.width, map.height, |x, y| {
+        Luma([(map.get(x, y).height * 255.0).round() as u8])
+    });
+    save(
+        |path| heightmap.save(path),
+        cli.output.join(format!("{prefix}_heightmap.png")),
+    )?;
+
+    let tiles = RgbImage::from_fn(map.width, map.height, |x, y| {
+        Rgb(tile_color(map.get(x, y).kind))
+    });
+    save(
+        |path| tiles.save(path),
+        cli.output.join(format!("{prefix}_tiles.png")),
+    )?;
+
+    // The features are drawn over a darker copy of the tile map
+    let features = RgbImage::from_fn(map.width, map.height, |x, y| {
+        let tile = map.get(x, y);
+        match tile.feature {
+            Some(feature) => Rgb(FEATURE_COLORS[feature % FEATURE_COLORS.len()]),
+            None => Rgb(tile_color(tile.kind).map(|channel| channel / 2)),
+        }
+    });
+    save(
+        |path| features.save(path),
+        cli.output.join(format!("{prefix}_features.png")),
+    )?;
+
+    Ok(())
+}
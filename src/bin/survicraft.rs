@@ -5,12 +5,20 @@ use survicraft::prelude::*;
 #[command(name = "survicraft")]
 #[command(version = "0.1")]
 #[command(about = "Launcher for survicraft client", long_about = None)]
-struct Cli;
+struct Cli {
+    /// Simulate bad network conditions for testing: none, good, average, poor or
+    /// <latency_ms>,<jitter_ms>,<loss>. Overrides the config file.
+    #[arg(long, value_name = "CONDITIONS")]
+    network_conditions: Option<NetworkConditions>,
+}
 
 fn main() {
-    let _ = Cli::parse();
+    let cli = Cli::parse();
 
     let mut app = new_gui_app();
-    app.add_plugins(LauncherPlugin { render: true });
+    app.add_plugins(LauncherPlugin {
+        render: true,
+        network_conditions: cli.network_conditions,
+    });
     app.run();
 }
@@ -5,12 +5,20 @@ use survicraft::prelude::*;
 #[command(name = "survicraftd")]
 #[command(version = "0.1")]
 #[command(about = "Dedicated server for survicraft client", long_about = None)]
-struct Cli;
+struct Cli {
+    /// Simulate bad network conditions for testing: none, good, average, poor or
+    /// <latency_ms>,<jitter_ms>,<loss>. Overrides the config file.
+    #[arg(long, value_name = "CONDITIONS")]
+    network_conditions: Option<NetworkConditions>,
+}
 
 fn main() {
-    let _ = Cli::parse();
+    let cli = Cli::parse();
 
     let mut app = new_headless_app();
-    app.add_plugins(LauncherPlugin { render: false });
+    app.add_plugins(LauncherPlugin {
+        render: false,
+        network_conditions: cli.network_conditions,
+    });
     app.run();
 }
@@ -2,20 +2,40 @@
 
 use std::collections::VecDeque;
 
-use bevy::{prelude::*, ui::FocusPolicy};
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    ui::FocusPolicy,
+};
 use bevy_simple_text_input::*;
-use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::prelude::*;
 
 pub mod prelude {
     pub use super::{
-        AddChatHistoryItemEvent, ChatHistoryRoot, ChatInputRoot, ChatMessageSubmittedEvent,
-        ChatPlugin,
+        AddChatHistoryItemEvent, ChatEnabled, ChatHistoryRoot, ChatInputRoot, ChatInputSet,
+        ChatMessageKind, ChatMessageSubmittedEvent, ChatPlugin,
     };
 }
 
 const BORDER_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.25);
 const BACKGROUND_COLOR: Color = Color::srgba(0.15, 0.15, 0.15, 0.75);
 const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const SYSTEM_TEXT_COLOR: Color = Color::srgb(1.0, 0.85, 0.35);
+const WHISPER_TEXT_COLOR: Color = Color::srgb(0.85, 0.55, 1.0);
+const TEAM_TEXT_COLOR: Color = Color::srgb(0.45, 0.9, 0.55);
+
+/// The number of messages kept in the chat history.
+const HISTORY_SIZE: usize = 200;
+/// The number of submitted messages that can be recalled in the chat input.
+const INPUT_HISTORY_SIZE: usize = 50;
+/// The time in seconds a message stays visible while the chat is closed.
+const FADE_DELAY: f32 = 10.0;
+/// The time in seconds it takes a message to fade out.
+const FADE_DURATION: f32 = 2.0;
+/// The scroll distance in pixels of one line of the mouse wheel.
+const SCROLL_LINE_HEIGHT: f32 = 24.0;
 
 /// Event that is triggered when the user submits a chat message.
 #[derive(Debug, Clone, Event)]
@@ -23,11 +43,50 @@ pub struct ChatMessageSubmittedEvent {
     pub message: String,
 }
 
+/// The kind of a chat message, used to style it in the chat history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum ChatMessageKind {
+    /// A message sent by a player to everyone.
+    #[default]
+    Player,
+    /// A notice from the server, such as command replies.
+    System,
+    /// A private message between two players.
+    Whisper,
+    /// A message sent to the team of the player.
+    Team,
+}
+
+impl ChatMessageKind {
+    pub fn color(&self) -> Color {
+        match self {
+            ChatMessageKind::Player => TEXT_COLOR,
+            ChatMessageKind::System => SYSTEM_TEXT_COLOR,
+            ChatMessageKind::Whisper => WHISPER_TEXT_COLOR,
+            ChatMessageKind::Team => TEAM_TEXT_COLOR,
+        }
+    }
+}
+
 /// Event that is used to add a chat message to the chat history UI.
 #[derive(Debug, Clone, Event)]
 pub struct AddChatHistoryItemEvent {
     pub sender: String,
     pub message: String,
+    pub kind: ChatMessageKind,
+}
+
+impl AddChatHistoryItemEvent {
+    /// The text of the message as displayed in the chat history.
+    pub fn text(&self) -> String {
+        match self.kind {
+            ChatMessageKind::Player => format!("{}: {}", self.sender, self.message),
+            ChatMessageKind::System | ChatMessageKind::Whisper => {
+                format!("[{}] {}", self.sender, self.message)
+            }
+            ChatMessageKind::Team => format!("[Team] {}: {}", self.sender, self.message),
+        }
+    }
 }
 
 /// Marker component that will spawn the chat history UI as a child.
@@ -38,23 +97,13 @@ pub struct ChatHistoryRoot;
 #[derive(Component)]
 pub struct ChatInputRoot;
 
-// TODO: How can I add this into some kind of common settings config?
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Reflect, Actionlike)]
-enum ChatInputAction {
-    OpenChat,
-    CloseChat,
-}
-
-impl ChatInputAction {
-    fn default_input_map() -> InputMap<Self> {
-        InputMap::default()
-            .with(Self::OpenChat, KeyCode::Enter)
-            .with(Self::CloseChat, KeyCode::Escape)
-    }
-}
-
+/// Resource that is true while the chat input is open.
 #[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Deref, DerefMut)]
-struct ChatEnabled(bool);
+pub struct ChatEnabled(pub bool);
+
+/// System set of the system that opens and closes the chat input.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChatInputSet;
 
 #[derive(Debug, Component, Clone, PartialEq, Eq)]
 struct HistoryListUI {
@@ -62,8 +111,67 @@ struct HistoryListUI {
     max_messages: usize,
 }
 
-#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
-struct HistoryItemUI;
+#[derive(Debug, Component, Clone, PartialEq)]
+struct HistoryItemUI {
+    /// The elapsed time when the message was received, used to fade it out.
+    received: f32,
+    /// The local time when the message was received.
+    timestamp: String,
+    text: String,
+}
+
+impl HistoryItemUI {
+    fn display_text(&self, timestamps: bool) -> String {
+        if timestamps {
+            format!("[{}] {}", self.timestamp, self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// The messages submitted in the chat input, recalled with the up and down arrows.
+#[derive(Resource, Debug, Clone, Default)]
+struct ChatInputHistory {
+    entries: VecDeque<String>,
+    /// The entry shown in the input, counted from the most recent one.
+    index: Option<usize>,
+}
+
+impl ChatInputHistory {
+    fn push(&mut self, message: String) {
+        self.index = None;
+        if self.entries.front() == Some(&message) {
+            return;
+        }
+
+        self.entries.push_front(message);
+        self.entries.truncate(INPUT_HISTORY_SIZE);
+    }
+
+    /// Move to an older entry with a positive step, or to a newer one with a negative step.
+    /// Returns the text to show in the input.
+    fn step(&mut self, step: isize) -> Option<String> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        let index = match self.index {
+            None if step > 0 => Some(0),
+            None => return None,
+            Some(index) => index
+                .checked_add_signed(step)
+                .map(|index| index.min(self.entries.len() - 1)),
+        };
+        self.index = index;
+
+        Some(
+            index
+                .and_then(|index| self.entries.get(index).cloned())
+                .unwrap_or_default(),
+        )
+    }
+}
 
 #[derive(Component, Clone, Copy, Debug)]
 struct ChatMessageInput;
@@ -73,7 +181,9 @@ pub struct ChatPlugin;
 impl Plugin for ChatPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ChatEnabled>();
-        app.add_plugins(InputManagerPlugin::<ChatInputAction>::default());
+        app.init_resource::<KeyBindings>();
+        app.init_resource::<ChatInputHistory>();
+        app.init_resource::<DisplayChatTimestampsSetting>();
 
         app.add_event::<ChatMessageSubmittedEvent>();
         app.add_event::<AddChatHistoryItemEvent>();
@@ -84,9 +194,13 @@ impl Plugin for ChatPlugin {
                 chat_history_ui_setup,
                 chat_input_ui_setup,
                 on_chat_message,
+                update_chat_timestamps.run_if(resource_changed::<DisplayChatTimestampsSetting>),
                 (
                     on_chat_submit.run_if(resource_equals(ChatEnabled(true))),
-                    handle_chat_input_action,
+                    recall_chat_input.run_if(resource_equals(ChatEnabled(true))),
+                    handle_chat_input_action.in_set(ChatInputSet),
+                    scroll_chat_history,
+                    fade_chat_history,
                 )
                     .chain(),
             ),
@@ -105,8 +219,9 @@ fn chat_history_ui_setup(
             Name::new("ChatHistoryUI"),
             HistoryListUI {
                 messages: VecDeque::new(),
-                max_messages: 5,
+                max_messages: HISTORY_SIZE,
             },
+            ScrollPosition::default(),
             Node {
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
@@ -129,11 +244,6 @@ fn chat_input_ui_setup(
     debug!("Setting up chat input UI...");
 
     commands.entity(root.entity()).with_children(|parent| {
-        parent.spawn((
-            Name::new("ChatInputManager"),
-            ChatInputAction::default_input_map(),
-        ));
-
         parent
             .spawn((
                 Name::new("ChatInputUI"),
@@ -189,6 +299,7 @@ fn on_chat_submit(
     mut ev_input: EventReader<TextInputSubmitEvent>,
     message_input: Single<Entity, With<ChatMessageInput>>,
     mut ev_submitted: EventWriter<ChatMessageSubmittedEvent>,
+    mut input_history: ResMut<ChatInputHistory>,
 ) {
     for ev in ev_input.read() {
         if ev.entity != message_input.entity() {
@@ -197,6 +308,7 @@ fn on_chat_submit(
 
         let msg = ev.value.trim();
         if !msg.is_empty() {
+            input_history.push(msg.to_string());
             ev_submitted.write(ChatMessageSubmittedEvent {
                 message: msg.to_string(),
             });
@@ -207,32 +319,35 @@ fn on_chat_submit(
 fn on_chat_message(
     mut commands: Commands,
     mut ev_chat: EventReader<AddChatHistoryItemEvent>,
-    history: Single<(Entity, &mut HistoryListUI)>,
+    history: Single<(Entity, &mut HistoryListUI, &mut ScrollPosition)>,
+    timestamps: Res<DisplayChatTimestampsSetting>,
+    time: Res<Time>,
 ) {
-    let (entity, mut history) = history.into_inner();
+    let (entity, mut history, mut scroll) = history.into_inner();
 
     for chat_item in ev_chat.read() {
+        let item = HistoryItemUI {
+            received: time.elapsed_secs(),
+            timestamp: chrono::Local::now().format("%H:%M").to_string(),
+            text: chat_item.text(),
+        };
+
         let item = commands
             .spawn((
                 Name::new("ChatHistoryItem"),
-                HistoryItemUI,
                 Node {
                     width: Val::Percent(100.0),
                     height: Val::Auto,
                     ..default()
                 },
-                BackgroundColor(Color::NONE),
+                Text::new(item.display_text(**timestamps)),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(chat_item.kind.color()),
+                item,
             ))
-            .with_children(|parent| {
-                parent.spawn((
-                    Name::new("ChatHistoryItemText"),
-                    Text::new(format!("{}: {}", chat_item.sender, chat_item.message)),
-                    TextFont {
-                        font_size: 20.0,
-                        ..default()
-                    },
-                ));
-            })
             .id();
         commands.entity(entity).add_child(item);
         history.messages.push_back(item);
@@ -242,23 +357,135 @@ fn on_chat_message(
                 commands.entity(old).despawn();
             }
         }
+
+        // NOTE: the scroll position is clamped to the content by the layout
+        scroll.offset_y = f32::MAX;
+    }
+}
+
+fn update_chat_timestamps(
+    timestamps: Res<DisplayChatTimestampsSetting>,
+    mut q_items: Query<(&HistoryItemUI, &mut Text)>,
+) {
+    for (item, mut text) in &mut q_items {
+        text.0 = item.display_text(**timestamps);
+    }
+}
+
+/// Scroll the chat history with the mouse wheel while the chat is open.
+fn scroll_chat_history(
+    mut ev_wheel: EventReader<MouseWheel>,
+    chat_enabled: Res<ChatEnabled>,
+    mut scroll: Single<&mut ScrollPosition, With<HistoryListUI>>,
+) {
+    // Jump back to the most recent messages when the chat is opened or closed
+    if chat_enabled.is_changed() {
+        scroll.offset_y = f32::MAX;
+    }
+
+    for ev in ev_wheel.read() {
+        if !chat_enabled.0 {
+            continue;
+        }
+
+        let delta = match ev.unit {
+            MouseScrollUnit::Line => ev.y * SCROLL_LINE_HEIGHT,
+            MouseScrollUnit::Pixel => ev.y,
+        };
+        scroll.offset_y = (scroll.offset_y - delta).max(0.0);
+    }
+}
+
+/// Fade out the old messages while the chat is closed. All the messages are shown while the chat
+/// is open.
+fn fade_chat_history(
+    chat_enabled: Res<ChatEnabled>,
+    time: Res<Time>,
+    mut q_items: Query<(&HistoryItemUI, &mut Node, &mut TextColor)>,
+) {
+    let now = time.elapsed_secs();
+
+    for (item, mut node, mut color) in &mut q_items {
+        let alpha = if chat_enabled.0 {
+            1.0
+        } else {
+            1.0 - ((now - item.received - FADE_DELAY) / FADE_DURATION).clamp(0.0, 1.0)
+        };
+
+        let display = if alpha > 0.0 {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if node.display != display {
+            node.display = display;
+        }
+        if color.0.alpha() != alpha {
+            color.0.set_alpha(alpha);
+        }
+    }
+}
+
+/// Recall the previously submitted messages with the up and down arrows.
+fn recall_chat_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    chat_enabled: Res<ChatEnabled>,
+    mut input_history: ResMut<ChatInputHistory>,
+    mut input: Single<&mut TextInputValue, With<ChatMessageInput>>,
+) {
+    if chat_enabled.is_changed() {
+        input_history.index = None;
+    }
+
+    let step = if keys.just_pressed(KeyCode::ArrowUp) {
+        1
+    } else if keys.just_pressed(KeyCode::ArrowDown) {
+        -1
+    } else {
+        return;
+    };
+
+    if let Some(text) = input_history.step(step) {
+        input.0 = text;
     }
 }
 
 fn handle_chat_input_action(
-    action_state: Single<&ActionState<ChatInputAction>>,
+    action_input: ActionInput,
     mut chat_enabled: ResMut<ChatEnabled>,
     input: Single<(&mut Visibility, &mut TextInputInactive), With<ChatMessageInput>>,
 ) {
     let (mut visibility, mut text_input) = input.into_inner();
 
-    if action_state.just_pressed(&ChatInputAction::OpenChat) {
+    if !chat_enabled.0 && action_input.just_pressed(GameAction::OpenChat) {
         chat_enabled.0 = true;
         *visibility = Visibility::Visible;
         text_input.0 = false;
-    } else if action_state.just_pressed(&ChatInputAction::CloseChat) {
+    } else if chat_enabled.0 && action_input.just_pressed(GameAction::CloseChat) {
         chat_enabled.0 = false;
         *visibility = Visibility::Hidden;
         text_input.0 = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_input_history_recall() {
+        let mut history = ChatInputHistory::default();
+        assert_eq!(history.step(1), None);
+
+        history.push("first".to_string());
+        history.push("second".to_string());
+        history.push("second".to_string());
+
+        assert_eq!(history.step(1).as_deref(), Some("second"));
+        assert_eq!(history.step(1).as_deref(), Some("first"));
+        assert_eq!(history.step(1).as_deref(), Some("first"));
+        assert_eq!(history.step(-1).as_deref(), Some("second"));
+        assert_eq!(history.step(-1).as_deref(), Some(""));
+        assert_eq!(history.step(-1), None);
+    }
+}
@@ -4,6 +4,8 @@
 //! that query and inserts the result as a component on the entities. This is done using tasks
 //! to allow for non-blocking computation and parallel processing.
 
+use std::{any::type_name, cell::RefCell, collections::HashMap, rc::Rc};
+
 use bevy::{
     ecs::{
         query::{QueryData, QueryItem},
@@ -15,7 +17,9 @@ use bevy::{
 use itertools::Itertools;
 
 pub mod prelude {
-    pub use super::{ChunkMapFunction, ChunkMapInput, ChunkMapPlugin};
+    pub use super::{
+        ChunkMapCache, ChunkMapFunction, ChunkMapInput, ChunkMapPlugin, ChunkMapTasks,
+    };
 }
 
 pub trait ChunkMapInput {
@@ -27,6 +31,36 @@ pub trait ChunkMapFunction<T, U> {
     fn get(&self, point: T) -> U;
 }
 
+/// A value that is built once and reused while its key stays the same. Meant to be used in a
+/// `thread_local!` by the chunk map functions that are expensive to build and cannot be shared
+/// between the threads of the tasks, like noise graphs.
+pub struct ChunkMapCache<K, V>(RefCell<Option<(K, Rc<V>)>>);
+
+impl<K, V> Default for ChunkMapCache<K, V> {
+    fn default() -> Self {
+        Self(RefCell::new(None))
+    }
+}
+
+impl<K: PartialEq, V> ChunkMapCache<K, V> {
+    pub const fn new() -> Self {
+        Self(RefCell::new(None))
+    }
+
+    /// Get the cached value, or build it if the key changed.
+    pub fn get_or_build(&self, key: K, build: impl FnOnce(&K) -> V) -> Rc<V> {
+        let mut cache = self.0.borrow_mut();
+        match cache.as_ref() {
+            Some((cached, value)) if *cached == key => value.clone(),
+            _ => {
+                let value = Rc::new(build(&key));
+                *cache = Some((key, value.clone()));
+                value
+            }
+        }
+    }
+}
+
 pub struct ChunkMapPlugin<T, U, F>
 where
     F: ChunkMapFunction<T, U>,
@@ -57,11 +91,25 @@ where
 {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.func.clone());
+        app.init_resource::<ChunkMapTasks>();
 
         app.add_systems(Update, (create_task::<T, U, F>, handle_task::<U>));
     }
 }
 
+/// The number of chunks that are still waiting for their compute task, per output component.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ChunkMapTasks(HashMap<&'static str, usize>);
+
+impl ChunkMapTasks {
+    /// The output components sorted by name, with the number of pending tasks.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        let mut tasks = self.0.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        tasks.sort_by_key(|(name, _)| *name);
+        tasks.into_iter()
+    }
+}
+
 #[derive(Component)]
 struct ComputeTask<U> {
     task: Task<CommandQueue>,
@@ -150,13 +198,22 @@ fn create_task<T, U, F>(
     }
 }
 
-fn handle_task<U>(mut commands: Commands, mut tasks: Query<&mut ComputeTask<U>>)
-where
+fn handle_task<U>(
+    mut commands: Commands,
+    mut tasks: Query<&mut ComputeTask<U>>,
+    mut pending: ResMut<ChunkMapTasks>,
+) where
     U: Send + Sync + 'static,
 {
+    let mut remaining = 0;
     for mut task in tasks.iter_mut() {
         if let Some(mut commands_queue) = block_on(future::poll_once(&mut task.task)) {
             commands.append(&mut commands_queue);
+        } else {
+            remaining += 1;
         }
     }
+
+    let name = type_name::<U>().rsplit("::").next().unwrap_or_default();
+    pending.0.insert(name, remaining);
 }
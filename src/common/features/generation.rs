@@ -7,9 +7,11 @@ pub struct FeaturesGenerationPlugin {}
 
 impl Plugin for FeaturesGenerationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ChunkMapPlugin::<(TileCoord, Tile), TileFeature, _>::new(
-            PlanetFeatures::default(),
-        ));
+        app.add_plugins(ChunkMapPlugin::<
+            (TileCoord, Tile, TileTemperature, TileMoisture),
+            TileFeature,
+            _,
+        >::new(PlanetFeatures::default()));
 
         app.add_systems(
             Update,
@@ -17,7 +19,10 @@ impl Plugin for FeaturesGenerationPlugin {
         );
         app.add_systems(
             Update,
-            update_terrain_seed.run_if(resource_changed::<TerrainGenerationSeed>),
+            update_terrain_generation.run_if(
+                resource_changed::<TerrainGenerationSeed>
+                    .or(resource_changed::<TerrainGenerationPreset>),
+            ),
         );
     }
 }
@@ -26,17 +31,30 @@ fn update_features_map(features: Res<FeatureAssets>, mut planet_features: ResMut
     planet_features.map = features.clone();
 }
 
-fn update_terrain_seed(mut func: ResMut<PlanetFeatures>, seed: Res<TerrainGenerationSeed>) {
-    *func = func.clone().with_seed(**seed + 1);
-    debug!("Updated terrain seed to {}", seed.0 + 1);
+fn update_terrain_generation(
+    mut func: ResMut<PlanetFeatures>,
+    seed: Res<TerrainGenerationSeed>,
+    preset: Res<TerrainGenerationPreset>,
+) {
+    *func = func.clone().with_preset(&preset).with_seed(**seed + 1);
+    debug!(
+        "Updated terrain seed to {} with preset {}",
+        seed.0 + 1,
+        preset.name
+    );
 }
 
-impl ChunkMapInput for (TileCoord, Tile) {
-    type Query = (&'static TileCoord, &'static Tile);
+impl ChunkMapInput for (TileCoord, Tile, TileTemperature, TileMoisture) {
+    type Query = (
+        &'static TileCoord,
+        &'static Tile,
+        &'static TileTemperature,
+        &'static TileMoisture,
+    );
 
     fn from_query_item(
         item: bevy::ecs::query::QueryItem<<Self::Query as bevy::ecs::query::QueryData>::ReadOnly>,
     ) -> Self {
-        (item.0.clone(), item.1.clone())
+        (item.0.clone(), item.1.clone(), *item.2, *item.3)
     }
 }
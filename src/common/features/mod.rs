@@ -10,6 +10,7 @@ use self::generation::FeaturesGenerationPlugin;
 
 pub mod prelude {
     pub use super::components::TileFeature;
+    pub use super::planet::PlanetFeatures;
     pub use super::render::FeaturesRenderPlugin;
     pub use super::resources::*;
     pub use super::FeaturesPlugin;
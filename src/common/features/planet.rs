@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use bevy::prelude::*;
 use noise::{Fbm, MultiFractal, NoiseFn, Perlin, Worley};
 
@@ -58,36 +60,78 @@ impl PlanetFeatures {
         self.seed = seed;
         self
     }
+
+    pub fn with_preset(mut self, preset: &TerrainGenerationPreset) -> Self {
+        self.zoom_scale = preset.zoom_scale;
+        self.patch_frequency = preset.feature_patch_frequency;
+        self.patch_lacunarity = preset.feature_patch_lacunarity;
+        self.abundance_frequency = preset.feature_abundance_frequency;
+        self.abundance_lacunarity = preset.feature_abundance_lacunarity;
+        self
+    }
+
+    /// The noise functions of the features, built once per thread and reused by the tasks of
+    /// every chunk until the seed or the preset changes.
+    fn noise(&self) -> Rc<PlanetFeaturesNoise> {
+        let key = (
+            self.seed,
+            self.patch_frequency,
+            self.patch_lacunarity,
+            self.abundance_frequency,
+            self.abundance_lacunarity,
+        );
+
+        PLANET_FEATURES_NOISE.with(|cache| {
+            cache.get_or_build(key, |_| PlanetFeaturesNoise {
+                selection: Fbm::<Worley>::new(self.seed)
+                    .set_frequency(self.patch_frequency)
+                    .set_persistence(0.5)
+                    .set_lacunarity(self.patch_lacunarity)
+                    .set_octaves(8),
+                abundance: Fbm::<Perlin>::new(self.seed + 1)
+                    .set_frequency(self.abundance_frequency)
+                    .set_persistence(0.5)
+                    .set_lacunarity(self.abundance_lacunarity)
+                    .set_octaves(4),
+            })
+        })
+    }
+}
+
+thread_local! {
+    static PLANET_FEATURES_NOISE: ChunkMapCache<(u32, f64, f64, f64, f64), PlanetFeaturesNoise> =
+        const { ChunkMapCache::new() };
+}
+
+struct PlanetFeaturesNoise {
+    selection: Fbm<Worley>,
+    abundance: Fbm<Perlin>,
 }
 
-impl ChunkMapFunction<(TileCoord, Tile), TileFeature> for PlanetFeatures {
-    fn get(&self, (point, tile): (TileCoord, Tile)) -> TileFeature {
+impl ChunkMapFunction<(TileCoord, Tile, TileTemperature, TileMoisture), TileFeature>
+    for PlanetFeatures
+{
+    fn get(
+        &self,
+        (point, tile, temperature, moisture): (TileCoord, Tile, TileTemperature, TileMoisture),
+    ) -> TileFeature {
         let x = point.x as f64 * self.zoom_scale;
         let y = point.y as f64 * self.zoom_scale;
+        let noise = self.noise();
 
         // 1. Voronoi-like patch selection using Worley noise
-        let selection_noise = Fbm::<Worley>::new(self.seed)
-            .set_frequency(self.patch_frequency)
-            .set_persistence(0.5)
-            .set_lacunarity(self.patch_lacunarity)
-            .set_octaves(8)
-            .get([x, y]);
+        let selection_noise = noise.selection.get([x, y]);
 
         let feature_index =
             ((selection_noise + 1.0) * 0.5 * self.map.features.len() as f64) as usize;
         let feature_index = feature_index.min(self.map.features.len() - 1);
 
         // 2. Abundance variation using high-frequency FBM
-        let abundance_val = Fbm::<Perlin>::new(self.seed + 1)
-            .set_frequency(self.abundance_frequency)
-            .set_persistence(0.5)
-            .set_lacunarity(self.abundance_lacunarity)
-            .set_octaves(4)
-            .get([x, y]);
+        let abundance_val = noise.abundance.get([x, y]);
         let abundance_val = (abundance_val + 1.0) * 0.5;
 
         let feature = self.map.features[feature_index].clone();
-        let variant = feature.get_variant(&tile);
+        let variant = feature.get_variant(&tile, *temperature, *moisture);
 
         if let Some(variant) = variant {
             if variant.threshold <= abundance_val {
@@ -10,7 +10,7 @@ pub struct FeaturesRenderPlugin {}
 
 impl Plugin for FeaturesRenderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, handle_feature_tile);
+        app.add_systems(Update, (handle_feature_tile, update_feature_height));
     }
 }
 
@@ -20,14 +20,24 @@ struct ChunkFeatureReady;
 fn handle_feature_tile(
     mut commands: Commands,
     assets: Res<FeatureAssets>,
-    q_hex: Query<(Entity, &TileWorldHeight, &Tile, &TileFeature), Without<ChunkFeatureReady>>,
+    q_hex: Query<
+        (
+            Entity,
+            &TileWorldHeight,
+            &Tile,
+            &TileTemperature,
+            &TileMoisture,
+            &TileFeature,
+        ),
+        Without<ChunkFeatureReady>,
+    >,
 ) {
     if q_hex.is_empty() {
         return;
     }
     trace!("Handling feature tiles for {} hexes", q_hex.iter().len());
 
-    for (entity, height, tile, feature) in q_hex.iter() {
+    for (entity, height, tile, temperature, moisture, feature) in q_hex.iter() {
         commands.entity(entity).insert(ChunkFeatureReady);
 
         let Some(id) = (**feature).clone() else {
@@ -38,7 +48,7 @@ fn handle_feature_tile(
             continue;
         };
 
-        let Some(variant) = feature_asset.get_variant(tile) else {
+        let Some(variant) = feature_asset.get_variant(tile, **temperature, **moisture) else {
             continue;
         };
 
@@ -51,3 +61,20 @@ fn handle_feature_tile(
         });
     }
 }
+
+/// Move the features of the tiles whose height changed after an edit of the terrain.
+fn update_feature_height(
+    q_tiles: Query<
+        (&TileWorldHeight, &Children),
+        (Changed<TileWorldHeight>, With<ChunkFeatureReady>),
+    >,
+    mut q_features: Query<&mut Transform, With<SceneRoot>>,
+) {
+    for (height, children) in q_tiles.iter() {
+        for child in children.iter() {
+            if let Ok(mut transform) = q_features.get_mut(child) {
+                transform.translation.y = (**height) as f32;
+            }
+        }
+    }
+}
@@ -12,17 +12,40 @@ pub struct FeatureAsset {
 }
 
 impl FeatureAsset {
-    pub fn get_variant(&self, id: &TileID) -> Option<&FeatureVariant> {
-        self.variants.iter().find(|variant| &variant.id == id)
+    /// Find the variant for the biome of a tile, the first variant of the tile whose climate
+    /// ranges contain the climate of the tile.
+    pub fn get_variant(
+        &self,
+        id: &TileID,
+        temperature: f64,
+        moisture: f64,
+    ) -> Option<&FeatureVariant> {
+        self.variants
+            .iter()
+            .find(|variant| &variant.id == id && variant.matches(temperature, moisture))
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct FeatureVariant {
     pub id: TileID,
     pub name: String,
     pub threshold: f64,
     pub scene: Handle<Scene>,
+    pub temperature_min: Option<f64>,
+    pub temperature_max: Option<f64>,
+    pub moisture_min: Option<f64>,
+    pub moisture_max: Option<f64>,
+}
+
+impl FeatureVariant {
+    /// Whether the climate is within the bounds of the variant.
+    pub fn matches(&self, temperature: f64, moisture: f64) -> bool {
+        self.temperature_min.is_none_or(|min| temperature >= min)
+            && self.temperature_max.is_none_or(|max| temperature <= max)
+            && self.moisture_min.is_none_or(|min| moisture >= min)
+            && self.moisture_max.is_none_or(|max| moisture <= max)
+    }
 }
 
 #[derive(Resource, Clone, Default, Debug)]
@@ -48,6 +48,7 @@ fn on_item_added(
 #[cfg(feature = "debug")]
 mod debug {
     use super::*;
+    use crate::common::prelude::*;
 
     #[derive(Debug, Resource, Default, Clone, Deref, DerefMut)]
     struct ShowGrid(pub bool);
@@ -61,8 +62,8 @@ mod debug {
         }
     }
 
-    fn toggle(kbd: Res<ButtonInput<KeyCode>>, mut show_grid: ResMut<ShowGrid>) {
-        if kbd.just_pressed(KeyCode::F11) {
+    fn toggle(input: ActionInput, mut show_grid: ResMut<ShowGrid>) {
+        if input.just_pressed(GameAction::ToggleDebug) {
             show_grid.0 = !show_grid.0;
         }
     }
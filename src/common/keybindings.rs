@@ -0,0 +1,421 @@
+//! Remappable key bindings shared by every input consumer of the game.
+//!
+//! The `KeyBindings` resource maps each `GameAction` to one keyboard or mouse input and one
+//! gamepad button. Systems that only care about button presses can use the `ActionInput` system
+//! param, while the leafwing based controllers build their `InputMap` from the bindings.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub mod prelude {
+    pub use super::{
+        ActionContext, ActionInput, BindingSlot, GameAction, InputBinding, KeyBinding, KeyBindings,
+    };
+}
+
+/// Every action of the game that can be bound to an input.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, Reflect,
+)]
+pub enum GameAction {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Craft,
+    SpawnItem,
+    OpenChat,
+    CloseChat,
+    LockCursor,
+    Pause,
+    PlayerList,
+    ToggleDebug,
+}
+
+impl GameAction {
+    /// All the actions, in the order they are displayed in the controls menu.
+    pub const ALL: [GameAction; 13] = [
+        GameAction::MoveForward,
+        GameAction::MoveBackward,
+        GameAction::MoveLeft,
+        GameAction::MoveRight,
+        GameAction::Jump,
+        GameAction::Craft,
+        GameAction::SpawnItem,
+        GameAction::OpenChat,
+        GameAction::CloseChat,
+        GameAction::LockCursor,
+        GameAction::Pause,
+        GameAction::PlayerList,
+        GameAction::ToggleDebug,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameAction::MoveForward => "Move Forward",
+            GameAction::MoveBackward => "Move Backward",
+            GameAction::MoveLeft => "Move Left",
+            GameAction::MoveRight => "Move Right",
+            GameAction::Jump => "Jump",
+            GameAction::Craft => "Craft",
+            GameAction::SpawnItem => "Spawn Item",
+            GameAction::OpenChat => "Open Chat",
+            GameAction::CloseChat => "Close Chat",
+            GameAction::LockCursor => "Lock Cursor",
+            GameAction::Pause => "Pause Menu",
+            GameAction::PlayerList => "Player List",
+            GameAction::ToggleDebug => "Toggle Debug",
+        }
+    }
+
+    /// The context in which the action is active. Two actions can share an input only if they
+    /// are never active at the same time.
+    pub fn context(&self) -> ActionContext {
+        match self {
+            GameAction::CloseChat => ActionContext::Chat,
+            GameAction::ToggleDebug => ActionContext::Global,
+            _ => ActionContext::Gameplay,
+        }
+    }
+
+    /// Movement actions are combined into a virtual dpad, which only supports keyboard keys.
+    pub fn allows_mouse(&self) -> bool {
+        !matches!(
+            self,
+            GameAction::MoveForward
+                | GameAction::MoveBackward
+                | GameAction::MoveLeft
+                | GameAction::MoveRight
+        )
+    }
+}
+
+/// The context in which an action is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionContext {
+    /// Active while playing, with the chat closed.
+    Gameplay,
+    /// Active while the chat input is open.
+    Chat,
+    /// Always active.
+    Global,
+}
+
+impl ActionContext {
+    fn overlaps(&self, other: &ActionContext) -> bool {
+        *self == ActionContext::Global || *other == ActionContext::Global || self == other
+    }
+}
+
+/// A keyboard or mouse input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Keyboard(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl Display for InputBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputBinding::Keyboard(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                write!(f, "{name}")
+            }
+            InputBinding::Mouse(button) => write!(f, "Mouse {button:?}"),
+        }
+    }
+}
+
+/// Which of the two inputs of a binding is referenced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingSlot {
+    Input,
+    Gamepad,
+}
+
+/// The inputs bound to a single action.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    pub input: Option<InputBinding>,
+    pub gamepad: Option<GamepadButton>,
+}
+
+impl KeyBinding {
+    fn new(input: Option<InputBinding>, gamepad: Option<GamepadButton>) -> Self {
+        Self { input, gamepad }
+    }
+}
+
+/// The resource that holds the bindings of all the actions. Actions that are missing from the
+/// map use their default binding, so new actions show up in old config files.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    bindings: BTreeMap<GameAction, KeyBinding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: GameAction::ALL
+                .iter()
+                .map(|action| (*action, Self::default_binding(*action)))
+                .collect(),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn default_binding(action: GameAction) -> KeyBinding {
+        use InputBinding::*;
+
+        match action {
+            GameAction::MoveForward => KeyBinding::new(Some(Keyboard(KeyCode::KeyW)), None),
+            GameAction::MoveBackward => KeyBinding::new(Some(Keyboard(KeyCode::KeyS)), None),
+            GameAction::MoveLeft => KeyBinding::new(Some(Keyboard(KeyCode::KeyA)), None),
+            GameAction::MoveRight => KeyBinding::new(Some(Keyboard(KeyCode::KeyD)), None),
+            GameAction::Jump => {
+                KeyBinding::new(Some(Keyboard(KeyCode::Space)), Some(GamepadButton::South))
+            }
+            GameAction::Craft => {
+                KeyBinding::new(Some(Keyboard(KeyCode::KeyF)), Some(GamepadButton::West))
+            }
+            GameAction::SpawnItem => KeyBinding::new(Some(Keyboard(KeyCode::KeyZ)), None),
+            GameAction::OpenChat => KeyBinding::new(Some(Keyboard(KeyCode::Enter)), None),
+            GameAction::CloseChat => KeyBinding::new(Some(Keyboard(KeyCode::Escape)), None),
+            GameAction::LockCursor => KeyBinding::new(Some(Mouse(MouseButton::Right)), None),
+            GameAction::Pause => {
+                KeyBinding::new(Some(Keyboard(KeyCode::Escape)), Some(GamepadButton::Start))
+            }
+            GameAction::PlayerList => {
+                KeyBinding::new(Some(Keyboard(KeyCode::Tab)), Some(GamepadButton::Select))
+            }
+            GameAction::ToggleDebug => KeyBinding::new(Some(Keyboard(KeyCode::F11)), None),
+        }
+    }
+
+    pub fn get(&self, action: GameAction) -> KeyBinding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| Self::default_binding(action))
+    }
+
+    /// Returns the action, other than `action`, that already uses `input` in an overlapping
+    /// context.
+    pub fn input_conflict(&self, action: GameAction, input: InputBinding) -> Option<GameAction> {
+        GameAction::ALL.into_iter().find(|other| {
+            *other != action
+                && other.context().overlaps(&action.context())
+                && self.get(*other).input == Some(input)
+        })
+    }
+
+    /// Returns the action, other than `action`, that already uses `button` in an overlapping
+    /// context.
+    pub fn gamepad_conflict(
+        &self,
+        action: GameAction,
+        button: GamepadButton,
+    ) -> Option<GameAction> {
+        GameAction::ALL.into_iter().find(|other| {
+            *other != action
+                && other.context().overlaps(&action.context())
+                && self.get(*other).gamepad == Some(button)
+        })
+    }
+
+    /// Binds `input` to `action`. If another action was using the same input, it is unbound and
+    /// returned.
+    pub fn bind_input(&mut self, action: GameAction, input: InputBinding) -> Option<GameAction> {
+        let conflict = self.input_conflict(action, input);
+        if let Some(other) = conflict {
+            self.entry(other).input = None;
+        }
+        self.entry(action).input = Some(input);
+        conflict
+    }
+
+    /// Binds `button` to `action`. If another action was using the same button, it is unbound
+    /// and returned.
+    pub fn bind_gamepad(
+        &mut self,
+        action: GameAction,
+        button: GamepadButton,
+    ) -> Option<GameAction> {
+        let conflict = self.gamepad_conflict(action, button);
+        if let Some(other) = conflict {
+            self.entry(other).gamepad = None;
+        }
+        self.entry(action).gamepad = Some(button);
+        conflict
+    }
+
+    pub fn clear(&mut self, action: GameAction, slot: BindingSlot) {
+        let binding = self.entry(action);
+        match slot {
+            BindingSlot::Input => binding.input = None,
+            BindingSlot::Gamepad => binding.gamepad = None,
+        }
+    }
+
+    /// Adds the inputs bound to `binding` as buttons for `action` in the given input map.
+    pub fn insert_buttons<A: Actionlike>(
+        &self,
+        map: &mut InputMap<A>,
+        binding: GameAction,
+        action: A,
+    ) {
+        let KeyBinding { input, gamepad } = self.get(binding);
+        match input {
+            Some(InputBinding::Keyboard(key)) => {
+                map.insert(action.clone(), key);
+            }
+            Some(InputBinding::Mouse(button)) => {
+                map.insert(action.clone(), button);
+            }
+            None => {}
+        }
+        if let Some(button) = gamepad {
+            map.insert(action, button);
+        }
+    }
+
+    /// Builds a virtual dpad out of the four movement actions. Returns `None` if any of them is
+    /// not bound to a keyboard key.
+    pub fn movement_dpad(&self) -> Option<VirtualDPad> {
+        let key = |action| match self.get(action).input {
+            Some(InputBinding::Keyboard(key)) => Some(key),
+            _ => None,
+        };
+
+        Some(VirtualDPad::new(
+            key(GameAction::MoveForward)?,
+            key(GameAction::MoveBackward)?,
+            key(GameAction::MoveLeft)?,
+            key(GameAction::MoveRight)?,
+        ))
+    }
+
+    fn entry(&mut self, action: GameAction) -> &mut KeyBinding {
+        self.bindings
+            .entry(action)
+            .or_insert_with(|| Self::default_binding(action))
+    }
+}
+
+/// System param to check the state of the inputs bound to a `GameAction`.
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    bindings: Res<'w, KeyBindings>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl ActionInput<'_, '_> {
+    pub fn pressed(&self, action: GameAction) -> bool {
+        let KeyBinding { input, gamepad } = self.bindings.get(action);
+
+        let input = match input {
+            Some(InputBinding::Keyboard(key)) => self.keyboard.pressed(key),
+            Some(InputBinding::Mouse(button)) => self.mouse.pressed(button),
+            None => false,
+        };
+        let gamepad = gamepad
+            .is_some_and(|button| self.gamepads.iter().any(|gamepad| gamepad.pressed(button)));
+
+        input || gamepad
+    }
+
+    pub fn just_pressed(&self, action: GameAction) -> bool {
+        let KeyBinding { input, gamepad } = self.bindings.get(action);
+
+        let input = match input {
+            Some(InputBinding::Keyboard(key)) => self.keyboard.just_pressed(key),
+            Some(InputBinding::Mouse(button)) => self.mouse.just_pressed(button),
+            None => false,
+        };
+        let gamepad = gamepad.is_some_and(|button| {
+            self.gamepads
+                .iter()
+                .any(|gamepad| gamepad.just_pressed(button))
+        });
+
+        input || gamepad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_input_unbinds_conflict() {
+        let mut bindings = KeyBindings::default();
+
+        let conflict =
+            bindings.bind_input(GameAction::Craft, InputBinding::Keyboard(KeyCode::Space));
+
+        assert_eq!(conflict, Some(GameAction::Jump));
+        assert_eq!(bindings.get(GameAction::Jump).input, None);
+        assert_eq!(
+            bindings.get(GameAction::Craft).input,
+            Some(InputBinding::Keyboard(KeyCode::Space))
+        );
+    }
+
+    #[test]
+    fn test_bind_input_different_context() {
+        let mut bindings = KeyBindings::default();
+
+        // CloseChat and Pause both use Escape by default
+        assert_eq!(
+            bindings.input_conflict(
+                GameAction::CloseChat,
+                InputBinding::Keyboard(KeyCode::Escape)
+            ),
+            None
+        );
+
+        // ToggleDebug is global, so it conflicts with every context
+        let conflict = bindings.bind_input(
+            GameAction::ToggleDebug,
+            InputBinding::Keyboard(KeyCode::Enter),
+        );
+        assert_eq!(conflict, Some(GameAction::OpenChat));
+    }
+
+    #[test]
+    fn test_bind_gamepad_unbinds_conflict() {
+        let mut bindings = KeyBindings::default();
+
+        let conflict = bindings.bind_gamepad(GameAction::Jump, GamepadButton::West);
+
+        assert_eq!(conflict, Some(GameAction::Craft));
+        assert_eq!(bindings.get(GameAction::Craft).gamepad, None);
+        assert_eq!(
+            bindings.get(GameAction::Jump).gamepad,
+            Some(GamepadButton::West)
+        );
+    }
+
+    #[test]
+    fn test_missing_action_uses_default() {
+        let bindings = KeyBindings {
+            bindings: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            bindings.get(GameAction::Jump),
+            KeyBindings::default_binding(GameAction::Jump)
+        );
+    }
+}
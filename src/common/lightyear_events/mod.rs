@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{any::type_name, collections::HashMap, fmt::Debug};
 
 use bevy::prelude::*;
 use lightyear::{connection::host::HostClient, prelude::*};
@@ -6,7 +6,7 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 pub mod prelude {
     pub use super::AppEventExt;
-    pub use super::{FromClient, ToClient};
+    pub use super::{ChannelBytes, ChannelTraffic, FromClient, ToClient};
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,6 +36,41 @@ pub struct FromClient<E: Event> {
     pub event: E,
 }
 
+/// The payload bytes of the events sent and received by this app, per channel. This is not the
+/// network traffic: the payloads are counted once per event even when it is sent to several
+/// clients, without the headers, the acks and the resends of lightyear, and the replication of
+/// the components is not counted at all. The events are only counted when this resource is
+/// inserted, e.g. by the metrics of the dedicated server, since they are encoded a second time.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ChannelTraffic(HashMap<&'static str, ChannelBytes>);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelBytes {
+    pub sent: u64,
+    pub received: u64,
+}
+
+impl ChannelTraffic {
+    /// The channels sorted by name, with the bytes that went through them.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, ChannelBytes)> + '_ {
+        let mut channels = self.0.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        channels.sort_by_key(|(name, _)| *name);
+        channels.into_iter()
+    }
+
+    fn entry<C: Channel>(&mut self) -> &mut ChannelBytes {
+        let name = type_name::<C>().rsplit("::").next().unwrap_or_default();
+        self.0.entry(name).or_default()
+    }
+}
+
+/// The size of the payload of the event once encoded with bincode.
+fn event_size<E: Serialize>(event: &E) -> u64 {
+    bincode::serde::encode_to_vec(event, bincode::config::standard())
+        .map(|bytes| bytes.len() as u64)
+        .unwrap_or_default()
+}
+
 pub trait AppEventExt {
     /// Register a new app event, with replication from client to server
     fn add_client_event<E: Event + Serialize + DeserializeOwned + Clone + Debug, C: Channel>(
@@ -57,7 +92,7 @@ impl AppEventExt for App {
         self.add_message::<ClientMessageEvent<E>>()
             .add_direction(NetworkDirection::ClientToServer);
         self.add_systems(Update, send_event_to_server::<E, C>);
-        self.add_systems(Update, receive_event_from_client::<E>);
+        self.add_systems(Update, receive_event_from_client::<E, C>);
         self
     }
 
@@ -69,18 +104,22 @@ impl AppEventExt for App {
         self.add_message::<ServerMessageEvent<E>>()
             .add_direction(NetworkDirection::ServerToClient);
         self.add_systems(Update, send_event_to_client::<E, C>);
-        self.add_systems(Update, receive_event_from_server::<E>);
+        self.add_systems(Update, receive_event_from_server::<E, C>);
         self
     }
 }
 
-fn send_event_to_client<E: Event + Clone + Debug, C: Channel>(
+fn send_event_to_client<E: Event + Serialize + Clone + Debug, C: Channel>(
     mut ev_server: EventReader<ToClient<E>>,
     mut sender: ServerMultiMessageSender,
     server: Single<&Server>,
+    mut traffic: Option<ResMut<ChannelTraffic>>,
 ) -> Result {
     for ToClient { target, event } in ev_server.read() {
         trace!("Sending event to client ({:?}): {:?}", target, event);
+        if let Some(traffic) = traffic.as_mut() {
+            traffic.entry::<C>().sent += event_size(event);
+        }
 
         sender.send::<_, C>(
             &ServerMessageEvent {
@@ -94,33 +133,41 @@ fn send_event_to_client<E: Event + Clone + Debug, C: Channel>(
     Ok(())
 }
 
-fn receive_event_from_server<E: Event + Debug>(
+fn receive_event_from_server<E: Event + Serialize + Debug, C: Channel>(
     receiver: Single<
         &mut MessageReceiver<ServerMessageEvent<E>>,
         Or<(With<Client>, With<HostClient>)>,
     >,
     mut ev_client: EventWriter<E>,
+    mut traffic: Option<ResMut<ChannelTraffic>>,
 ) {
     let mut receiver = receiver.into_inner();
 
     for ServerMessageEvent { message } in receiver.receive() {
         trace!("Received event from server: {:?}", message);
+        if let Some(traffic) = traffic.as_mut() {
+            traffic.entry::<C>().received += event_size(&message);
+        }
 
         ev_client.write(message);
     }
 }
 
-fn send_event_to_server<E: Event + Clone + Debug, C: Channel>(
+fn send_event_to_server<E: Event + Serialize + Clone + Debug, C: Channel>(
     mut ev_client: EventReader<E>,
     sender: Single<
         (&RemoteId, &mut MessageSender<ClientMessageEvent<E>>),
         Or<(With<Client>, With<HostClient>)>,
     >,
+    mut traffic: Option<ResMut<ChannelTraffic>>,
 ) -> Result {
     let (RemoteId(_), mut sender) = sender.into_inner();
 
     for event in ev_client.read() {
         trace!("Sending event to server: {:?}", event);
+        if let Some(traffic) = traffic.as_mut() {
+            traffic.entry::<C>().sent += event_size(event);
+        }
 
         sender.send::<C>(ClientMessageEvent {
             message: event.clone(),
@@ -130,18 +177,22 @@ fn send_event_to_server<E: Event + Clone + Debug, C: Channel>(
     Ok(())
 }
 
-fn receive_event_from_client<E: Event + Debug>(
+fn receive_event_from_client<E: Event + Serialize + Debug, C: Channel>(
     mut q_receiver: Query<(
         Entity,
         &RemoteId,
         &mut MessageReceiver<ClientMessageEvent<E>>,
     )>,
     mut ev_server: EventWriter<FromClient<E>>,
+    mut traffic: Option<ResMut<ChannelTraffic>>,
     _: Single<&Server>,
 ) {
     for (entity, RemoteId(peer), mut receiver) in q_receiver.iter_mut() {
         for ClientMessageEvent { message } in receiver.receive() {
             trace!("Received event from client {:?}: {:?}", peer, message);
+            if let Some(traffic) = traffic.as_mut() {
+                traffic.entry::<C>().received += event_size(&message);
+            }
 
             ev_server.write(FromClient {
                 owner: entity,
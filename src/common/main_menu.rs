@@ -1,8 +1,10 @@
 //! A Bevy plugin that implements a main menu with multiple sub-menus, including settings and
 //! multiplayer options.
 //!
-//! This can trigger events to start a new game or connect to a multiplayer server.
-//! This plugin also manages user settings such as display quality, volume, and player name.
+//! This can trigger events to start a new game or connect to a multiplayer server. The same
+//! settings screens are also available in the pause menu while playing.
+//! This plugin also manages user settings such as display quality, volume, player name and key
+//! bindings.
 
 use std::{fmt::Debug, time::SystemTime};
 
@@ -13,9 +15,11 @@ use crate::common::prelude::*;
 
 pub mod prelude {
     pub use super::{
-        ClientMultiplayerClickEvent, ClientPlayClickEvent, DisplayFPSSetting,
-        DisplayLatencySetting, DisplayQualitySetting, MainMenuIcons, MainMenuPlugin, MainMenuRoot,
-        PlayerNameSetting, VolumeSetting,
+        ClientDisconnectClickEvent, ClientMultiplayerClickEvent, ClientPlayClickEvent,
+        ClientResumeClickEvent, DisplayChatTimestampsSetting, DisplayFPSSetting,
+        DisplayLatencySetting, DisplayNetworkStatsSetting, DisplayQualitySetting, MainMenuIcons,
+        MainMenuMessage, MainMenuPlugin, MainMenuRoot, PauseMenuRoot, PlayerNameSetting,
+        VolumeSetting,
     };
 }
 
@@ -32,6 +36,14 @@ pub struct DisplayFPSSetting(pub bool);
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref, DerefMut)]
 pub struct DisplayLatencySetting(pub bool);
 
+/// Whether the status bar shows the network diagnostics, such as packet loss and bandwidth.
+#[derive(Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Deref, DerefMut)]
+pub struct DisplayNetworkStatsSetting(pub bool);
+
+/// Whether the chat history shows the time each message was received.
+#[derive(Resource, Debug, Component, Default, PartialEq, Eq, Clone, Copy, Deref, DerefMut)]
+pub struct DisplayChatTimestampsSetting(pub bool);
+
 #[derive(Resource, Debug, Component, PartialEq, Eq, Clone, Copy, Deref, DerefMut)]
 pub struct VolumeSetting(pub u32);
 
@@ -59,6 +71,7 @@ const BORDER_COLOR_ACTIVE: Color = Color::srgb(0.75, 0.52, 0.99);
 const BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
 
 const TEXT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const TEXT_ERROR_COLOR: Color = Color::srgb(0.9, 0.35, 0.35);
 
 const TEXT_YES: &str = "Yes";
 const TEXT_NO: &str = "No";
@@ -68,6 +81,19 @@ const TEXT_NO: &str = "No";
 #[derive(Debug, Clone, Component)]
 pub struct MainMenuRoot;
 
+/// Marker component for the root UI node of the pause menu
+/// Add this component to an entity to spawn the pause menu and the settings screens
+#[derive(Debug, Clone, Component)]
+pub struct PauseMenuRoot;
+
+/// Filter for the root nodes that contain the settings screens
+type SettingsRootAdded = Or<(Added<MainMenuRoot>, Added<PauseMenuRoot>)>;
+
+/// A message shown once the next time the main menu is opened, e.g. the reason the client was
+/// disconnected from the server
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq, Deref, DerefMut)]
+pub struct MainMenuMessage(pub Option<String>);
+
 #[derive(Resource, Default)]
 pub struct MainMenuIcons {
     pub exit_icon: Handle<Image>,
@@ -85,6 +111,14 @@ pub struct ClientMultiplayerClickEvent {
     pub address: String,
 }
 
+/// Event that is triggered when the "Resume" button is clicked in the pause menu
+#[derive(Debug, Clone, Event)]
+pub struct ClientResumeClickEvent;
+
+/// Event that is triggered when the "Disconnect" button is clicked in the pause menu
+#[derive(Debug, Clone, Event)]
+pub struct ClientDisconnectClickEvent;
+
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 enum MenuState {
     #[default]
@@ -95,6 +129,8 @@ enum MenuState {
     SettingsDisplay,
     SettingsSound,
     SettingsName,
+    SettingsControls,
+    Pause,
 }
 
 #[derive(Component, Clone, Copy, Debug)]
@@ -112,6 +148,30 @@ struct AddressInput;
 #[derive(Component)]
 struct SelectedOption;
 
+/// Button used to rebind one of the inputs of an action.
+#[derive(Component, Clone, Copy, Debug)]
+struct BindingButton {
+    action: GameAction,
+    slot: BindingSlot,
+}
+
+/// Text that displays the input bound to an action.
+#[derive(Component, Clone, Copy, Debug)]
+struct BindingButtonText {
+    action: GameAction,
+    slot: BindingSlot,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+struct ControlsStatusText;
+
+/// The binding that is waiting for an input in the controls menu.
+#[derive(Resource, Debug, Default)]
+struct RebindingState {
+    target: Option<(GameAction, BindingSlot)>,
+    message: String,
+}
+
 #[derive(Component)]
 enum MenuButtonAction {
     NewGame,
@@ -122,8 +182,12 @@ enum MenuButtonAction {
     SettingsDisplay,
     SettingsSound,
     SettingsName,
+    SettingsControls,
+    ResetControls,
     BackToMainMenu,
     BackToSettings,
+    Resume,
+    Disconnect,
     Quit,
 }
 
@@ -133,16 +197,23 @@ impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<ClientPlayClickEvent>();
         app.add_event::<ClientMultiplayerClickEvent>();
+        app.add_event::<ClientResumeClickEvent>();
+        app.add_event::<ClientDisconnectClickEvent>();
 
         app.init_state::<MenuState>();
 
         app.insert_resource(DisplayQualitySetting::Medium);
         app.insert_resource(DisplayFPSSetting(true));
         app.insert_resource(DisplayLatencySetting(true));
+        app.init_resource::<DisplayChatTimestampsSetting>();
+        app.init_resource::<DisplayNetworkStatsSetting>();
         app.insert_resource(VolumeSetting(7));
         app.insert_resource(PlayerNameSetting::default());
+        app.init_resource::<KeyBindings>();
+        app.init_resource::<RebindingState>();
 
         app.insert_resource(MainMenuIcons::default());
+        app.init_resource::<MainMenuMessage>();
         app.add_systems(
             Update,
             (
@@ -153,8 +224,14 @@ impl Plugin for MainMenuPlugin {
                 display_settings_menu_setup,
                 sound_settings_menu_setup,
                 name_settings_menu_setup,
-                handle_visible_menus.run_if(state_changed::<MenuState>),
+                controls_settings_menu_setup,
+                pause_menu_setup,
+                handle_visible_menus
+                    .run_if(state_changed::<MenuState>.or(any_match_filter::<Added<MenuItem>>)),
                 menu_action,
+                pause_menu_back
+                    .before(controls_listen_rebind)
+                    .run_if(any_with_component::<PauseMenuRoot>),
                 handle_button_interact,
                 handle_text_interact.before(TextInputSystem),
             ),
@@ -167,11 +244,27 @@ impl Plugin for MainMenuPlugin {
                 setting_button::<DisplayFPSSetting>.run_if(in_state(MenuState::SettingsDisplay)),
                 setting_button::<DisplayLatencySetting>
                     .run_if(in_state(MenuState::SettingsDisplay)),
+                setting_button::<DisplayChatTimestampsSetting>
+                    .run_if(in_state(MenuState::SettingsDisplay)),
+                setting_button::<DisplayNetworkStatsSetting>
+                    .run_if(in_state(MenuState::SettingsDisplay)),
                 setting_button::<VolumeSetting>.run_if(in_state(MenuState::SettingsSound)),
                 name_settings_menu_update.run_if(in_state(MenuState::SettingsName)),
                 seed_settings_menu_update.run_if(in_state(MenuState::NewGame)),
+                setting_button::<TerrainGenerationPreset>.run_if(in_state(MenuState::NewGame)),
             ),
         );
+        app.add_systems(
+            Update,
+            (
+                controls_binding_button,
+                controls_listen_rebind,
+                controls_settings_menu_update,
+            )
+                .chain()
+                .run_if(in_state(MenuState::SettingsControls)),
+        );
+        app.add_systems(OnExit(MenuState::SettingsControls), controls_cancel_rebind);
     }
 }
 
@@ -248,11 +341,144 @@ fn seed_settings_menu_update(
     }
 }
 
+fn controls_binding_button(
+    interaction_query: Query<(&Interaction, &BindingButton), (Changed<Interaction>, With<Button>)>,
+    mut rebinding: ResMut<RebindingState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            rebinding.target = Some((button.action, button.slot));
+            rebinding.message = format!(
+                "Press a {} for {} (Delete to clear, Left Click to cancel)",
+                match button.slot {
+                    BindingSlot::Input => "key",
+                    BindingSlot::Gamepad => "gamepad button",
+                },
+                button.action.label()
+            );
+            debug!("Rebinding {:?} {:?}", button.action, button.slot);
+        }
+    }
+}
+
+fn controls_listen_rebind(
+    mut rebinding: ResMut<RebindingState>,
+    mut bindings: ResMut<KeyBindings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    // The click that started the rebinding must not be used as the new binding
+    if rebinding.is_changed() {
+        return;
+    }
+    let Some((action, slot)) = rebinding.target else {
+        return;
+    };
+
+    if keyboard.just_pressed(KeyCode::Delete) {
+        bindings.clear(action, slot);
+        rebinding.target = None;
+        rebinding.message = format!("Cleared {}", action.label());
+        return;
+    }
+    if mouse.just_pressed(MouseButton::Left) {
+        rebinding.target = None;
+        rebinding.message.clear();
+        return;
+    }
+
+    let conflict = match slot {
+        BindingSlot::Input => {
+            let input = keyboard
+                .get_just_pressed()
+                .next()
+                .map(|key| InputBinding::Keyboard(*key))
+                .or_else(|| {
+                    mouse
+                        .get_just_pressed()
+                        .next()
+                        .filter(|_| action.allows_mouse())
+                        .map(|button| InputBinding::Mouse(*button))
+                });
+            let Some(input) = input else {
+                return;
+            };
+
+            bindings
+                .bind_input(action, input)
+                .map(|other| format!("{} was unbound from {}", input, other.label()))
+        }
+        BindingSlot::Gamepad => {
+            let Some(button) = gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next().copied())
+            else {
+                return;
+            };
+
+            bindings
+                .bind_gamepad(action, button)
+                .map(|other| format!("{:?} was unbound from {}", button, other.label()))
+        }
+    };
+
+    debug!("Rebound {:?} {:?}", action, slot);
+    rebinding.target = None;
+    rebinding.message = conflict.unwrap_or_default();
+}
+
+fn controls_settings_menu_update(
+    rebinding: Res<RebindingState>,
+    bindings: Res<KeyBindings>,
+    mut q_text: Query<(&BindingButtonText, &mut Text)>,
+    mut q_status: Query<&mut Text, (With<ControlsStatusText>, Without<BindingButtonText>)>,
+) {
+    if !rebinding.is_changed() && !bindings.is_changed() {
+        return;
+    }
+
+    for (button, mut text) in &mut q_text {
+        text.0 = binding_text(&bindings, &rebinding, button.action, button.slot);
+    }
+    for mut text in &mut q_status {
+        text.0 = rebinding.message.clone();
+    }
+}
+
+fn controls_cancel_rebind(mut rebinding: ResMut<RebindingState>) {
+    rebinding.target = None;
+    rebinding.message.clear();
+}
+
+fn binding_text(
+    bindings: &KeyBindings,
+    rebinding: &RebindingState,
+    action: GameAction,
+    slot: BindingSlot,
+) -> String {
+    if rebinding.target == Some((action, slot)) {
+        return "...".to_string();
+    }
+
+    let binding = bindings.get(action);
+    let text = match slot {
+        BindingSlot::Input => binding.input.map(|input| input.to_string()),
+        BindingSlot::Gamepad => binding.gamepad.map(|button| format!("{button:?}")),
+    };
+    text.unwrap_or_else(|| "-".to_string())
+}
+
 fn main_menu_setup(
     mut commands: Commands,
     assets: Res<MainMenuIcons>,
+    mut message: ResMut<MainMenuMessage>,
+    mut menu_state: ResMut<NextState<MenuState>>,
     root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
 ) {
+    // The menu can be left in any state, e.g. after disconnecting from the pause menu
+    menu_state.set(MenuState::Main);
+
     // Common style for all buttons on the screen
     let button_node = Node {
         width: Val::Px(300.0),
@@ -311,6 +537,23 @@ fn main_menu_setup(
                             },
                         ));
 
+                        // Display the message left for the menu, it is only shown once
+                        if let Some(message) = message.take() {
+                            parent.spawn((
+                                Name::new("MainMenuMessage"),
+                                Text::new(message),
+                                TextFont {
+                                    font_size: 25.0,
+                                    ..default()
+                                },
+                                TextColor(TEXT_ERROR_COLOR),
+                                Node {
+                                    margin: UiRect::bottom(Val::Px(20.0)),
+                                    ..default()
+                                },
+                            ));
+                        }
+
                         // Display three buttons for each action available from the main menu:
                         // - new game
                         // - multiplayer
@@ -395,6 +638,7 @@ fn main_menu_setup(
 fn new_game_menu_setup(
     mut commands: Commands,
     mut world_seed: ResMut<TerrainGenerationSeed>,
+    mut world_preset: ResMut<TerrainGenerationPreset>,
     root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
 ) {
     **world_seed = SystemTime::now()
@@ -403,6 +647,12 @@ fn new_game_menu_setup(
         .as_secs() as u32;
     debug!("World seed changed to {}", **world_seed);
 
+    // The preset of the last joined server might not be one of the builtin presets
+    let presets = TerrainGenerationPreset::builtin();
+    if !presets.contains(&*world_preset) {
+        *world_preset = TerrainGenerationPreset::default();
+    }
+
     let button_node = Node {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -468,6 +718,34 @@ fn new_game_menu_setup(
                             },
                         ));
 
+                        parent.spawn((Text::new("World Preset"), button_text_style.clone()));
+                        parent
+                            .spawn((Node {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },))
+                            .with_children(|parent| {
+                                // Display a button for each builtin preset
+                                for preset in presets {
+                                    let selected = *world_preset == preset;
+                                    let mut entity = parent.spawn((
+                                        Button,
+                                        button_node.clone(),
+                                        BackgroundColor(NORMAL_BUTTON),
+                                    ));
+                                    entity.with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(preset.name.clone()),
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                                    entity.insert(preset);
+                                    if selected {
+                                        entity.insert(SelectedOption);
+                                    }
+                                }
+                            });
+
                         // Display the back and play buttons
                         parent
                             .spawn((
@@ -589,10 +867,7 @@ fn multiplayer_menu_setup(
     });
 }
 
-fn settings_menu_setup(
-    mut commands: Commands,
-    root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
-) {
+fn settings_menu_setup(mut commands: Commands, root: Single<Entity, SettingsRootAdded>) {
     let button_node = Node {
         width: Val::Px(200.0),
         height: Val::Px(65.0),
@@ -637,6 +912,7 @@ fn settings_menu_setup(
                             (MenuButtonAction::SettingsDisplay, "Display"),
                             (MenuButtonAction::SettingsSound, "Sound"),
                             (MenuButtonAction::SettingsName, "Name"),
+                            (MenuButtonAction::SettingsControls, "Controls"),
                             (MenuButtonAction::BackToMainMenu, "Back"),
                         ] {
                             parent
@@ -660,7 +936,9 @@ fn display_settings_menu_setup(
     display_quality: Res<DisplayQualitySetting>,
     display_fps: Res<DisplayFPSSetting>,
     display_latency: Res<DisplayLatencySetting>,
-    root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
+    display_timestamps: Res<DisplayChatTimestampsSetting>,
+    display_network: Res<DisplayNetworkStatsSetting>,
+    root: Single<Entity, SettingsRootAdded>,
 ) {
     let button_node = Node {
         width: Val::Px(200.0),
@@ -806,6 +1084,72 @@ fn display_settings_menu_setup(
                                     }
                                 }
                             });
+                        parent
+                            .spawn((Node {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new("Chat Timestamps"),
+                                    button_text_style.clone(),
+                                ));
+
+                                // Display a button for each possible value
+                                for timestamps_setting in [(true, TEXT_YES), (false, TEXT_NO)] {
+                                    let mut entity = parent.spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(150.0),
+                                            height: Val::Px(65.0),
+                                            ..button_node.clone()
+                                        },
+                                        BackgroundColor(NORMAL_BUTTON),
+                                        DisplayChatTimestampsSetting(timestamps_setting.0),
+                                    ));
+                                    entity.with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(timestamps_setting.1),
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                                    if **display_timestamps == timestamps_setting.0 {
+                                        entity.insert(SelectedOption);
+                                    }
+                                }
+                            });
+                        parent
+                            .spawn((Node {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn((Text::new("Network Stats"), button_text_style.clone()));
+
+                                // Display a button for each possible value
+                                for network_setting in [(true, TEXT_YES), (false, TEXT_NO)] {
+                                    let mut entity = parent.spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(150.0),
+                                            height: Val::Px(65.0),
+                                            ..button_node.clone()
+                                        },
+                                        BackgroundColor(NORMAL_BUTTON),
+                                        DisplayNetworkStatsSetting(network_setting.0),
+                                    ));
+                                    entity.with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(network_setting.1),
+                                            button_text_style.clone(),
+                                        ));
+                                    });
+                                    if **display_network == network_setting.0 {
+                                        entity.insert(SelectedOption);
+                                    }
+                                }
+                            });
                         // Display the back button to return to the settings screen
                         parent
                             .spawn((
@@ -825,7 +1169,7 @@ fn display_settings_menu_setup(
 fn sound_settings_menu_setup(
     mut commands: Commands,
     volume: Res<VolumeSetting>,
-    root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
+    root: Single<Entity, SettingsRootAdded>,
 ) {
     let button_node = Node {
         width: Val::Px(200.0),
@@ -905,7 +1249,7 @@ fn sound_settings_menu_setup(
 fn name_settings_menu_setup(
     mut commands: Commands,
     player_name: Res<PlayerNameSetting>,
-    root: Single<Entity, (With<MainMenuRoot>, Added<MainMenuRoot>)>,
+    root: Single<Entity, SettingsRootAdded>,
 ) {
     let button_node = Node {
         width: Val::Px(200.0),
@@ -984,6 +1328,232 @@ fn name_settings_menu_setup(
     });
 }
 
+fn controls_settings_menu_setup(
+    mut commands: Commands,
+    bindings: Res<KeyBindings>,
+    rebinding: Res<RebindingState>,
+    root: Single<Entity, SettingsRootAdded>,
+) {
+    let button_node = Node {
+        width: Val::Px(200.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let binding_node = Node {
+        width: Val::Px(200.0),
+        height: Val::Px(40.0),
+        margin: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = (
+        TextFont {
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+    let binding_text_style = (
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+
+    commands.entity(root.entity()).with_children(|parent| {
+        parent
+            .spawn((
+                Name::new("ControlsSettingsMenu"),
+                MenuItem(MenuState::SettingsControls),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    display: Display::None,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },))
+                    .with_children(|parent| {
+                        // Display a row for each action with a button for the keyboard or mouse
+                        // input and a button for the gamepad input
+                        for action in GameAction::ALL {
+                            parent
+                                .spawn((Node {
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        Text::new(action.label()),
+                                        binding_text_style.clone(),
+                                        Node {
+                                            width: Val::Px(250.0),
+                                            ..default()
+                                        },
+                                    ));
+                                    for slot in [BindingSlot::Input, BindingSlot::Gamepad] {
+                                        parent
+                                            .spawn((
+                                                Button,
+                                                binding_node.clone(),
+                                                BackgroundColor(NORMAL_BUTTON),
+                                                BindingButton { action, slot },
+                                            ))
+                                            .with_child((
+                                                Text::new(binding_text(
+                                                    &bindings, &rebinding, action, slot,
+                                                )),
+                                                binding_text_style.clone(),
+                                                BindingButtonText { action, slot },
+                                            ));
+                                    }
+                                });
+                        }
+
+                        parent.spawn((
+                            Text::default(),
+                            binding_text_style.clone(),
+                            ControlsStatusText,
+                            Node {
+                                margin: UiRect::all(Val::Px(10.0)),
+                                ..default()
+                            },
+                        ));
+
+                        parent
+                            .spawn((Node {
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },))
+                            .with_children(|parent| {
+                                for (action, text) in [
+                                    (MenuButtonAction::ResetControls, "Reset"),
+                                    (MenuButtonAction::BackToSettings, "Back"),
+                                ] {
+                                    parent
+                                        .spawn((
+                                            Button,
+                                            button_node.clone(),
+                                            BackgroundColor(NORMAL_BUTTON),
+                                            action,
+                                        ))
+                                        .with_child((Text::new(text), button_text_style.clone()));
+                                }
+                            });
+                    });
+            });
+    });
+}
+
+fn pause_menu_setup(
+    mut commands: Commands,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    root: Single<Entity, (With<PauseMenuRoot>, Added<PauseMenuRoot>)>,
+) {
+    menu_state.set(MenuState::Pause);
+
+    let button_node = Node {
+        width: Val::Px(300.0),
+        height: Val::Px(65.0),
+        margin: UiRect::all(Val::Px(20.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = (
+        TextFont {
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR),
+    );
+
+    commands.entity(root.entity()).with_children(|parent| {
+        parent
+            .spawn((
+                Name::new("PauseMenu"),
+                MenuItem(MenuState::Pause),
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    display: Display::None,
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((Text::new("Paused"), button_text_style.clone()))
+                    .insert(Node {
+                        margin: UiRect::all(Val::Px(50.0)),
+                        ..default()
+                    });
+
+                parent
+                    .spawn((Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },))
+                    .with_children(|parent| {
+                        for (action, text) in [
+                            (MenuButtonAction::Resume, "Resume"),
+                            (MenuButtonAction::Settings, "Settings"),
+                            (MenuButtonAction::Disconnect, "Disconnect"),
+                            (MenuButtonAction::Quit, "Quit"),
+                        ] {
+                            parent
+                                .spawn((
+                                    Button,
+                                    button_node.clone(),
+                                    BackgroundColor(NORMAL_BUTTON),
+                                    action,
+                                ))
+                                .with_child((Text::new(text), button_text_style.clone()));
+                        }
+                    });
+            });
+    });
+}
+
+/// Pressing the pause input in the pause menu goes back one screen, or resumes the game from the
+/// root of the pause menu.
+fn pause_menu_back(
+    input: ActionInput,
+    rebinding: Res<RebindingState>,
+    current_state: Res<State<MenuState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut resume_ev: EventWriter<ClientResumeClickEvent>,
+) {
+    if rebinding.target.is_some() || !input.just_pressed(GameAction::Pause) {
+        return;
+    }
+
+    match **current_state {
+        MenuState::Pause => {
+            resume_ev.write(ClientResumeClickEvent);
+        }
+        MenuState::Settings => menu_state.set(MenuState::Pause),
+        _ => menu_state.set(MenuState::Settings),
+    }
+}
+
 fn handle_visible_menus(
     menu_state: Res<State<MenuState>>,
     mut query: Query<(&MenuItem, &mut Node)>,
@@ -1007,6 +1577,11 @@ fn menu_action(
     q_address: Query<&TextInputValue, With<AddressInput>>,
     mut play_ev: EventWriter<ClientPlayClickEvent>,
     mut connect_ev: EventWriter<ClientMultiplayerClickEvent>,
+    mut resume_ev: EventWriter<ClientResumeClickEvent>,
+    mut disconnect_ev: EventWriter<ClientDisconnectClickEvent>,
+    mut bindings: ResMut<KeyBindings>,
+    mut rebinding: ResMut<RebindingState>,
+    q_pause: Query<(), With<PauseMenuRoot>>,
 ) {
     for (interaction, menu_button_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -1024,7 +1599,13 @@ fn menu_action(
                 MenuButtonAction::SettingsSound => {
                     menu_state.set(MenuState::SettingsSound);
                 }
-                MenuButtonAction::BackToMainMenu => menu_state.set(MenuState::Main),
+                MenuButtonAction::BackToMainMenu => {
+                    if q_pause.is_empty() {
+                        menu_state.set(MenuState::Main);
+                    } else {
+                        menu_state.set(MenuState::Pause);
+                    }
+                }
                 MenuButtonAction::BackToSettings => {
                     menu_state.set(MenuState::Settings);
                 }
@@ -1038,6 +1619,22 @@ fn menu_action(
                     menu_state.set(MenuState::Main);
                 }
                 MenuButtonAction::SettingsName => menu_state.set(MenuState::SettingsName),
+                MenuButtonAction::SettingsControls => {
+                    menu_state.set(MenuState::SettingsControls);
+                }
+                MenuButtonAction::ResetControls => {
+                    info!("Resetting key bindings to defaults");
+                    *bindings = KeyBindings::default();
+                    rebinding.target = None;
+                    rebinding.message = "Controls reset to defaults".to_string();
+                }
+                MenuButtonAction::Resume => {
+                    resume_ev.write(ClientResumeClickEvent);
+                }
+                MenuButtonAction::Disconnect => {
+                    info!("Disconnecting from the game from pause menu");
+                    disconnect_ev.write(ClientDisconnectClickEvent);
+                }
                 MenuButtonAction::Quit => {
                     app_exit_events.write(AppExit::Success);
                 }
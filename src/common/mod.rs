@@ -5,11 +5,13 @@ mod chat;
 mod chunk_map;
 mod features;
 mod main_menu;
+mod player_list;
 mod terrain;
 mod tilemap;
 mod inventory;
 mod status;
 mod lightyear_events;
+mod keybindings;
 
 pub mod prelude {
     pub use super::assets::prelude::*;
@@ -19,9 +21,11 @@ pub mod prelude {
     pub use super::chunk_map::prelude::*;
     pub use super::features::prelude::*;
     pub use super::main_menu::prelude::*;
+    pub use super::player_list::prelude::*;
     pub use super::terrain::prelude::*;
     pub use super::tilemap::prelude::*;
     pub use super::inventory::prelude::*;
     pub use super::status::prelude::*;
     pub use super::lightyear_events::prelude::*;
+    pub use super::keybindings::prelude::*;
 }
@@ -0,0 +1,181 @@
+//! The Player List plugin provides an overlay UI that lists the players in the game.
+//! Add a `PlayerListItem` component for each player and the overlay updates automatically,
+//! in the same way as the status bar.
+
+use bevy::prelude::*;
+
+use crate::common::prelude::*;
+
+pub mod prelude {
+    pub use super::{PlayerListItem, PlayerListPlugin, PlayerListRoot};
+}
+
+const HOST_COLOR: Color = Color::srgb(1.0, 0.85, 0.35);
+const OPERATOR_COLOR: Color = Color::srgb(0.45, 0.75, 1.0);
+
+/// The PlayerListRoot component is a marker component that indicates the root node of the
+/// player list UI.
+#[derive(Component)]
+pub struct PlayerListRoot;
+
+/// The text of the list that shows how long a player has been connected, it is updated
+/// separately from the rest of the list.
+#[derive(Component)]
+struct PlayerListItemTime {
+    joined_at: i64,
+}
+
+#[derive(Component, Debug, Clone, PartialEq, Reflect)]
+pub struct PlayerListItem {
+    pub username: String,
+    pub rtt_ms: u32,
+    /// The time the player joined at, in seconds since the unix epoch.
+    pub joined_at: i64,
+    pub host: bool,
+    pub operator: bool,
+}
+
+pub struct PlayerListPlugin;
+
+impl Plugin for PlayerListPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PlayerListItem>();
+
+        app.add_systems(
+            Update,
+            update_player_list.run_if(
+                any_match_filter::<Changed<PlayerListItem>>
+                    .or(any_match_filter::<Added<PlayerListRoot>>)
+                    .or(any_component_removed::<PlayerListItem>),
+            ),
+        );
+        app.add_systems(Update, update_player_list_times.after(update_player_list));
+    }
+}
+
+/// Format a duration in seconds as a short human readable text.
+fn format_duration(secs: u32) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// The time since a player joined, in seconds since the unix epoch.
+fn connected_secs(joined_at: i64) -> u32 {
+    (chrono::Utc::now().timestamp() - joined_at).clamp(0, u32::MAX as i64) as u32
+}
+
+fn update_player_list_times(mut q_times: Query<(&PlayerListItemTime, &mut Text)>) {
+    for (time, mut text) in &mut q_times {
+        let duration = format_duration(connected_secs(time.joined_at));
+        if text.0 != duration {
+            text.0 = duration;
+        }
+    }
+}
+
+fn update_player_list(
+    mut commands: Commands,
+    root: Single<Entity, With<PlayerListRoot>>,
+    q_items: Query<&PlayerListItem>,
+) {
+    let mut items = q_items.iter().collect::<Vec<_>>();
+    items.sort_by_key(|item| item.username.to_lowercase());
+
+    let text_font = TextFont {
+        font_size: 14.0,
+        ..default()
+    };
+
+    commands
+        .entity(root.entity())
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            parent.spawn((
+                Name::new("PlayerListTitle"),
+                Text::new(format!("Players ({})", items.len())),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                Node {
+                    margin: UiRect::bottom(Val::Px(4.0)),
+                    ..default()
+                },
+            ));
+
+            for item in items {
+                parent
+                    .spawn((
+                        Name::new(format!("PlayerListItem: {}", item.username)),
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(24.0),
+                            margin: UiRect::all(Val::Px(4.0)),
+                            align_items: AlignItems::Center,
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Name::new("PlayerListItemName"),
+                            Text::new(item.username.clone()),
+                            text_font.clone(),
+                            Node {
+                                flex_grow: 1.0,
+                                ..default()
+                            },
+                        ));
+                        if item.host {
+                            parent.spawn((
+                                Name::new("PlayerListItemHost"),
+                                Text::new("host"),
+                                text_font.clone(),
+                                TextColor(HOST_COLOR),
+                            ));
+                        } else if item.operator {
+                            parent.spawn((
+                                Name::new("PlayerListItemOperator"),
+                                Text::new("op"),
+                                text_font.clone(),
+                                TextColor(OPERATOR_COLOR),
+                            ));
+                        }
+                        parent.spawn((
+                            Name::new("PlayerListItemTime"),
+                            PlayerListItemTime {
+                                joined_at: item.joined_at,
+                            },
+                            Text::new(format_duration(connected_secs(item.joined_at))),
+                            text_font.clone(),
+                        ));
+                        parent.spawn((
+                            Name::new("PlayerListItemPing"),
+                            Text::new(format!("{} ms", item.rtt_ms)),
+                            text_font.clone(),
+                            TextColor(mapping_color(&LATENCY_MAPPING, Some(item.rtt_ms as f64))),
+                        ));
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(59), "59s");
+        assert_eq!(format_duration(61), "1m 01s");
+        assert_eq!(format_duration(3600 + 5 * 60 + 30), "1h 05m");
+    }
+}
@@ -1,11 +1,40 @@
 //! The Status plugin provides a simple status bar UI for displaying different metrics.
 //! The idea is it should be easy to add what metrics to display via generic components
 //! Then you update the components and the ststaus bar updates automatically.
+//!
+//! Items can be spawned and despawned at any time, the status bar adds and removes their UI.
+
+use std::fmt::Display;
 
 use bevy::prelude::*;
 
 pub mod prelude {
-    pub use super::{StatusBarItem, StatusBarPlugin, StatusBarRoot};
+    pub use super::{
+        mapping_color, StatusBarItem, StatusBarPlugin, StatusBarRoot, StatusValue, LATENCY_MAPPING,
+    };
+}
+
+/// The round trip time thresholds in milliseconds, used for the latency of the status bar and the
+/// ping of the player list.
+pub const LATENCY_MAPPING: [(Option<f64>, Color); 3] = [
+    (Some(100.0), Color::srgb(0.0, 1.0, 0.0)),
+    (Some(200.0), Color::srgb(1.0, 1.0, 0.0)),
+    (None, Color::srgb(1.0, 0.0, 0.0)),
+];
+
+/// The color of a value for the thresholds of a mapping, in increasing order. The first
+/// threshold the value is less than or equal to is used, `None` matches any value, and a missing
+/// value uses the color of the `None` threshold.
+pub fn mapping_color(mapping: &[(Option<f64>, Color)], value: Option<f64>) -> Color {
+    let mut color = Color::WHITE;
+    for (threshold, map_color) in mapping {
+        match (threshold, value) {
+            (Some(threshold), Some(value)) if value <= *threshold => return *map_color,
+            (Some(_), _) => {}
+            (None, _) => color = *map_color,
+        }
+    }
+    color
 }
 
 /// The StatusBarRoot component is a marker component that indicates the root node of the status
@@ -13,12 +42,66 @@ pub mod prelude {
 #[derive(Component)]
 pub struct StatusBarRoot;
 
+/// The value displayed by a status bar item.
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub enum StatusValue {
+    Int(i64),
+    /// A float displayed with a fixed number of decimals.
+    Float {
+        value: f64,
+        precision: usize,
+    },
+    Text(String),
+}
+
+impl StatusValue {
+    pub fn float(value: f64, precision: usize) -> Self {
+        StatusValue::Float { value, precision }
+    }
+
+    /// The numeric value used for the thresholds, `None` for text values.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            StatusValue::Int(value) => Some(*value as f64),
+            StatusValue::Float { value, .. } => Some(*value),
+            StatusValue::Text(_) => None,
+        }
+    }
+}
+
+impl Default for StatusValue {
+    fn default() -> Self {
+        StatusValue::Int(0)
+    }
+}
+
+impl Display for StatusValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusValue::Int(value) => write!(f, "{value}"),
+            StatusValue::Float { value, precision } => write!(f, "{value:.precision$}"),
+            StatusValue::Text(value) => write!(f, "{value}"),
+        }
+    }
+}
+
 #[derive(Component, Debug, Clone, PartialEq, Reflect)]
 pub struct StatusBarItem {
     pub icon: Option<Handle<Image>>,
-    pub value: u32,
+    pub value: StatusValue,
+    /// The label displayed after the value, usually the unit of the value.
     pub label: String,
-    pub mapping: Vec<(Option<u32>, Color)>,
+    /// The color of the value for each threshold, in increasing order. The first threshold the
+    /// value is less than or equal to is used, `None` matches any value.
+    pub mapping: Vec<(Option<f64>, Color)>,
+}
+
+impl StatusBarItem {
+    /// The color of the value according to the thresholds. Text values use the color of the
+    /// `None` threshold.
+    pub fn color(&self) -> Color {
+        mapping_color(&self.mapping, self.value.as_f64())
+    }
 }
 
 pub struct StatusBarPlugin;
@@ -27,96 +110,165 @@ impl Plugin for StatusBarPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<StatusBarItem>();
 
-        app.add_systems(Update, setup_status_bar);
-        app.add_systems(Update, update_status_bar);
+        app.add_observer(on_status_bar_item_removed);
+        app.add_systems(Update, (setup_status_bar, update_status_bar).chain());
     }
 }
 
+/// The row of a status bar item in the status bar UI.
+#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut)]
+struct StatusBarItemRow(Entity);
+
+#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut)]
+struct StatusBarItemValue(Entity);
+
+#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut)]
+struct StatusBarItemLabel(Entity);
+
 fn setup_status_bar(
     mut commands: Commands,
-    root: Single<Entity, (With<StatusBarRoot>, Added<StatusBarRoot>)>,
-    q_items: Query<(Entity, &StatusBarItem)>,
+    root: Single<Entity, With<StatusBarRoot>>,
+    q_items: Query<(Entity, &StatusBarItem), Without<StatusBarItemRow>>,
 ) {
-    debug!("Setting up status bar UI...");
-
-    commands.entity(root.entity()).with_children(|parent| {
-        for (entity, item) in &q_items {
-            parent
-                .spawn((
-                    Name::new(format!("StatusBarItem: {}", item.label)),
-                    Node {
-                        width: Val::Auto,
-                        height: Val::Px(24.0),
-                        margin: UiRect::all(Val::Px(4.0)),
-                        align_items: AlignItems::Center,
-                        justify_content: JustifyContent::Center,
-                        flex_direction: FlexDirection::Row,
-                        column_gap: Val::Px(4.0),
-                        ..default()
-                    },
-                ))
-                .with_children(|parent| {
-                    if let Some(icon) = &item.icon {
-                        parent.spawn((
-                            Name::new("StatusBarItemIcon"),
-                            ImageNode {
-                                image: icon.clone(),
-                                ..default()
-                            },
-                            Node {
-                                width: Val::Px(16.0),
-                                height: Val::Px(16.0),
-                                ..default()
-                            },
-                        ));
-                    }
+    for (entity, item) in &q_items {
+        debug!("Adding status bar item {}", item.label);
+
+        let row = commands
+            .spawn((
+                Name::new(format!("StatusBarItem: {}", item.label)),
+                Node {
+                    width: Val::Auto,
+                    height: Val::Px(24.0),
+                    margin: UiRect::all(Val::Px(4.0)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                },
+            ))
+            .with_children(|parent| {
+                if let Some(icon) = &item.icon {
                     parent.spawn((
-                        Name::new("StatusBarItemValue"),
-                        StatusBarItemValue(entity),
-                        Text::new(item.value.to_string().clone()),
-                        TextFont {
-                            font_size: 14.0,
+                        Name::new("StatusBarItemIcon"),
+                        ImageNode {
+                            image: icon.clone(),
                             ..default()
                         },
-                        TextColor(Color::WHITE),
-                    ));
-                    parent.spawn((
-                        Name::new("StatusBarItemLabel"),
-                        Text::new(item.label.clone()),
-                        TextFont {
-                            font_size: 14.0,
+                        Node {
+                            width: Val::Px(16.0),
+                            height: Val::Px(16.0),
                             ..default()
                         },
                     ));
-                });
-        }
-    });
+                }
+                parent.spawn((
+                    Name::new("StatusBarItemValue"),
+                    StatusBarItemValue(entity),
+                    Text::new(item.value.to_string()),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(item.color()),
+                ));
+                parent.spawn((
+                    Name::new("StatusBarItemLabel"),
+                    StatusBarItemLabel(entity),
+                    Text::new(item.label.clone()),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                ));
+            })
+            .id();
+
+        commands.entity(root.entity()).add_child(row);
+        commands.entity(entity).insert(StatusBarItemRow(row));
+    }
 }
 
-#[derive(Component, Debug, Clone, PartialEq, Deref, DerefMut)]
-struct StatusBarItemValue(Entity);
+fn on_status_bar_item_removed(
+    trigger: Trigger<OnRemove, StatusBarItem>,
+    mut commands: Commands,
+    q_rows: Query<&StatusBarItemRow>,
+) {
+    let entity = trigger.target();
+    if let Ok(StatusBarItemRow(row)) = q_rows.get(entity) {
+        debug!("Removing status bar item {:?}", entity);
+
+        commands.entity(*row).try_despawn();
+        commands.entity(entity).try_remove::<StatusBarItemRow>();
+    }
+}
 
 fn update_status_bar(
-    q_items: Query<&StatusBarItem>,
-    mut q_text: Query<(&mut Text, &mut TextColor, &StatusBarItemValue)>,
+    q_items: Query<&StatusBarItem, Changed<StatusBarItem>>,
+    mut q_values: Query<(&mut Text, &mut TextColor, &StatusBarItemValue)>,
+    mut q_labels: Query<(&mut Text, &StatusBarItemLabel), Without<StatusBarItemValue>>,
 ) {
-    for (mut text, mut color, StatusBarItemValue(entity)) in &mut q_text {
+    for (mut text, mut color, StatusBarItemValue(entity)) in &mut q_values {
         if let Ok(item) = q_items.get(*entity) {
             **text = item.value.to_string();
+            **color = item.color();
+        }
+    }
 
-            // Update color based on mapping
-            let mut new_color = Color::WHITE;
-            for (threshold, map_color) in &item.mapping {
-                if let Some(thresh) = threshold {
-                    if item.value <= *thresh {
-                        new_color = *map_color;
-                        break;
-                    }
-                } else {
-                    new_color = *map_color;
-                }
+    for (mut text, StatusBarItemLabel(entity)) in &mut q_labels {
+        if let Ok(item) = q_items.get(*entity) {
+            if **text != item.label {
+                **text = item.label.clone();
             }
-            **color = new_color;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(value: StatusValue) -> StatusBarItem {
+        StatusBarItem {
+            icon: None,
+            value,
+            label: String::new(),
+            mapping: vec![
+                (Some(30.0), Color::srgb(1.0, 0.0, 0.0)),
+                (Some(60.0), Color::srgb(1.0, 1.0, 0.0)),
+                (None, Color::srgb(0.0, 1.0, 0.0)),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_status_value_format() {
+        assert_eq!(StatusValue::Int(-3).to_string(), "-3");
+        assert_eq!(StatusValue::float(1.23456, 2).to_string(), "1.23");
+        assert_eq!(StatusValue::float(2.0, 0).to_string(), "2");
+        assert_eq!(
+            StatusValue::Text("Plains".to_string()).to_string(),
+            "Plains"
+        );
+    }
+
+    #[test]
+    fn test_status_item_thresholds() {
+        assert_eq!(
+            item(StatusValue::Int(30)).color(),
+            Color::srgb(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            item(StatusValue::float(45.5, 1)).color(),
+            Color::srgb(1.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            item(StatusValue::Int(120)).color(),
+            Color::srgb(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            item(StatusValue::Text("n/a".to_string())).color(),
+            Color::srgb(0.0, 1.0, 0.0)
+        );
+    }
+}
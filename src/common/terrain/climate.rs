@@ -0,0 +1,138 @@
+//! Temperature and moisture of the planet, used to pick the biome of the tiles.
+
+use std::rc::Rc;
+
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use super::{components::*, preset::*};
+use crate::common::prelude::*;
+
+/// Planet seed. Change this to generate a different planet.
+const CURRENT_SEED: u32 = 0;
+
+/// Scale of the planet. Change this to zoom in or out.
+const ZOOM_SCALE: f64 = 0.001;
+
+/// Frequency of the temperature zones. Higher frequency produces smaller,
+/// more numerous zones. This value is measured in radians.
+const TEMPERATURE_FREQUENCY: f64 = 2.5;
+
+/// Lacunarity of the temperature zones. For the best results, this value
+/// should be random, but close to 2.0.
+const TEMPERATURE_LACUNARITY: f64 = 2.1953125;
+
+/// Frequency of the moisture zones. Higher frequency produces smaller, more
+/// numerous zones. This value is measured in radians.
+const MOISTURE_FREQUENCY: f64 = 3.5;
+
+/// Lacunarity of the moisture zones. For the best results, this value
+/// should be random, but close to 2.0.
+const MOISTURE_LACUNARITY: f64 = 2.2265625;
+
+/// Stretches the noise so that the extreme climates are not too rare. The
+/// noise is mostly close to zero, so a value of 1.0 would produce almost only
+/// temperate climates.
+const CLIMATE_CONTRAST: f64 = 2.0;
+
+/// How much colder the highest mountains are than the sea level.
+const ELEVATION_COOLING: f64 = 0.5;
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct PlanetClimate {
+    seed: u32,
+    zoom_scale: f64,
+    temperature_frequency: f64,
+    temperature_lacunarity: f64,
+    moisture_frequency: f64,
+    moisture_lacunarity: f64,
+    contrast: f64,
+    elevation_cooling: f64,
+}
+
+impl Default for PlanetClimate {
+    fn default() -> Self {
+        PlanetClimate {
+            seed: CURRENT_SEED,
+            zoom_scale: ZOOM_SCALE,
+            temperature_frequency: TEMPERATURE_FREQUENCY,
+            temperature_lacunarity: TEMPERATURE_LACUNARITY,
+            moisture_frequency: MOISTURE_FREQUENCY,
+            moisture_lacunarity: MOISTURE_LACUNARITY,
+            contrast: CLIMATE_CONTRAST,
+            elevation_cooling: ELEVATION_COOLING,
+        }
+    }
+}
+
+impl PlanetClimate {
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_preset(mut self, preset: &TerrainGenerationPreset) -> Self {
+        self.zoom_scale = preset.zoom_scale;
+        self
+    }
+
+    /// The noise functions of the climate, built once per thread and reused by the tasks of
+    /// every chunk until the climate changes.
+    fn noise(&self) -> Rc<PlanetClimateNoise> {
+        PLANET_CLIMATE_NOISE.with(|cache| {
+            cache.get_or_build(*self, |climate| PlanetClimateNoise {
+                temperature: Fbm::<Perlin>::new(climate.seed + 200)
+                    .set_frequency(climate.temperature_frequency)
+                    .set_persistence(0.5)
+                    .set_lacunarity(climate.temperature_lacunarity)
+                    .set_octaves(6),
+                moisture: Fbm::<Perlin>::new(climate.seed + 210)
+                    .set_frequency(climate.moisture_frequency)
+                    .set_persistence(0.5)
+                    .set_lacunarity(climate.moisture_lacunarity)
+                    .set_octaves(6),
+            })
+        })
+    }
+
+    /// Map the output of the noise to the 0..1 range of the climate.
+    fn normalize(&self, noise: f64) -> f64 {
+        ((noise * self.contrast + 1.0) * 0.5).clamp(0.0, 1.0)
+    }
+}
+
+thread_local! {
+    static PLANET_CLIMATE_NOISE: ChunkMapCache<PlanetClimate, PlanetClimateNoise> =
+        const { ChunkMapCache::new() };
+}
+
+struct PlanetClimateNoise {
+    temperature: Fbm<Perlin>,
+    moisture: Fbm<Perlin>,
+}
+
+impl ChunkMapFunction<(TileCoord, TileNoiseHeight), TileTemperature> for PlanetClimate {
+    fn get(&self, (point, height): (TileCoord, TileNoiseHeight)) -> TileTemperature {
+        let x = point.x as f64 * self.zoom_scale;
+        let y = point.y as f64 * self.zoom_scale;
+
+        let noise = self.noise().temperature.get([x, y]);
+
+        // The land gets colder the higher it is, the sea level is mapped to 0.5
+        let altitude = ((*height - 0.5) * 2.0).max(0.0);
+        let temperature = self.normalize(noise) - altitude * self.elevation_cooling;
+
+        TileTemperature(temperature.clamp(0.0, 1.0))
+    }
+}
+
+impl ChunkMapFunction<TileCoord, TileMoisture> for PlanetClimate {
+    fn get(&self, point: TileCoord) -> TileMoisture {
+        let x = point.x as f64 * self.zoom_scale;
+        let y = point.y as f64 * self.zoom_scale;
+
+        let noise = self.noise().moisture.get([x, y]);
+
+        TileMoisture(self.normalize(noise))
+    }
+}
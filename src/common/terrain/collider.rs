@@ -3,39 +3,71 @@
 use avian3d::prelude::*;
 use bevy::prelude::*;
 
-use super::components::*;
+use super::{components::*, lod::*};
 use crate::common::prelude::*;
 
 pub struct TerrainColliderPlugin;
 
 impl Plugin for TerrainColliderPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, generate_chunk_collider);
+        app.add_systems(
+            Update,
+            (update_chunk_colliders, generate_chunk_collider)
+                .chain()
+                .after(update_chunk_lod),
+        );
     }
 }
 
 #[derive(Component)]
 struct ChunkColliderReady;
 
+/// Mark the meshes of the chunks whose level of detail changed to have their collider added or
+/// removed.
+fn update_chunk_colliders(
+    mut commands: Commands,
+    q_chunks: Query<&Children, (With<ChunkCoord>, Changed<ChunkLod>)>,
+    q_meshes: Query<(), (With<ChunkMesh>, With<ChunkColliderReady>)>,
+) {
+    for children in q_chunks.iter() {
+        for child in children.iter().filter(|child| q_meshes.contains(*child)) {
+            commands.entity(child).remove::<ChunkColliderReady>();
+        }
+    }
+}
+
 fn generate_chunk_collider(
     mut commands: Commands,
-    q_meshes: Query<(Entity, &ChunkMesh, &ChildOf), Without<ChunkColliderReady>>,
-    q_chunks: Query<Entity, With<ChunkCoord>>,
+    q_meshes: Query<
+        (Entity, &ChunkMesh, &ChildOf),
+        Or<(Without<ChunkColliderReady>, Changed<ChunkMesh>)>,
+    >,
+    q_chunks: Query<&ChunkLod, With<ChunkCoord>>,
 ) {
     if q_meshes.is_empty() {
         return;
     }
-    trace!("Generating collider for {} meshes", q_meshes.iter().len());
+    trace!("Generating collider for {} meshes", q_meshes.iter().count());
 
     for (entity, ChunkMesh(mesh), ChildOf(parent)) in q_meshes.iter() {
-        commands.entity(entity).insert((
-            ChunkColliderReady,
-            Collider::trimesh_from_mesh(mesh).unwrap(),
-            RigidBody::Static,
-        ));
-
-        if let Ok(parent) = q_chunks.get(*parent) {
-            commands.entity(parent).insert(ChunkReady);
+        let Ok(lod) = q_chunks.get(*parent) else {
+            continue;
+        };
+
+        // Only the chunks close to the viewers have a collider
+        if lod.collider {
+            commands.entity(entity).insert((
+                ChunkColliderReady,
+                Collider::trimesh_from_mesh(mesh).unwrap(),
+                RigidBody::Static,
+            ));
+        } else {
+            commands
+                .entity(entity)
+                .insert(ChunkColliderReady)
+                .remove::<(Collider, RigidBody)>();
         }
+
+        commands.entity(*parent).insert(ChunkReady);
     }
 }
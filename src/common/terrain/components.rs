@@ -10,6 +10,18 @@ pub struct Tile(pub TileID);
 #[derive(Component, Debug, Clone, Copy, Deref, DerefMut, Reflect)]
 pub struct TileNoiseHeight(pub f64);
 
+/// Whether the tile is part of a river bed.
+#[derive(Component, Debug, Clone, Copy, Deref, DerefMut, Reflect)]
+pub struct TileRiver(pub bool);
+
+/// The temperature of the tile, from 0.0 (coldest) to 1.0 (hottest).
+#[derive(Component, Debug, Clone, Copy, Deref, DerefMut, Reflect)]
+pub struct TileTemperature(pub f64);
+
+/// The moisture of the tile, from 0.0 (driest) to 1.0 (wettest).
+#[derive(Component, Debug, Clone, Copy, Deref, DerefMut, Reflect)]
+pub struct TileMoisture(pub f64);
+
 #[derive(Component, Debug, Clone, Deref, DerefMut, Reflect)]
 pub struct ChunkMesh(pub Mesh);
 
@@ -0,0 +1,236 @@
+//! Edits of the terrain heights.
+//!
+//! The generated heights are never modified, the edits are stored as deltas over the
+//! `TileNoiseHeight` of the tiles, grouped by the chunk that owns the tile. This keeps the edits
+//! small enough to be sent over the network and stored in the world save, and the chunks that
+//! are generated later get the edits applied when their mesh is built.
+
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::generator::*;
+use crate::common::prelude::*;
+
+/// The height change of a raise or lower edit, one unit of world height with the default max
+/// height of the terrain.
+pub const TERRAIN_EDIT_STEP: f64 = 0.025;
+
+/// The deltas that are smaller than this are removed from the edits.
+const TERRAIN_EDIT_EPSILON: f64 = 1e-9;
+
+/// The kind of edit applied to the height of a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum TerrainEditKind {
+    Raise,
+    Lower,
+    /// Set the height to the average of the neighbouring tiles.
+    Flatten,
+}
+
+/// An edit of the height of a single tile.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Reflect)]
+pub struct TerrainEdit {
+    pub tile: IVec2,
+    pub kind: TerrainEditKind,
+}
+
+/// The height deltas of the edited tiles, by chunk center and then by tile. The kind of the
+/// edited tiles is not changed.
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TerrainEdits {
+    chunks: HashMap<IVec2, HashMap<IVec2, f64>>,
+    /// The tiles edited since the chunk meshes were last rebuilt.
+    #[serde(skip)]
+    changed: HashSet<IVec2>,
+}
+
+impl TerrainEdits {
+    /// The height delta of a tile, zero if the tile was never edited.
+    pub fn delta(&self, storage: &TileMapStorage, tile: IVec2) -> f64 {
+        self.chunks
+            .get(&storage.tile_to_center(&tile))
+            .and_then(|chunk| chunk.get(&tile))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The height of a tile with the edits applied.
+    pub fn height(
+        &self,
+        storage: &TileMapStorage,
+        generator: &dyn TerrainGenerator,
+        tile: IVec2,
+    ) -> f64 {
+        generator.height(&TileCoord(tile)) + self.delta(storage, tile)
+    }
+
+    /// The deltas of the edited tiles of a chunk.
+    pub fn chunk(&self, center: IVec2) -> Option<&HashMap<IVec2, f64>> {
+        self.chunks.get(&center)
+    }
+
+    /// The edited chunks, by chunk center.
+    pub fn iter(&self) -> impl Iterator<Item = (&IVec2, &HashMap<IVec2, f64>)> {
+        self.chunks.iter()
+    }
+
+    /// Replace the deltas of a chunk, used by the clients when the server sends the edits.
+    pub fn insert_chunk(&mut self, center: IVec2, deltas: HashMap<IVec2, f64>) {
+        let previous = self.chunks.remove(&center).unwrap_or_default();
+        self.changed.extend(
+            previous
+                .iter()
+                .filter(|(tile, delta)| deltas.get(*tile) != Some(*delta))
+                .map(|(tile, _)| *tile),
+        );
+        self.changed.extend(
+            deltas
+                .iter()
+                .filter(|(tile, delta)| previous.get(*tile) != Some(*delta))
+                .map(|(tile, _)| *tile),
+        );
+
+        if !deltas.is_empty() {
+            self.chunks.insert(center, deltas);
+        }
+    }
+
+    /// Apply an edit on top of the current height of the tile. Returns the center of the chunk
+    /// that owns the tile if its deltas changed.
+    pub fn apply(
+        &mut self,
+        storage: &TileMapStorage,
+        generator: &dyn TerrainGenerator,
+        edit: &TerrainEdit,
+    ) -> Option<IVec2> {
+        let base = generator.height(&TileCoord(edit.tile));
+        let current = base + self.delta(storage, edit.tile);
+
+        let height = match edit.kind {
+            TerrainEditKind::Raise => current + TERRAIN_EDIT_STEP,
+            TerrainEditKind::Lower => current - TERRAIN_EDIT_STEP,
+            TerrainEditKind::Flatten => {
+                let neighbours = (-1..=1)
+                    .flat_map(|x| (-1..=1).map(move |y| IVec2::new(x, y)))
+                    .filter(|offset| *offset != IVec2::ZERO)
+                    .map(|offset| self.height(storage, generator, edit.tile + offset))
+                    .collect::<Vec<_>>();
+                neighbours.iter().sum::<f64>() / neighbours.len() as f64
+            }
+        };
+        let height = height.clamp(0.0, 1.0);
+        if (height - current).abs() < TERRAIN_EDIT_EPSILON {
+            return None;
+        }
+
+        let center = storage.tile_to_center(&edit.tile);
+        let chunk = self.chunks.entry(center).or_default();
+        let delta = height - base;
+        if delta.abs() < TERRAIN_EDIT_EPSILON {
+            chunk.remove(&edit.tile);
+        } else {
+            chunk.insert(edit.tile, delta);
+        }
+        if chunk.is_empty() {
+            self.chunks.remove(&center);
+        }
+        self.changed.insert(edit.tile);
+
+        Some(center)
+    }
+
+    /// Remove all the edits, used when leaving the world.
+    pub fn clear(&mut self) {
+        self.changed
+            .extend(self.chunks.drain().flat_map(|(_, chunk)| chunk.into_keys()));
+    }
+
+    /// The tiles edited since the last call.
+    pub(super) fn take_changed(&mut self) -> HashSet<IVec2> {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage() -> TileMapStorage {
+        TileMapStorage::new(Vec2::splat(1.0), 2)
+    }
+
+    fn edit(x: i32, y: i32, kind: TerrainEditKind) -> TerrainEdit {
+        TerrainEdit {
+            tile: IVec2::new(x, y),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_raise_and_lower() {
+        let storage = storage();
+        let generator = FlatGenerator { height: 0.6 };
+        let mut edits = TerrainEdits::default();
+
+        let chunk = edits.apply(&storage, &generator, &edit(3, 0, TerrainEditKind::Raise));
+        assert_eq!(chunk, Some(IVec2::new(5, 0)));
+        let height = edits.height(&storage, &generator, IVec2::new(3, 0));
+        assert!((height - 0.6 - TERRAIN_EDIT_STEP).abs() < TERRAIN_EDIT_EPSILON);
+        assert_eq!(edits.take_changed(), HashSet::from_iter([IVec2::new(3, 0)]));
+
+        // Lowering back to the generated height removes the edit
+        edits.apply(&storage, &generator, &edit(3, 0, TerrainEditKind::Lower));
+        assert!(edits.delta(&storage, IVec2::new(3, 0)).abs() < TERRAIN_EDIT_EPSILON);
+        assert!(edits.chunk(IVec2::new(5, 0)).is_none());
+    }
+
+    #[test]
+    fn test_edits_are_clamped() {
+        let storage = storage();
+        let generator = FlatGenerator { height: 1.0 };
+        let mut edits = TerrainEdits::default();
+
+        let chunk = edits.apply(&storage, &generator, &edit(0, 0, TerrainEditKind::Raise));
+        assert_eq!(chunk, None);
+        assert!(edits.take_changed().is_empty());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let storage = storage();
+        let generator = FlatGenerator { height: 0.6 };
+        let mut edits = TerrainEdits::default();
+
+        for _ in 0..4 {
+            edits.apply(&storage, &generator, &edit(0, 0, TerrainEditKind::Raise));
+        }
+        edits.apply(&storage, &generator, &edit(1, 0, TerrainEditKind::Lower));
+
+        // The average of the eight neighbours, one of them was lowered
+        edits.apply(&storage, &generator, &edit(0, 0, TerrainEditKind::Flatten));
+        let expected = 0.6 - TERRAIN_EDIT_STEP / 8.0;
+        let height = edits.height(&storage, &generator, IVec2::new(0, 0));
+        assert!((height - expected).abs() < TERRAIN_EDIT_EPSILON);
+    }
+
+    #[test]
+    fn test_insert_chunk() {
+        let storage = storage();
+        let mut edits = TerrainEdits::default();
+
+        let center = IVec2::new(0, 0);
+        edits.insert_chunk(
+            center,
+            HashMap::from_iter([(IVec2::new(1, 1), 0.1), (IVec2::new(2, 2), 0.2)]),
+        );
+        assert_eq!(edits.delta(&storage, IVec2::new(2, 2)), 0.2);
+        assert_eq!(edits.take_changed().len(), 2);
+
+        // Only the tiles whose delta changed are rebuilt
+        edits.insert_chunk(center, HashMap::from_iter([(IVec2::new(1, 1), 0.1)]));
+        assert_eq!(edits.delta(&storage, IVec2::new(2, 2)), 0.0);
+        assert_eq!(edits.take_changed(), HashSet::from_iter([IVec2::new(2, 2)]));
+    }
+}
@@ -4,7 +4,7 @@ use std::time::SystemTime;
 
 use bevy::prelude::*;
 
-use super::{components::*, planet::*, resources::*};
+use super::{climate::*, components::*, edit::*, generator::*, preset::*, resources::*};
 use crate::common::prelude::*;
 
 #[derive(Resource, Debug, Clone, PartialEq, Deref, DerefMut, Reflect)]
@@ -39,14 +39,30 @@ impl Plugin for TerrainGenerationPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Tile>()
             .register_type::<TileNoiseHeight>()
+            .register_type::<TileRiver>()
+            .register_type::<TileTemperature>()
+            .register_type::<TileMoisture>()
             .register_type::<TerrainGenerationProgress>()
-            .register_type::<TerrainGenerationSeed>();
+            .register_type::<TerrainGenerationSeed>()
+            .register_type::<TerrainGenerationPreset>()
+            .register_type::<TerrainEditKind>();
 
         app.add_plugins(TileMapPlugin::new(self.tile_size, self.chunk_radius))
-            // TODO: Somehow I want to be able to add this PlanetHeight from outside
-            // I need this to be more generic for copy pastability
             .add_plugins(ChunkMapPlugin::<TileCoord, TileNoiseHeight, _>::new(
-                PlanetHeight::default(),
+                ActiveTerrainGenerator::default(),
+            ))
+            .add_plugins(
+                ChunkMapPlugin::<(TileCoord, TileNoiseHeight), TileRiver, _>::new(
+                    ActiveTerrainGenerator::default(),
+                ),
+            )
+            .add_plugins(ChunkMapPlugin::<
+                (TileCoord, TileNoiseHeight),
+                TileTemperature,
+                _,
+            >::new(PlanetClimate::default()))
+            .add_plugins(ChunkMapPlugin::<TileCoord, TileMoisture, _>::new(
+                PlanetClimate::default(),
             ))
             .add_systems(Update, handle_chunk);
 
@@ -54,16 +70,32 @@ impl Plugin for TerrainGenerationPlugin {
         app.add_systems(Update, handle_chunk_progress);
 
         app.insert_resource(TerrainGenerationSeed::default());
+        app.init_resource::<TerrainGenerationPreset>();
+        app.init_resource::<TerrainGenerators>();
+        app.init_resource::<TerrainEdits>();
         app.add_systems(
             Update,
-            update_terrain_seed.run_if(resource_changed::<TerrainGenerationSeed>),
+            update_terrain_generation.run_if(
+                resource_changed::<TerrainGenerationSeed>
+                    .or(resource_changed::<TerrainGenerationPreset>)
+                    .or(resource_changed::<TerrainGenerators>),
+            ),
         );
     }
 }
 
 fn handle_chunk(
     mut commands: Commands,
-    q_tiles: Query<(Entity, &TileNoiseHeight), (With<TileCoord>, Without<Tile>)>,
+    q_tiles: Query<
+        (
+            Entity,
+            &TileNoiseHeight,
+            &TileRiver,
+            &TileTemperature,
+            &TileMoisture,
+        ),
+        (With<TileCoord>, Without<Tile>),
+    >,
     assets: Res<TerrainAssets>,
 ) {
     if q_tiles.is_empty() {
@@ -71,10 +103,10 @@ fn handle_chunk(
     }
     trace!("Computing Tile kind for {} tiles", q_tiles.iter().len());
 
-    for (entity, height) in q_tiles {
+    for (entity, height, river, temperature, moisture) in q_tiles {
         let height = **height;
 
-        let kind = assets.get_tile(height);
+        let kind = assets.get_tile(height, **temperature, **moisture, **river);
 
         match kind {
             Some(kind) => {
@@ -87,9 +119,26 @@ fn handle_chunk(
     }
 }
 
-fn update_terrain_seed(mut func: ResMut<PlanetHeight>, seed: Res<TerrainGenerationSeed>) {
-    *func = func.with_seed(**seed);
-    debug!("Updated terrain seed to {}", seed.0);
+fn update_terrain_generation(
+    mut func: ResMut<ActiveTerrainGenerator>,
+    mut climate: ResMut<PlanetClimate>,
+    generators: Res<TerrainGenerators>,
+    seed: Res<TerrainGenerationSeed>,
+    preset: Res<TerrainGenerationPreset>,
+) {
+    // The servers and the clients check the generator before they load or join a world
+    match generators.create(**seed, &preset) {
+        Ok(generator) => **func = generator,
+        Err(err) => {
+            error!("Failed to create the terrain generator: {}", err);
+            return;
+        }
+    }
+    *climate = climate.with_preset(&preset).with_seed(**seed);
+    debug!(
+        "Updated terrain seed to {} with preset {}",
+        seed.0, preset.name
+    );
 }
 
 #[derive(Resource, Debug, Clone, Default, Reflect)]
@@ -118,3 +167,13 @@ impl ChunkMapInput for TileCoord {
         item.0.clone()
     }
 }
+
+impl ChunkMapInput for (TileCoord, TileNoiseHeight) {
+    type Query = (&'static TileCoord, &'static TileNoiseHeight);
+
+    fn from_query_item(
+        item: bevy::ecs::query::QueryItem<<Self::Query as bevy::ecs::query::QueryData>::ReadOnly>,
+    ) -> Self {
+        (item.0.clone(), *item.1)
+    }
+}
@@ -0,0 +1,226 @@
+//! Pluggable generators of the terrain heights.
+//!
+//! The generator of a world is picked by the `generator` of its preset. The planet generator is
+//! the default, the flat and islands generators are mostly useful for testing, and mods can add
+//! their own generators to the `TerrainGenerators` registry and select them by name.
+
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use serde::{Deserialize, Serialize};
+
+use super::{components::*, planet::*, preset::*};
+use crate::common::prelude::*;
+
+/// Height of the flat worlds, just above the sea level so the tiles are grass.
+pub(super) const FLAT_HEIGHT: f64 = 0.6;
+
+/// Radius of the island of the islands generator, relative to the zoom of the preset.
+const ISLAND_RADIUS: f64 = 0.5;
+
+/// A generator of the terrain heights.
+pub trait TerrainGenerator: Send + Sync + 'static {
+    /// The height of the tile between 0.0 and 1.0, the sea level is mapped to 0.5.
+    fn height(&self, point: &TileCoord) -> f64;
+
+    /// Whether the tile is part of a river bed.
+    fn river(&self, _point: &TileCoord, _height: f64) -> bool {
+        false
+    }
+}
+
+/// Creates a generator for the given seed and preset.
+pub type TerrainGeneratorFactory = fn(u32, &TerrainGenerationPreset) -> Arc<dyn TerrainGenerator>;
+
+/// The kind of generator used by a world.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, Reflect)]
+pub enum TerrainGeneratorKind {
+    /// The complex planet with continents, mountains and rivers.
+    #[default]
+    Planet,
+    /// A flat world at the given height.
+    Flat(f64),
+    /// A single island around the origin of the world.
+    Islands,
+    /// A generator added by a mod to the `TerrainGenerators` registry.
+    Custom(String),
+}
+
+/// The registry of the custom generators, by name.
+#[derive(Resource, Default)]
+pub struct TerrainGenerators {
+    factories: HashMap<String, TerrainGeneratorFactory>,
+}
+
+impl TerrainGenerators {
+    pub fn register(&mut self, name: impl Into<String>, factory: TerrainGeneratorFactory) {
+        self.factories.insert(name.into(), factory);
+    }
+
+    /// Check that the generator is available. A world made by an unknown custom generator
+    /// would be generated differently, so it must not be joined or loaded without its mod.
+    pub fn validate(&self, kind: &TerrainGeneratorKind) -> Result<(), String> {
+        match kind {
+            TerrainGeneratorKind::Custom(name) if !self.factories.contains_key(name) => {
+                Err(format!("unknown terrain generator {name}"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Create the generator of a world, fails if the generator is unknown.
+    pub fn create(
+        &self,
+        seed: u32,
+        preset: &TerrainGenerationPreset,
+    ) -> Result<Arc<dyn TerrainGenerator>, String> {
+        let generator: Arc<dyn TerrainGenerator> = match &preset.generator {
+            TerrainGeneratorKind::Planet => {
+                Arc::new(PlanetHeight::default().with_preset(preset).with_seed(seed))
+            }
+            TerrainGeneratorKind::Flat(height) => Arc::new(FlatGenerator { height: *height }),
+            TerrainGeneratorKind::Islands => Arc::new(IslandsGenerator::new(seed, preset)),
+            TerrainGeneratorKind::Custom(name) => match self.factories.get(name) {
+                Some(factory) => factory(seed, preset),
+                None => return Err(format!("unknown terrain generator {name}")),
+            },
+        };
+
+        Ok(generator)
+    }
+}
+
+/// The generator of the current world, used by the chunk map plugins of the terrain.
+#[derive(Resource, Clone, Deref, DerefMut)]
+pub struct ActiveTerrainGenerator(pub Arc<dyn TerrainGenerator>);
+
+impl Default for ActiveTerrainGenerator {
+    fn default() -> Self {
+        Self(Arc::new(PlanetHeight::default()))
+    }
+}
+
+impl ChunkMapFunction<TileCoord, TileNoiseHeight> for ActiveTerrainGenerator {
+    fn get(&self, point: TileCoord) -> TileNoiseHeight {
+        TileNoiseHeight(self.height(&point))
+    }
+}
+
+impl ChunkMapFunction<(TileCoord, TileNoiseHeight), TileRiver> for ActiveTerrainGenerator {
+    fn get(&self, (point, height): (TileCoord, TileNoiseHeight)) -> TileRiver {
+        TileRiver(self.river(&point, *height))
+    }
+}
+
+impl TerrainGenerator for PlanetHeight {
+    fn height(&self, point: &TileCoord) -> f64 {
+        *self.get(point.clone())
+    }
+
+    fn river(&self, point: &TileCoord, height: f64) -> bool {
+        *self.get((point.clone(), TileNoiseHeight(height)))
+    }
+}
+
+/// Every tile has the same height.
+#[derive(Clone, Copy, Debug)]
+pub struct FlatGenerator {
+    pub height: f64,
+}
+
+impl TerrainGenerator for FlatGenerator {
+    fn height(&self, _point: &TileCoord) -> f64 {
+        self.height
+    }
+}
+
+/// A single island around the origin, surrounded by the sea.
+#[derive(Clone, Debug)]
+pub struct IslandsGenerator {
+    zoom_scale: f64,
+    noise: Fbm<Perlin>,
+}
+
+impl IslandsGenerator {
+    pub fn new(seed: u32, preset: &TerrainGenerationPreset) -> Self {
+        Self {
+            zoom_scale: preset.zoom_scale,
+            noise: Fbm::<Perlin>::new(seed)
+                .set_frequency(preset.continent_frequency * 4.0)
+                .set_persistence(0.5)
+                .set_lacunarity(preset.continent_lacunarity)
+                .set_octaves(6),
+        }
+    }
+}
+
+impl TerrainGenerator for IslandsGenerator {
+    fn height(&self, point: &TileCoord) -> f64 {
+        let x = point.x as f64 * self.zoom_scale;
+        let y = point.y as f64 * self.zoom_scale;
+
+        let noise = self.noise.get([x, y]);
+
+        // The land falls off with the distance to the origin, the coast is around 0.7 radius
+        let distance = (x * x + y * y).sqrt() / ISLAND_RADIUS;
+        let elevation = noise * 0.5 + 1.0 - 2.0 * distance * distance;
+
+        (0.5 + elevation * 0.25).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(x: i32, y: i32) -> TileCoord {
+        TileCoord(IVec2::new(x, y))
+    }
+
+    #[test]
+    fn test_flat_generator() {
+        let preset = TerrainGenerationPreset::flat();
+        let generator = TerrainGenerators::default().create(42, &preset).unwrap();
+
+        for point in [tile(0, 0), tile(-1234, 567), tile(4000, 4000)] {
+            assert_eq!(generator.height(&point), FLAT_HEIGHT);
+            assert!(!generator.river(&point, FLAT_HEIGHT));
+        }
+    }
+
+    #[test]
+    fn test_islands_generator() {
+        let preset = TerrainGenerationPreset {
+            generator: TerrainGeneratorKind::Islands,
+            ..default()
+        };
+        let generator = TerrainGenerators::default().create(42, &preset).unwrap();
+
+        assert!(generator.height(&tile(0, 0)) > 0.5);
+        assert!(generator.height(&tile(2000, -2000)) < 0.5);
+    }
+
+    #[test]
+    fn test_custom_generator() {
+        let mut generators = TerrainGenerators::default();
+        generators.register("sea", |_, _| Arc::new(FlatGenerator { height: 0.25 }));
+
+        let preset = TerrainGenerationPreset {
+            generator: TerrainGeneratorKind::Custom("sea".to_string()),
+            ..default()
+        };
+        assert_eq!(
+            generators.create(0, &preset).unwrap().height(&tile(10, 10)),
+            0.25
+        );
+
+        // Unknown generators are refused
+        let preset = TerrainGenerationPreset {
+            generator: TerrainGeneratorKind::Custom("missing".to_string()),
+            ..default()
+        };
+        assert!(generators.validate(&preset.generator).is_err());
+        assert!(generators.create(0, &preset).is_err());
+    }
+}
@@ -1,4 +1,13 @@
-//! TODO: Add module documentation
+//! Geometry of the terrain chunks.
+//!
+//! Each tile is a quad whose corners are shared with the three other tiles around them. The
+//! height and the normal of a corner only depend on the heights of these four tiles, so the
+//! chunks also mesh their `TileBorder` ring and neighbouring chunks produce the same vertices
+//! along their shared edges, without gaps or lighting seams.
+//!
+//! The chunks further from the viewers only use some of the corners, see the `lod` module. The
+//! corners they use are the same as the full detail ones, and skirts hanging from the edges of
+//! every chunk hide the cracks where a coarse edge meets a finer one.
 
 use bevy::{
     asset::RenderAssetUsages,
@@ -8,18 +17,20 @@ use bevy::{
 };
 use itertools::Itertools;
 
-use super::components::*;
+use super::{components::*, edit::*, lod::*};
 use crate::common::prelude::*;
 
 pub struct TerrainGeometryPlugin {
     tile_size: Vec2,
+    chunk_radius: u32,
     max_height: f64,
 }
 
 impl TerrainGeometryPlugin {
-    pub fn new(tile_size: Vec2, max_height: f64) -> Self {
+    pub fn new(tile_size: Vec2, chunk_radius: u32, max_height: f64) -> Self {
         Self {
             tile_size,
+            chunk_radius,
             max_height,
         }
     }
@@ -30,129 +41,124 @@ impl Plugin for TerrainGeometryPlugin {
         app.register_type::<ChunkMesh>()
             .register_type::<GeometrySettings>();
 
-        app.insert_resource(GeometrySettings::new(self.tile_size, self.max_height))
-            .add_systems(Update, generate_chunk_mesh);
+        app.insert_resource(GeometrySettings::new(
+            self.tile_size,
+            self.chunk_radius,
+            self.max_height,
+        ))
+        .add_systems(
+            Update,
+            (
+                rebuild_edited_chunks.run_if(resource_changed::<TerrainEdits>),
+                rebuild_lod_chunks,
+                generate_chunk_mesh,
+            )
+                .chain()
+                .after(update_chunk_lod),
+        );
     }
 }
 
+/// The height of the bottom of the skirts, below the sea level which is the lowest height of the
+/// terrain.
+const SKIRT_BOTTOM: f32 = -1.0;
+
 #[derive(Component)]
 struct ChunkMeshReady;
 
+/// The level of detail the mesh of a chunk was built with, `None` when the chunk has no mesh.
+#[derive(Component)]
+struct ChunkMeshLod(Option<u32>);
+
+/// The level of detail of the mesh of a chunk, the chunks that are neither rendered nor have a
+/// collider are not meshed.
+fn mesh_level(lod: &ChunkLod, rendered: bool) -> Option<u32> {
+    (rendered || lod.collider).then_some(lod.level)
+}
+
 #[derive(Resource, Debug, Clone, Default, Reflect)]
 struct GeometrySettings {
     tile_size: Vec2,
+    chunk_radius: u32,
     max_height: f64,
 }
 
 impl GeometrySettings {
-    fn new(tile_size: Vec2, max_height: f64) -> Self {
+    fn new(tile_size: Vec2, chunk_radius: u32, max_height: f64) -> Self {
         Self {
             tile_size,
+            chunk_radius,
             max_height,
         }
     }
 
-    fn mesh(&self, chunk: HashMap<IVec2, f64>) -> Mesh {
-        let mut positions = Vec::new();
-        let mut uvs = Vec::new();
-        let mut indices = Vec::new();
-
-        let tile_size = self.tile_size;
-        let half_size = tile_size * 0.5;
-
-        for (tile_coord, _) in chunk.iter() {
-            let base_x = tile_coord.x as f32 * tile_size.x;
-            let base_y = tile_coord.y as f32 * tile_size.y;
+    /// The height of the tile in the world, the tiles below the sea level are flattened to 0.0.
+    fn world_height(&self, height: f64) -> f64 {
+        let height_value = (height * 2.0 - 1.0).clamp(0.0, 1.0);
+        (height_value * self.max_height).round()
+    }
 
-            let base = Vec2::new(base_x, base_y);
+    /// The height and the normal of the corner shared by the four tiles starting at `min`. The
+    /// tiles are always read in the same order, so every chunk computes the exact same values.
+    fn corner(&self, heights: &HashMap<IVec2, f64>, min: IVec2) -> Option<(f32, Vec3)> {
+        let h00 = *heights.get(&min)?;
+        let h10 = *heights.get(&(min + IVec2::new(1, 0)))?;
+        let h01 = *heights.get(&(min + IVec2::new(0, 1)))?;
+        let h11 = *heights.get(&(min + IVec2::new(1, 1)))?;
+
+        // The corners that touch the water are flattened to the sea level
+        if [h00, h10, h01, h11].iter().any(|h| *h <= 0.0) {
+            return Some((0.0, Vec3::Y));
+        }
 
-            let v0 = base + Vec2::new(-half_size.x, -half_size.y);
-            let v1 = base + Vec2::new(half_size.x, -half_size.y);
-            let v2 = base + Vec2::new(-half_size.x, half_size.y);
-            let v3 = base + Vec2::new(half_size.x, half_size.y);
+        let height = (h00 + h10 + h01 + h11) / 4.0;
+        let slope_x = ((h10 + h11) - (h00 + h01)) / 2.0 / self.tile_size.x as f64;
+        let slope_z = ((h01 + h11) - (h00 + h10)) / 2.0 / self.tile_size.y as f64;
+        let normal = Vec3::new(-slope_x as f32, 1.0, -slope_z as f32).normalize();
 
-            let ns = [
-                IVec2::new(0, 0),
-                IVec2::new(-1, -1),
-                IVec2::new(0, -1),
-                IVec2::new(-1, 0),
-            ]
-            .iter()
-            .filter_map(|offset| chunk.get(&(tile_coord + *offset)).copied())
-            .collect_vec();
-            if ns.len() < 4 {
-                continue;
-            }
-            let h = if ns.iter().any(|n| *n <= 0.0) {
-                0.0
-            } else {
-                ns.iter().sum::<f64>() / 4.0
-            };
-            let v0 = Vec3::new(v0.x, h as f32, v0.y);
+        Some((height as f32, normal))
+    }
 
-            let ns = [
-                IVec2::new(0, 0),
-                IVec2::new(1, -1),
-                IVec2::new(0, -1),
-                IVec2::new(1, 0),
-            ]
-            .iter()
-            .filter_map(|offset| chunk.get(&(tile_coord + *offset)).copied())
-            .collect_vec();
-            if ns.len() < 4 {
-                continue;
-            }
-            let h = if ns.iter().any(|n| *n <= 0.0) {
-                0.0
-            } else {
-                ns.iter().sum::<f64>() / 4.0
-            };
-            let v1 = Vec3::new(v1.x, h as f32, v1.y);
+    /// The local corner lines sampled by the mesh of a chunk at a level of detail, every
+    /// `2^level` corner from the first one, and always the last one.
+    fn corner_lines(&self, level: u32) -> Vec<u32> {
+        let size = self.chunk_radius * 2 + 1;
+        let step = 2u32.saturating_pow(level).min(size);
 
-            let ns = [
-                IVec2::new(0, 0),
-                IVec2::new(-1, 1),
-                IVec2::new(0, 1),
-                IVec2::new(-1, 0),
-            ]
-            .iter()
-            .filter_map(|offset| chunk.get(&(tile_coord + *offset)).copied())
-            .collect_vec();
-            if ns.len() < 4 {
-                continue;
-            }
-            let h = if ns.iter().any(|n| *n <= 0.0) {
-                0.0
-            } else {
-                ns.iter().sum::<f64>() / 4.0
-            };
-            let v2 = Vec3::new(v2.x, h as f32, v2.y);
+        (0..size).step_by(step as usize).chain([size]).collect()
+    }
 
-            let ns = [
-                IVec2::new(0, 0),
-                IVec2::new(1, 1),
-                IVec2::new(0, 1),
-                IVec2::new(1, 0),
-            ]
-            .iter()
-            .filter_map(|offset| chunk.get(&(tile_coord + *offset)).copied())
-            .collect_vec();
-            if ns.len() < 4 {
-                continue;
-            }
-            let h = if ns.iter().any(|n| *n <= 0.0) {
-                0.0
-            } else {
-                ns.iter().sum::<f64>() / 4.0
-            };
-            let v3 = Vec3::new(v3.x, h as f32, v3.y);
+    /// Build the mesh of a chunk from the world heights of its tiles and of its border ring, by
+    /// local tile coordinate. The coarser levels of detail only sample some of the corners, and
+    /// every level hangs skirts from the edges of the chunk to hide the cracks with the
+    /// neighbouring chunks meshed at another level.
+    fn mesh(&self, heights: &HashMap<IVec2, f64>, level: u32) -> Mesh {
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut indices = Vec::new();
 
+        let lines = self.corner_lines(level);
+        let first = lines[0];
+        let last = lines[lines.len() - 1];
+        let radius = self.chunk_radius as i32;
+        let half_size = self.tile_size * 0.5;
+
+        // The position and the normal of the corner on the given corner lines
+        let corner = |line: UVec2| {
+            let min = line.as_ivec2() - IVec2::splat(radius + 1);
+            self.corner(heights, min).map(|(height, normal)| {
+                let position = min.as_vec2() * self.tile_size + half_size;
+                (position.extend(height).xzy(), normal)
+            })
+        };
+
+        let mut push_quad = |vertices: [(Vec3, Vec3); 4]| {
             let start_index = positions.len() as u32;
-
-            positions.push(v0);
-            positions.push(v1);
-            positions.push(v2);
-            positions.push(v3);
+            for (position, normal) in vertices {
+                positions.push(position);
+                normals.push(normal);
+            }
 
             // UVs
             uvs.push([0.0, 0.0]);
@@ -168,31 +174,71 @@ impl GeometrySettings {
             indices.push(start_index + 1);
             indices.push(start_index + 2);
             indices.push(start_index + 3);
-        }
-
-        let mut normals = vec![Vec3::ZERO; positions.len()];
+        };
 
-        for tri in indices.chunks(3) {
-            let i0 = tri[0] as usize;
-            let i1 = tri[1] as usize;
-            let i2 = tri[2] as usize;
-
-            let v0 = positions[i0];
-            let v1 = positions[i1];
-            let v2 = positions[i2];
-
-            let edge1 = v1 - v0;
-            let edge2 = v2 - v0;
-            let n = edge1.cross(edge2).normalize();
+        for ((y0, y1), (x0, x1)) in lines
+            .iter()
+            .copied()
+            .tuple_windows()
+            .cartesian_product(lines.iter().copied().tuple_windows())
+        {
+            // The corners in the order A, B, C, D
+            let Some(vertices) = [
+                UVec2::new(x0, y0),
+                UVec2::new(x1, y0),
+                UVec2::new(x0, y1),
+                UVec2::new(x1, y1),
+            ]
+            .into_iter()
+            .map(corner)
+            .collect::<Option<Vec<_>>>() else {
+                warn!(
+                    "Missing the heights around the quad at corner {:?} of the chunk",
+                    UVec2::new(x0, y0)
+                );
+                continue;
+            };
 
-            normals[i0] += n;
-            normals[i1] += n;
-            normals[i2] += n;
+            push_quad([vertices[0], vertices[1], vertices[2], vertices[3]]);
         }
 
-        // normalize all
-        for n in &mut normals {
-            *n = n.normalize();
+        // The skirts are vertical quads along the edges, facing out of the chunk
+        let sides = [
+            (
+                Vec3::NEG_Z,
+                lines.iter().map(|x| UVec2::new(*x, first)).collect_vec(),
+            ),
+            (
+                Vec3::Z,
+                lines.iter().map(|x| UVec2::new(*x, last)).collect_vec(),
+            ),
+            (
+                Vec3::NEG_X,
+                lines.iter().map(|y| UVec2::new(first, *y)).collect_vec(),
+            ),
+            (
+                Vec3::X,
+                lines.iter().map(|y| UVec2::new(last, *y)).collect_vec(),
+            ),
+        ];
+        for (outward, side) in sides {
+            for (a, b) in side.into_iter().tuple_windows() {
+                let (Some(a), Some(b)) = (corner(a), corner(b)) else {
+                    continue;
+                };
+                let (a, b) = if Vec3::NEG_Y.cross(b.0 - a.0).dot(outward) < 0.0 {
+                    (b, a)
+                } else {
+                    (a, b)
+                };
+
+                push_quad([
+                    a,
+                    b,
+                    (a.0.with_y(SKIRT_BOTTOM), a.1),
+                    (b.0.with_y(SKIRT_BOTTOM), b.1),
+                ]);
+            }
         }
 
         // Create mesh
@@ -210,7 +256,21 @@ impl GeometrySettings {
 fn generate_chunk_mesh(
     mut commands: Commands,
     layout: Res<GeometrySettings>,
-    q_tiles: Query<(Entity, &LocalTileCoord, &TileNoiseHeight, &ChildOf), Without<ChunkMeshReady>>,
+    storage: Res<TileMapStorage>,
+    edits: Res<TerrainEdits>,
+    rendered: Option<Res<TerrainRendered>>,
+    q_tiles: Query<
+        (
+            Entity,
+            &TileCoord,
+            &LocalTileCoord,
+            &TileNoiseHeight,
+            &ChildOf,
+        ),
+        Without<ChunkMeshReady>,
+    >,
+    q_chunks: Query<(&Children, &ChunkLod), With<ChunkCoord>>,
+    q_meshes: Query<(), With<ChunkMesh>>,
 ) {
     if q_tiles.is_empty() {
         return;
@@ -219,27 +279,260 @@ fn generate_chunk_mesh(
 
     for (chunk_entity, chunk) in q_tiles
         .iter()
-        .sorted_by_key(|(_, _, _, ChildOf(e))| *e)
-        .chunk_by(|(_, _, _, ChildOf(e))| *e)
+        .sorted_by_key(|(_, _, _, _, ChildOf(e))| *e)
+        .chunk_by(|(_, _, _, _, ChildOf(e))| *e)
         .into_iter()
     {
-        let mut storage = HashMap::default();
+        // The chunks are meshed once their level of detail is known
+        let Ok((children, lod)) = q_chunks.get(chunk_entity) else {
+            continue;
+        };
+
+        let mut heights = HashMap::default();
 
-        for (entity, local, height, _) in chunk {
-            let height = **height;
-            let height_value = (height * 2.0 - 1.0).clamp(0.0, 1.0); // flatten 0.5 and below to 0.0 hardcoded for water
-            let height_mesh = (height_value * layout.max_height).round();
+        for (entity, tile, local, height, _) in chunk {
+            let height_mesh = layout.world_height(**height + edits.delta(&storage, **tile));
 
-            storage.insert(**local, height_mesh);
+            heights.insert(**local, height_mesh);
             commands.entity(entity).insert(TileWorldHeight(height_mesh));
 
             commands.entity(entity).insert(ChunkMeshReady);
         }
 
-        let mesh = layout.mesh(storage);
+        let existing = children.iter().find(|child| q_meshes.contains(*child));
+        let level = mesh_level(lod, rendered.is_some());
+        commands.entity(chunk_entity).insert(ChunkMeshLod(level));
+
+        let Some(level) = level else {
+            // The tiles still need their height, for the features and the edits
+            if let Some(mesh_entity) = existing {
+                commands.entity(mesh_entity).despawn();
+            }
+            commands.entity(chunk_entity).insert(ChunkReady);
+            continue;
+        };
+        let mesh = layout.mesh(&heights, level);
+
+        // The mesh of an edited chunk is replaced, so the collider and the render are rebuilt
+        match existing {
+            Some(mesh_entity) => {
+                commands.entity(mesh_entity).insert(ChunkMesh(mesh));
+            }
+            None => {
+                commands.entity(chunk_entity).with_children(|parent| {
+                    parent.spawn((Name::new("Chunk Mesh"), ChunkMesh(mesh)));
+                });
+            }
+        }
+    }
+}
+
+/// Mark the chunks of the edited tiles to have their mesh generated again. The border tiles of
+/// a chunk belong to its neighbours, so the chunks around the edited tiles are also rebuilt.
+fn rebuild_edited_chunks(
+    mut commands: Commands,
+    mut edits: ResMut<TerrainEdits>,
+    storage: Res<TileMapStorage>,
+    q_chunks: Query<&Children, With<ChunkCoord>>,
+    q_tiles: Query<(), (With<TileCoord>, With<ChunkMeshReady>)>,
+) {
+    let changed = edits.take_changed();
+    if changed.is_empty() {
+        return;
+    }
+
+    let chunks = changed
+        .iter()
+        .flat_map(|tile| {
+            (-1..=1).flat_map(move |x| (-1..=1).map(move |y| *tile + IVec2::new(x, y)))
+        })
+        .map(|tile| storage.tile_to_center(&tile))
+        .unique()
+        .collect_vec();
+    debug!(
+        "Rebuilding {} chunks for {} edited tiles",
+        chunks.len(),
+        changed.len()
+    );
+
+    for center in chunks {
+        let Some(children) = storage
+            .get_chunk(center)
+            .and_then(|chunk| q_chunks.get(*chunk).ok())
+        else {
+            continue;
+        };
+
+        for child in children.iter().filter(|child| q_tiles.contains(*child)) {
+            commands.entity(child).remove::<ChunkMeshReady>();
+        }
+    }
+}
+
+/// Mark the chunks whose level of detail changed to have their mesh generated again.
+fn rebuild_lod_chunks(
+    mut commands: Commands,
+    rendered: Option<Res<TerrainRendered>>,
+    q_chunks: Query<(&ChunkLod, &ChunkMeshLod, &Children), Changed<ChunkLod>>,
+    q_tiles: Query<(), (With<TileCoord>, With<ChunkMeshReady>)>,
+) {
+    for (lod, ChunkMeshLod(built), children) in q_chunks.iter() {
+        if mesh_level(lod, rendered.is_some()) == *built {
+            continue;
+        }
+
+        for child in children.iter().filter(|child| q_tiles.contains(*child)) {
+            commands.entity(child).remove::<ChunkMeshReady>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use super::*;
+    use crate::common::terrain::generator::TerrainGenerator;
+
+    const RADIUS: u32 = 3;
+
+    /// Rolling hills with some water, so the corners have different heights and slopes.
+    struct Hills;
+
+    impl TerrainGenerator for Hills {
+        fn height(&self, point: &TileCoord) -> f64 {
+            0.6 + 0.35 * (point.x as f64 * 0.7).sin() * (point.y as f64 * 0.4).cos()
+        }
+    }
+
+    fn chunk_mesh(
+        settings: &GeometrySettings,
+        storage: &TileMapStorage,
+        center: IVec2,
+        level: u32,
+    ) -> Mesh {
+        let heights = storage
+            .chunk_tiles(center)
+            .into_iter()
+            .chain(storage.chunk_border(center))
+            .map(|tile| {
+                let height = settings.world_height(Hills.height(&TileCoord(tile)));
+                (tile - center, height)
+            })
+            .collect();
+
+        settings.mesh(&heights, level)
+    }
+
+    /// The vertices of the surface of a chunk mesh in world space, by their position on the
+    /// ground in half tiles. The tops of the skirts are the vertices of the edges.
+    fn vertices(mesh: &Mesh, center: IVec2) -> HashMap<IVec2, (f32, Vec3)> {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("The chunk mesh has no positions");
+        };
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("The chunk mesh has no normals");
+        };
+
+        let mut vertices = HashMap::default();
+        for (position, normal) in positions.iter().zip(normals) {
+            if position[1] == SKIRT_BOTTOM {
+                continue;
+            }
+
+            let position = Vec3::from_array(*position) + center.as_vec2().extend(0.0).xzy();
+            let key = (position.xz() * 2.0).round().as_ivec2();
+            let vertex = (position.y, Vec3::from_array(*normal));
+
+            // The tiles of a chunk share their corners
+            let previous = vertices.insert(key, vertex);
+            assert!(previous.is_none_or(|previous| previous == vertex));
+        }
+        vertices
+    }
+
+    #[test]
+    fn test_chunk_mesh_has_every_tile() {
+        let settings = GeometrySettings::new(Vec2::splat(1.0), RADIUS, 20.0);
+        let storage = TileMapStorage::new(Vec2::splat(1.0), RADIUS);
+
+        let mesh = chunk_mesh(&settings, &storage, IVec2::ZERO, 0);
+
+        // One quad per tile, and one skirt quad per tile along the edges
+        let size = (RADIUS * 2 + 1) as usize;
+        assert_eq!(mesh.count_vertices(), size * size * 4 + size * 4 * 4);
+        assert_eq!(vertices(&mesh, IVec2::ZERO).len(), (size + 1) * (size + 1));
+    }
+
+    #[test]
+    fn test_neighbouring_chunks_share_edges() {
+        let settings = GeometrySettings::new(Vec2::splat(1.0), RADIUS, 20.0);
+        let storage = TileMapStorage::new(Vec2::splat(1.0), RADIUS);
+
+        let step = RADIUS as i32 * 2 + 1;
+        let center = IVec2::ZERO;
+        let chunk = vertices(&chunk_mesh(&settings, &storage, center, 0), center);
+
+        let mut heights = chunk
+            .values()
+            .map(|(height, _)| *height as i32)
+            .collect_vec();
+        heights.sort();
+        heights.dedup();
+        assert!(heights.len() > 2, "The terrain of the test is too flat");
+
+        // The edges with the side neighbours, and the corner with the diagonal neighbours
+        for (offset, shared) in [
+            (IVec2::new(1, 0), step as usize + 1),
+            (IVec2::new(-1, 0), step as usize + 1),
+            (IVec2::new(0, 1), step as usize + 1),
+            (IVec2::new(0, -1), step as usize + 1),
+            (IVec2::new(1, 1), 1),
+            (IVec2::new(-1, 1), 1),
+        ] {
+            let neighbour = center + offset * step;
+            let other = vertices(&chunk_mesh(&settings, &storage, neighbour, 0), neighbour);
+
+            let edge = chunk
+                .iter()
+                .filter_map(|(key, vertex)| other.get(key).map(|other| (key, vertex, other)))
+                .collect_vec();
+            assert_eq!(edge.len(), shared, "Gap with the chunk at {neighbour}");
+
+            for (key, vertex, other) in edge {
+                assert_eq!(vertex, other, "Seam at {key} with the chunk at {neighbour}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_coarse_mesh_uses_the_full_detail_corners() {
+        let settings = GeometrySettings::new(Vec2::splat(1.0), RADIUS, 20.0);
+        let storage = TileMapStorage::new(Vec2::splat(1.0), RADIUS);
+
+        let center = IVec2::new(RADIUS as i32 * 2 + 1, 0);
+        let fine = vertices(&chunk_mesh(&settings, &storage, center, 0), center);
+
+        // The corner lines 0, 2, 4, 6 and the last one 7
+        let mesh = chunk_mesh(&settings, &storage, center, 1);
+        assert_eq!(mesh.count_vertices(), 4 * 4 * 4 + 4 * 4 * 4);
+
+        let coarse = vertices(&mesh, center);
+        assert_eq!(coarse.len(), 5 * 5);
+        for (key, vertex) in coarse {
+            assert_eq!(
+                fine.get(&key),
+                Some(&vertex),
+                "Corner {key} is not on the terrain"
+            );
+        }
 
-        commands.entity(chunk_entity).with_children(|parent| {
-            parent.spawn((Name::new("Chunk Mesh"), ChunkMesh(mesh.clone())));
-        });
+        // The coarsest level only keeps the corners of the chunk
+        let mesh = chunk_mesh(&settings, &storage, center, 10);
+        assert_eq!(vertices(&mesh, center).len(), 4);
     }
 }
@@ -0,0 +1,194 @@
+//! Level of detail of the terrain chunks.
+//!
+//! The chunks close to a `TerrainViewer` are meshed with one quad per tile and have a collider.
+//! The chunks further away are meshed by sampling every 2nd, 4th, ... tile corner and have no
+//! collider. When the terrain is not rendered, like on a dedicated server, only the chunks with a
+//! collider are meshed at all.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::prelude::*;
+
+pub struct TerrainLodPlugin;
+
+impl Plugin for TerrainLodPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TerrainViewer>()
+            .register_type::<TerrainLodSettings>()
+            .register_type::<ChunkLod>();
+
+        app.init_resource::<TerrainLodSettings>()
+            .add_systems(Update, update_chunk_lod);
+    }
+}
+
+/// The entities around which the terrain is meshed at full detail and has colliders, like the
+/// player characters. The world origin is used while there is no viewer.
+#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+pub struct TerrainViewer;
+
+/// The distances of the levels of detail of the terrain, in chunks from the closest viewer.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct TerrainLodSettings {
+    /// The distances after which each next level of detail is used. The chunks up to the first
+    /// distance have one quad per tile, the next levels sample every 2nd, 4th, ... tile.
+    pub distances: Vec<u32>,
+    /// The distance up to which the chunks have a collider. These chunks are always meshed at
+    /// full detail.
+    pub collider_distance: u32,
+}
+
+impl Default for TerrainLodSettings {
+    fn default() -> Self {
+        Self {
+            distances: vec![2, 4],
+            collider_distance: 1,
+        }
+    }
+}
+
+impl TerrainLodSettings {
+    /// The level of detail of a chunk at the given distance in chunks from the closest viewer.
+    pub fn lod(&self, distance: u32) -> ChunkLod {
+        let collider = distance <= self.collider_distance;
+        let level = if collider {
+            0
+        } else {
+            self.distances.iter().filter(|d| distance > **d).count() as u32
+        };
+
+        ChunkLod { level, collider }
+    }
+}
+
+/// The level of detail of a chunk, updated when the viewers move.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub struct ChunkLod {
+    /// The mesh of the chunk samples every `2^level` tile corner.
+    pub level: u32,
+    /// Whether the chunk has a collider.
+    pub collider: bool,
+}
+
+/// Marks that the terrain meshes are rendered, so the chunks without a collider are still meshed.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub(super) struct TerrainRendered;
+
+pub(super) fn update_chunk_lod(
+    mut commands: Commands,
+    settings: Res<TerrainLodSettings>,
+    storage: Res<TileMapStorage>,
+    q_viewers: Query<(Option<&Position>, Option<&GlobalTransform>), With<TerrainViewer>>,
+    q_chunks: Query<(Entity, &ChunkCoord, Option<&ChunkLod>)>,
+) {
+    // The transforms of the physics bodies are not synced on the server, so their position is
+    // used instead, and the transform is only used by the viewers without physics
+    let mut viewers = q_viewers
+        .iter()
+        .filter_map(|(position, transform)| {
+            position
+                .map(|position| position.0)
+                .or_else(|| transform.map(GlobalTransform::translation))
+        })
+        .map(|translation| {
+            let tile = storage.world_pos_to_tile(translation.xz());
+            storage.tile_to_center(&tile)
+        })
+        .collect::<Vec<_>>();
+    if viewers.is_empty() {
+        viewers.push(IVec2::ZERO);
+    }
+
+    let step = storage.chunk_size() as i32;
+    for (entity, ChunkCoord(center), current) in q_chunks.iter() {
+        let distance = viewers
+            .iter()
+            .map(|viewer| ((*center - *viewer).abs().max_element() / step) as u32)
+            .min()
+            .unwrap_or_default();
+
+        let lod = settings.lod(distance);
+        if current != Some(&lod) {
+            commands.entity(entity).insert(lod);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lod_by_distance() {
+        let settings = TerrainLodSettings::default();
+
+        let levels = (0..=6)
+            .map(|distance| settings.lod(distance))
+            .map(|lod| (lod.level, lod.collider))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            levels,
+            [
+                (0, true),
+                (0, true),
+                (0, false),
+                (1, false),
+                (1, false),
+                (2, false),
+                (2, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colliders_follow_the_viewer() {
+        let mut app = App::new();
+        app.insert_resource(TileMapStorage::new(Vec2::splat(1.0), 2))
+            .init_resource::<TerrainLodSettings>()
+            .add_systems(Update, update_chunk_lod);
+
+        let near = app.world_mut().spawn(ChunkCoord(IVec2::ZERO)).id();
+        let far = app.world_mut().spawn(ChunkCoord(IVec2::new(10, 0))).id();
+        // The transform is stale, like on a server without the transform sync of the physics
+        let viewer = app
+            .world_mut()
+            .spawn((
+                TerrainViewer,
+                Position(Vec3::ZERO),
+                GlobalTransform::IDENTITY,
+            ))
+            .id();
+
+        let collider =
+            |app: &App, chunk: Entity| app.world().get::<ChunkLod>(chunk).unwrap().collider;
+
+        app.update();
+        assert!(collider(&app, near));
+        assert!(!collider(&app, far));
+
+        app.world_mut().get_mut::<Position>(viewer).unwrap().0 = Vec3::new(10.0, 0.0, 0.0);
+        app.update();
+        assert!(!collider(&app, near));
+        assert!(collider(&app, far));
+    }
+
+    #[test]
+    fn test_colliders_are_full_detail() {
+        let settings = TerrainLodSettings {
+            distances: vec![0],
+            collider_distance: 2,
+        };
+
+        assert_eq!(
+            settings.lod(2),
+            ChunkLod {
+                level: 0,
+                collider: true
+            }
+        );
+        assert_eq!(settings.lod(3).level, 1);
+    }
+}
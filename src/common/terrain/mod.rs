@@ -3,11 +3,16 @@
 //! component. Instead of having it here, it should be somehow stored in the `common` crate, but
 //! not sure yet how to achieve that level of genericity.
 
+mod climate;
 mod collider;
 mod components;
+mod edit;
 mod generation;
+mod generator;
 mod geometry;
+mod lod;
 mod planet;
+mod preset;
 mod render;
 mod resources;
 
@@ -16,10 +21,21 @@ use bevy::prelude::*;
 use self::collider::TerrainColliderPlugin;
 use self::generation::TerrainGenerationPlugin;
 use self::geometry::TerrainGeometryPlugin;
+use self::lod::TerrainLodPlugin;
 
 pub mod prelude {
-    pub use super::components::{Tile, TileWorldHeight};
+    pub use super::climate::PlanetClimate;
+    pub use super::components::{
+        Tile, TileMoisture, TileNoiseHeight, TileRiver, TileTemperature, TileWorldHeight,
+    };
+    pub use super::edit::{TerrainEdit, TerrainEditKind, TerrainEdits, TERRAIN_EDIT_STEP};
     pub use super::generation::{TerrainGenerationProgress, TerrainGenerationSeed};
+    pub use super::generator::{
+        ActiveTerrainGenerator, TerrainGenerator, TerrainGeneratorFactory, TerrainGeneratorKind,
+        TerrainGenerators,
+    };
+    pub use super::lod::{ChunkLod, TerrainLodSettings, TerrainViewer};
+    pub use super::preset::TerrainGenerationPreset;
     pub use super::render::TerrainRenderPlugin;
     pub use super::resources::*;
     pub use super::TerrainPlugin;
@@ -60,7 +76,12 @@ impl Plugin for TerrainPlugin {
             self.tile_size,
             self.chunk_radius,
         ));
-        app.add_plugins(TerrainGeometryPlugin::new(self.tile_size, self.max_height));
+        app.add_plugins(TerrainGeometryPlugin::new(
+            self.tile_size,
+            self.chunk_radius,
+            self.max_height,
+        ));
         app.add_plugins(TerrainColliderPlugin);
+        app.add_plugins(TerrainLodPlugin);
     }
 }
@@ -1,7 +1,12 @@
-use bevy::prelude::*;
-use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use std::rc::Rc;
 
-use super::components::*;
+use noise::{
+    core::worley::ReturnType, Add, Billow, Blend, Cache, Clamp, Constant, Curve, Exponent, Fbm,
+    Max, Min, MultiFractal, Multiply, NoiseFn, Perlin, RidgedMulti, ScaleBias, ScalePoint,
+    Seedable, Select, Terrace, Turbulence, Worley,
+};
+
+use super::{components::*, preset::*};
 use crate::common::prelude::*;
 
 /// Planet seed. Change this to generate a different planet.
@@ -92,10 +97,20 @@ const MOUNTAIN_GLACIATION: f64 = 1.375;
 /// elevation units.
 const CONTINENT_HEIGHT_SCALE: f64 = (1.0 - SEA_LEVEL) / 4.0;
 
+/// Scale applied to the input of the mountains, hills, plains and badlands.
+/// The frequencies of these terrains are tuned for a whole planet, so they
+/// have to be scaled down to produce features that span several tiles.
+const TERRAIN_DETAIL_SCALE: f64 = 1.0 / 64.0;
+
 /// Maximum depth of the rivers, in planetary elevation units.
 const RIVER_DEPTH: f64 = 0.0234375;
 
-#[derive(Resource, Clone, Copy, Debug)]
+/// Output value of the river positions below which a tile is part of a
+/// river bed. Only the large rivers are deep enough to reach it, the small
+/// rivers just carve shallow valleys.
+const RIVER_BED: f64 = -1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct PlanetHeight {
     seed: u32,
     zoom_scale: f64,
@@ -116,6 +131,7 @@ pub struct PlanetHeight {
     terrain_offset: f64,
     mountain_glaciation: f64,
     continent_height_scale: f64,
+    terrain_detail_scale: f64,
     river_depth: f64,
 }
 
@@ -141,6 +157,7 @@ impl Default for PlanetHeight {
             terrain_offset: TERRAIN_OFFSET,
             mountain_glaciation: MOUNTAIN_GLACIATION,
             continent_height_scale: CONTINENT_HEIGHT_SCALE,
+            terrain_detail_scale: TERRAIN_DETAIL_SCALE,
             river_depth: RIVER_DEPTH,
         }
     }
@@ -151,26 +168,94 @@ impl PlanetHeight {
         self.seed = seed;
         self
     }
-}
 
-impl ChunkMapFunction<TileCoord, TileNoiseHeight> for PlanetHeight {
-    fn get(&self, point: TileCoord) -> TileNoiseHeight {
-        _ = self.mountain_lacunarity; // Silence unused warning
-        _ = self.hills_lacunarity; // Silence unused warning
-        _ = self.plains_lacunarity; // Silence unused warning
-        _ = self.badlands_lacunarity; // Silence unused warning
-        _ = self.mountains_twist; // Silence unused warning
-        _ = self.hills_twist; // Silence unused warning
-        _ = self.badlands_twist; // Silence unused warning
-        _ = self.shelf_level; // Silence unused warning
-        _ = self.mountain_glaciation; // Silence unused warning
-        _ = self.river_depth; // Silence unused warning
-        _ = self.terrain_offset; // Silence unused warning
-        _ = self.hills_amount; // Silence unused warning
-        _ = self.mountains_amount; // Silence unused warning
-        _ = self.badlands_amount; // Silence unused warning
-        _ = self.continent_height_scale; // Silence unused warning
+    pub fn with_preset(mut self, preset: &TerrainGenerationPreset) -> Self {
+        self.zoom_scale = preset.zoom_scale;
+        self.continent_frequency = preset.continent_frequency;
+        self.continent_lacunarity = preset.continent_lacunarity;
+        self.mountain_lacunarity = preset.mountain_lacunarity;
+        self.hills_lacunarity = preset.hills_lacunarity;
+        self.plains_lacunarity = preset.plains_lacunarity;
+        self.badlands_lacunarity = preset.badlands_lacunarity;
+        self.sea_level = preset.sea_level;
+        self.shelf_level = preset.shelf_level;
+        self.mountains_amount = preset.mountains_amount;
+        self.hills_amount = preset.hills_amount;
+        self.badlands_amount = preset.badlands_amount;
+        self.continent_height_scale = (1.0 - preset.sea_level) / 4.0;
+        self.river_depth = preset.river_depth;
+        self
+    }
 
+    /// The river positions group. The output value is 1.0 away from the
+    /// rivers and goes down to -1.5 at the bottom of the large rivers. The
+    /// rivers only depend on the position, so they are continuous across the
+    /// chunks.
+    fn river_positions(&self) -> impl NoiseFn<f64, 2> {
+        // 1: [Large-river-basis module]: This ridged-multifractal-noise function
+        // creates the large, deep rivers.
+        let river_positions_rm0 = RidgedMulti::<Perlin>::new(self.seed + 100)
+            .set_frequency(18.75)
+            .set_lacunarity(self.continent_lacunarity)
+            .set_octaves(1);
+
+        // 2: [Large-river-curve module]: This curve module inverts the ridges
+        // of the large-river-basis module, which creates the rivers. It also
+        // compresses the edge of the rivers, so the banks are steep.
+        let river_positions_cu0 = Curve::new(river_positions_rm0)
+            .add_control_point(-2.000, 2.000)
+            .add_control_point(-1.000, 1.000)
+            .add_control_point(-0.125, 0.875)
+            .add_control_point(0.000, -1.000)
+            .add_control_point(1.000, -1.500)
+            .add_control_point(2.000, -2.000);
+
+        // 3: [Small-river-basis module]: This ridged-multifractal-noise function
+        // creates the small, shallow rivers.
+        let river_positions_rm1 = RidgedMulti::<Perlin>::new(self.seed + 101)
+            .set_frequency(43.25)
+            .set_lacunarity(self.continent_lacunarity)
+            .set_octaves(1);
+
+        // 4: [Small-river-curve module]: Same as step 2 for the small rivers,
+        // which never get deeper than the large ones.
+        let river_positions_cu1 = Curve::new(river_positions_rm1)
+            .add_control_point(-2.000, 2.0000)
+            .add_control_point(-1.000, 1.5000)
+            .add_control_point(-0.125, 1.4375)
+            .add_control_point(0.000, 0.5000)
+            .add_control_point(1.000, 0.2500)
+            .add_control_point(2.000, 0.0000);
+
+        // 5: [Combined-rivers module]: The small rivers cut into the large ones.
+        let river_positions_mi = Min::new(river_positions_cu0, river_positions_cu1);
+
+        // 6: [Warped-rivers module]: This turbulence module twists the rivers.
+        Turbulence::<_, Perlin>::new(river_positions_mi)
+            .set_seed(self.seed + 102)
+            .set_frequency(9.25)
+            .set_power(1.0 / 57.75)
+            .set_roughness(6)
+    }
+
+    /// Map the planetary elevation to the 0..1 range used by the tiles. The
+    /// sea level is mapped to 0.5 and the land is stretched so that the
+    /// highest mountains reach the top of the range.
+    fn normalize(&self, elevation: f64) -> f64 {
+        let elevation = if elevation < self.sea_level {
+            (elevation + 1.0) / (self.sea_level + 1.0) * 0.5
+        } else {
+            0.5 + (elevation - self.sea_level) / (self.continent_height_scale * 4.0)
+        };
+
+        elevation.clamp(0.0, 1.0)
+    }
+}
+
+impl PlanetHeight {
+    /// Build the noise graph of the planet elevation. The output is the
+    /// planetary elevation, see `normalize` for the range used by the tiles.
+    fn elevation(&self) -> SharedNoise {
         // Example taken from
         // <https://github.com/Razaekel/noise-rs/blob/develop/examples/complexplanet.rs>
 
@@ -186,7 +271,7 @@ impl ChunkMapFunction<TileCoord, TileNoiseHeight> for PlanetHeight {
         // 2: [Continent-with-ranges module]: Next, a curve module modifies the
         // output value from the continent module so that very high values appear
         // near sea level. This defines the positions of the mountain ranges.
-        let base_continent_def_cu = noise::Curve::new(base_continent_def_fb0)
+        let base_continent_def_cu = Curve::new(base_continent_def_fb0)
             .add_control_point(-2.0000 + self.sea_level, -1.625 + self.sea_level)
             .add_control_point(-1.0000 + self.sea_level, -1.375 + self.sea_level)
             .add_control_point(0.0000 + self.sea_level, -0.375 + self.sea_level)
@@ -211,7 +296,7 @@ impl ChunkMapFunction<TileCoord, TileNoiseHeight> for PlanetHeight {
         // 4: [Scaled-carver module]: This scale/bias module scales the output
         // value from the carver module such that it is usually near 1.0. This
         // is required for step 5.
-        let base_continent_def_sb = noise::ScaleBias::new(base_continent_def_fb1)
+        let base_continent_def_sb = ScaleBias::new(base_continent_def_fb1)
             .set_scale(0.375)
             .set_bias(0.625);
 
@@ -225,21 +310,525 @@ impl ChunkMapFunction<TileCoord, TileNoiseHeight> for PlanetHeight {
         // the output from the scaled-carver module will be less than the output
         // value from the continent-with-ranges module, so in this case, the output
         // value from the scaled-carver module is selected.
-        let base_continent_def_mi = noise::Min::new(base_continent_def_sb, base_continent_def_cu);
+        let base_continent_def_mi = Min::new(base_continent_def_sb, base_continent_def_cu);
 
         // 6: [Clamped-continent module]: Finally, a clamp module modifies the
         // carved continent module to ensure that the output value of this subgroup
         // is between -1.0 and 1.0.
-        let base_continent_def_cl = noise::Clamp::new(base_continent_def_mi).set_bounds(-1.0, 1.0);
+        let base_continent_def_cl = Clamp::new(base_continent_def_mi).set_bounds(-1.0, 1.0);
 
         // 7: [Base-continent-definition subgroup]: Caches the output value from
         // the clamped-continent module.
-        let base_continent_def = noise::Cache::new(base_continent_def_cl);
+        let base_continent_def = SharedNoise::new(base_continent_def_cl);
+
+        // 8: [Continent definition]: Three turbulence modules warp the base
+        // continent definition so that the coastlines become more rugged, the
+        // warped output is only used above the sea level.
+        let continent_def_tu0 = Turbulence::<_, Perlin>::new(base_continent_def.clone())
+            .set_seed(self.seed + 10)
+            .set_frequency(self.continent_frequency * 15.25)
+            .set_power(self.continent_frequency / 113.75)
+            .set_roughness(13);
+        let continent_def_tu1 = Turbulence::<_, Perlin>::new(continent_def_tu0)
+            .set_seed(self.seed + 11)
+            .set_frequency(self.continent_frequency * 47.25)
+            .set_power(self.continent_frequency / 433.75)
+            .set_roughness(12);
+        let continent_def_tu2 = Turbulence::<_, Perlin>::new(continent_def_tu1)
+            .set_seed(self.seed + 12)
+            .set_frequency(self.continent_frequency * 95.25)
+            .set_power(self.continent_frequency / 1019.75)
+            .set_roughness(11);
+        let continent_def_se = Select::new(
+            base_continent_def.clone(),
+            continent_def_tu2,
+            base_continent_def.clone(),
+        )
+        .set_bounds(self.sea_level - 0.0375, self.sea_level + 1000.0375)
+        .set_falloff(0.0625);
+        let continent_def = SharedNoise::new(continent_def_se);
+
+        // 9: [Terrain type definition]: A warped and terraced copy of the
+        // continent definition selects where the rough terrains (hills and
+        // mountains) appear, `terrain_offset` controls the warping.
+        let terrain_type_def_tu = Turbulence::<_, Perlin>::new(continent_def.clone())
+            .set_seed(self.seed + 20)
+            .set_frequency(self.continent_frequency * 18.125)
+            .set_power(self.continent_frequency / 20.59375 * self.terrain_offset)
+            .set_roughness(3);
+        let terrain_type_def_te = Terrace::new(terrain_type_def_tu)
+            .add_control_point(-1.00)
+            .add_control_point(self.shelf_level + self.sea_level / 2.0)
+            .add_control_point(1.00);
+        let terrain_type_def = SharedNoise::new(terrain_type_def_te);
+
+        // 10: [Mountainous terrain]: Ridged multifractal mountains with a twisted
+        // base, high peaks and low valleys, glaciated by `mountain_glaciation`.
+        let mountain_base_def_rm0 = RidgedMulti::<Perlin>::new(self.seed + 30)
+            .set_frequency(1723.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(4);
+        let mountain_base_def_sb0 = ScaleBias::new(mountain_base_def_rm0)
+            .set_scale(0.5)
+            .set_bias(0.375);
+        let mountain_base_def_rm1 = RidgedMulti::<Perlin>::new(self.seed + 31)
+            .set_frequency(367.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(1);
+        let mountain_base_def_sb1 = ScaleBias::new(mountain_base_def_rm1)
+            .set_scale(-2.0)
+            .set_bias(-0.5);
+        let mountain_base_def_co = Constant::new(-1.0);
+        let mountain_base_def_bl = Blend::new(
+            mountain_base_def_co,
+            mountain_base_def_sb0,
+            mountain_base_def_sb1,
+        );
+        let mountain_base_def_tu0 = Turbulence::<_, Perlin>::new(mountain_base_def_bl)
+            .set_seed(self.seed + 32)
+            .set_frequency(1337.0)
+            .set_power(1.0 / 6730.0 * self.mountains_twist)
+            .set_roughness(4);
+        let mountain_base_def_tu1 = Turbulence::<_, Perlin>::new(mountain_base_def_tu0)
+            .set_seed(self.seed + 33)
+            .set_frequency(21221.0)
+            .set_power(1.0 / 120157.0 * self.mountains_twist)
+            .set_roughness(6);
+        let mountain_base_def = SharedNoise::new(mountain_base_def_tu1);
+
+        let mountainous_high_rm0 = RidgedMulti::<Perlin>::new(self.seed + 40)
+            .set_frequency(2371.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(3);
+        let mountainous_high_rm1 = RidgedMulti::<Perlin>::new(self.seed + 41)
+            .set_frequency(2341.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(3);
+        let mountainous_high_ma = Max::new(mountainous_high_rm0, mountainous_high_rm1);
+        let mountainous_high = Turbulence::<_, Perlin>::new(mountainous_high_ma)
+            .set_seed(self.seed + 42)
+            .set_frequency(31511.0)
+            .set_power(1.0 / 180371.0 * self.mountains_twist)
+            .set_roughness(4);
+
+        let mountainous_low_rm0 = RidgedMulti::<Perlin>::new(self.seed + 50)
+            .set_frequency(1381.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(8);
+        let mountainous_low_rm1 = RidgedMulti::<Perlin>::new(self.seed + 51)
+            .set_frequency(1427.0)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(8);
+        let mountainous_low = Multiply::new(mountainous_low_rm0, mountainous_low_rm1);
+
+        let mountainous_terrain_sb0 = ScaleBias::new(mountainous_low)
+            .set_scale(0.03125)
+            .set_bias(-0.96875);
+        let mountainous_terrain_sb1 = ScaleBias::new(mountainous_high)
+            .set_scale(0.25)
+            .set_bias(0.25);
+        let mountainous_terrain_ad = Add::new(mountainous_terrain_sb1, mountain_base_def.clone());
+        let mountainous_terrain_se = Select::new(
+            mountainous_terrain_sb0,
+            mountainous_terrain_ad,
+            mountain_base_def.clone(),
+        )
+        .set_bounds(-0.5, 999.5)
+        .set_falloff(0.5);
+        let mountainous_terrain_sb2 = ScaleBias::new(mountainous_terrain_se)
+            .set_scale(0.8)
+            .set_bias(0.0);
+        let mountainous_terrain =
+            Exponent::new(mountainous_terrain_sb2).set_exponent(self.mountain_glaciation);
+
+        // 11: [Hilly terrain]: Billowing hills with ridged river valleys.
+        let hilly_terrain_bi = Billow::<Perlin>::new(self.seed + 60)
+            .set_frequency(1663.0)
+            .set_persistence(0.5)
+            .set_lacunarity(self.hills_lacunarity)
+            .set_octaves(6);
+        let hilly_terrain_sb0 = ScaleBias::new(hilly_terrain_bi)
+            .set_scale(0.5)
+            .set_bias(0.5);
+        let hilly_terrain_rm = RidgedMulti::<Perlin>::new(self.seed + 61)
+            .set_frequency(367.5)
+            .set_lacunarity(self.hills_lacunarity)
+            .set_octaves(1);
+        let hilly_terrain_sb1 = ScaleBias::new(hilly_terrain_rm)
+            .set_scale(-2.0)
+            .set_bias(-1.0);
+        let hilly_terrain_co = Constant::new(-1.0);
+        let hilly_terrain_bl = Blend::new(hilly_terrain_co, hilly_terrain_sb1, hilly_terrain_sb0);
+        let hilly_terrain_sb2 = ScaleBias::new(hilly_terrain_bl)
+            .set_scale(0.75)
+            .set_bias(-0.25);
+        let hilly_terrain_ex = Exponent::new(hilly_terrain_sb2).set_exponent(1.375);
+        let hilly_terrain_tu0 = Turbulence::<_, Perlin>::new(hilly_terrain_ex)
+            .set_seed(self.seed + 62)
+            .set_frequency(1531.0)
+            .set_power(1.0 / 16921.0 * self.hills_twist)
+            .set_roughness(4);
+        let hilly_terrain = Turbulence::<_, Perlin>::new(hilly_terrain_tu0)
+            .set_seed(self.seed + 63)
+            .set_frequency(21617.0)
+            .set_power(1.0 / 117529.0 * self.hills_twist)
+            .set_roughness(6);
+
+        // 12: [Plains terrain]: Two multiplied billow modules produce mostly flat
+        // terrain with a few low bumps.
+        let plains_terrain_bi0 = Billow::<Perlin>::new(self.seed + 70)
+            .set_frequency(1097.5)
+            .set_persistence(0.5)
+            .set_lacunarity(self.plains_lacunarity)
+            .set_octaves(8);
+        let plains_terrain_sb0 = ScaleBias::new(plains_terrain_bi0)
+            .set_scale(0.5)
+            .set_bias(0.5);
+        let plains_terrain_bi1 = Billow::<Perlin>::new(self.seed + 71)
+            .set_frequency(1097.5)
+            .set_persistence(0.5)
+            .set_lacunarity(self.plains_lacunarity)
+            .set_octaves(8);
+        let plains_terrain_sb1 = ScaleBias::new(plains_terrain_bi1)
+            .set_scale(0.5)
+            .set_bias(0.5);
+        let plains_terrain_mu = Multiply::new(plains_terrain_sb0, plains_terrain_sb1);
+        let plains_terrain = ScaleBias::new(plains_terrain_mu)
+            .set_scale(2.0)
+            .set_bias(-1.0);
+
+        // 13: [Badlands terrain]: Sand dunes and terraced cliffs.
+        let badlands_sand_rm = RidgedMulti::<Perlin>::new(self.seed + 80)
+            .set_frequency(6163.5)
+            .set_lacunarity(self.badlands_lacunarity)
+            .set_octaves(1);
+        let badlands_sand_sb0 = ScaleBias::new(badlands_sand_rm)
+            .set_scale(0.875)
+            .set_bias(0.0);
+        let badlands_sand_wo = Worley::new(self.seed + 81)
+            .set_frequency(16183.25)
+            .set_return_type(ReturnType::Distance);
+        let badlands_sand_sb1 = ScaleBias::new(badlands_sand_wo)
+            .set_scale(0.25)
+            .set_bias(0.25);
+        let badlands_sand = Add::new(badlands_sand_sb0, badlands_sand_sb1);
+
+        let badlands_cliffs_fb = Fbm::<Perlin>::new(self.seed + 90)
+            .set_frequency(self.continent_frequency * 839.0)
+            .set_persistence(0.5)
+            .set_lacunarity(self.badlands_lacunarity)
+            .set_octaves(6);
+        let badlands_cliffs_cu = Curve::new(badlands_cliffs_fb)
+            .add_control_point(-2.000, -2.000)
+            .add_control_point(-1.000, -1.000)
+            .add_control_point(-0.000, -0.750)
+            .add_control_point(0.500, -0.250)
+            .add_control_point(0.625, 0.875)
+            .add_control_point(0.750, 1.000)
+            .add_control_point(2.000, 1.250);
+        let badlands_cliffs_cl = Clamp::new(badlands_cliffs_cu).set_bounds(-999.125, 0.875);
+        let badlands_cliffs_te = Terrace::new(badlands_cliffs_cl)
+            .add_control_point(-1.000)
+            .add_control_point(-0.875)
+            .add_control_point(-0.750)
+            .add_control_point(-0.500)
+            .add_control_point(0.000)
+            .add_control_point(1.000);
+        let badlands_cliffs_tu0 = Turbulence::<_, Perlin>::new(badlands_cliffs_te)
+            .set_seed(self.seed + 91)
+            .set_frequency(16111.0)
+            .set_power(1.0 / 141539.0 * self.badlands_twist)
+            .set_roughness(3);
+        let badlands_cliffs = Turbulence::<_, Perlin>::new(badlands_cliffs_tu0)
+            .set_seed(self.seed + 92)
+            .set_frequency(36107.0)
+            .set_power(1.0 / 211543.0 * self.badlands_twist)
+            .set_roughness(3);
+
+        let badlands_terrain_sb = ScaleBias::new(badlands_sand)
+            .set_scale(0.25)
+            .set_bias(-0.75);
+        let badlands_terrain = Max::new(badlands_cliffs, badlands_terrain_sb);
+
+        // 14: [Scaled terrains]: Every terrain is scaled to the height it adds on
+        // top of the continents. The terrains are tuned for a whole planet, so
+        // their input is scaled by `terrain_detail_scale` to span several tiles.
+        let scaled_mountainous_terrain_sb0 = ScaleBias::new(
+            ScalePoint::new(mountainous_terrain).set_scale(self.terrain_detail_scale),
+        )
+        .set_scale(0.125)
+        .set_bias(0.125);
+        let scaled_mountainous_terrain_fb = Fbm::<Perlin>::new(self.seed + 110)
+            .set_frequency(14.5)
+            .set_persistence(0.5)
+            .set_lacunarity(self.mountain_lacunarity)
+            .set_octaves(6);
+        let scaled_mountainous_terrain_ex =
+            Exponent::new(scaled_mountainous_terrain_fb).set_exponent(1.25);
+        let scaled_mountainous_terrain_sb1 = ScaleBias::new(scaled_mountainous_terrain_ex)
+            .set_scale(0.25)
+            .set_bias(1.0);
+        let scaled_mountainous_terrain = Multiply::new(
+            scaled_mountainous_terrain_sb0,
+            scaled_mountainous_terrain_sb1,
+        );
+
+        let scaled_hilly_terrain_sb0 =
+            ScaleBias::new(ScalePoint::new(hilly_terrain).set_scale(self.terrain_detail_scale))
+                .set_scale(0.0625)
+                .set_bias(0.0625);
+        let scaled_hilly_terrain_fb = Fbm::<Perlin>::new(self.seed + 120)
+            .set_frequency(13.5)
+            .set_persistence(0.5)
+            .set_lacunarity(self.hills_lacunarity)
+            .set_octaves(6);
+        let scaled_hilly_terrain_ex = Exponent::new(scaled_hilly_terrain_fb).set_exponent(1.25);
+        let scaled_hilly_terrain_sb1 = ScaleBias::new(scaled_hilly_terrain_ex)
+            .set_scale(0.5)
+            .set_bias(1.5);
+        let scaled_hilly_terrain =
+            Multiply::new(scaled_hilly_terrain_sb0, scaled_hilly_terrain_sb1);
+
+        let scaled_plains_terrain =
+            ScaleBias::new(ScalePoint::new(plains_terrain).set_scale(self.terrain_detail_scale))
+                .set_scale(0.00390625)
+                .set_bias(0.0078125);
+
+        let scaled_badlands_terrain =
+            ScaleBias::new(ScalePoint::new(badlands_terrain).set_scale(self.terrain_detail_scale))
+                .set_scale(0.0625)
+                .set_bias(0.0625);
+
+        // 15: [Final planet]: The continental shelf is added below the sea level
+        // and the terrains are layered on top of the continents, plains first,
+        // then hills, mountains and badlands, using the terrain type definition.
+        let continental_shelf_te = Terrace::new(continent_def.clone())
+            .add_control_point(-1.0)
+            .add_control_point(-0.75)
+            .add_control_point(self.shelf_level)
+            .add_control_point(1.0);
+        let continental_shelf_cl =
+            Clamp::new(continental_shelf_te).set_bounds(-0.75, self.sea_level);
+        let continental_shelf_rm = RidgedMulti::<Perlin>::new(self.seed + 130)
+            .set_frequency(self.continent_frequency * 4.375)
+            .set_lacunarity(self.continent_lacunarity)
+            .set_octaves(16);
+        let continental_shelf_sb = ScaleBias::new(continental_shelf_rm)
+            .set_scale(-0.125)
+            .set_bias(-0.125);
+        let continental_shelf = Add::new(continental_shelf_sb, continental_shelf_cl);
+
+        let base_continent_elev_sb = ScaleBias::new(continent_def.clone())
+            .set_scale(self.continent_height_scale)
+            .set_bias(0.0);
+        let base_continent_elev_se = Select::new(
+            base_continent_elev_sb,
+            continental_shelf,
+            continent_def.clone(),
+        )
+        .set_bounds(self.shelf_level - 1000.0, self.shelf_level)
+        .set_falloff(0.03125);
+        let base_continent_elev = SharedNoise::new(base_continent_elev_se);
+
+        let continents_with_plains =
+            SharedNoise::new(Add::new(base_continent_elev.clone(), scaled_plains_terrain));
+
+        let continents_with_hills_ad = Add::new(base_continent_elev.clone(), scaled_hilly_terrain);
+        let continents_with_hills = Select::new(
+            continents_with_plains.clone(),
+            continents_with_hills_ad,
+            terrain_type_def.clone(),
+        )
+        .set_bounds(1.0 - self.hills_amount, 1001.0 - self.hills_amount)
+        .set_falloff(0.25);
+
+        let continents_with_mountains_ad0 =
+            Add::new(base_continent_elev.clone(), scaled_mountainous_terrain);
+        let continents_with_mountains_cu = Curve::new(continent_def.clone())
+            .add_control_point(-1.0, -0.0625)
+            .add_control_point(0.0, 0.0000)
+            .add_control_point(1.0 - self.mountains_amount, 0.0625)
+            .add_control_point(1.0, 0.2500);
+        let continents_with_mountains_ad1 =
+            Add::new(continents_with_mountains_ad0, continents_with_mountains_cu);
+        let continents_with_mountains_se = Select::new(
+            continents_with_hills,
+            continents_with_mountains_ad1,
+            terrain_type_def.clone(),
+        )
+        .set_bounds(1.0 - self.mountains_amount, 1001.0 - self.mountains_amount)
+        .set_falloff(0.25);
+        let continents_with_mountains = SharedNoise::new(continents_with_mountains_se);
+
+        let continents_with_badlands_bm = Fbm::<Perlin>::new(self.seed + 140)
+            .set_frequency(16.5)
+            .set_persistence(0.5)
+            .set_lacunarity(self.continent_lacunarity)
+            .set_octaves(2);
+        let continents_with_badlands_ad =
+            Add::new(base_continent_elev.clone(), scaled_badlands_terrain);
+        let continents_with_badlands_se = Select::new(
+            continents_with_mountains.clone(),
+            continents_with_badlands_ad,
+            continents_with_badlands_bm,
+        )
+        .set_bounds(1.0 - self.badlands_amount, 1001.0 - self.badlands_amount)
+        .set_falloff(0.25);
+        let continents_with_badlands = SharedNoise::new(Max::new(
+            continents_with_mountains.clone(),
+            continents_with_badlands_se,
+        ));
+
+        // 16: [Continents with rivers]: The rivers are carved out of the
+        // continents by up to `river_depth`. The rivers are deep near the sea
+        // level and get shallower in higher terrain.
+        let continents_with_rivers_sb = ScaleBias::new(self.river_positions())
+            .set_scale(self.river_depth / 2.0)
+            .set_bias(-self.river_depth / 2.0);
+        let continents_with_rivers_ad =
+            Add::new(continents_with_badlands.clone(), continents_with_rivers_sb);
+        let continents_with_rivers = Select::new(
+            continents_with_badlands.clone(),
+            continents_with_rivers_ad,
+            continents_with_badlands.clone(),
+        )
+        .set_bounds(self.sea_level, self.continent_height_scale + self.sea_level)
+        .set_falloff(self.continent_height_scale - self.sea_level);
+
+        SharedNoise::new(continents_with_rivers)
+    }
+}
+
+impl PlanetHeight {
+    /// The noise graphs of the planet. Building them is much slower than
+    /// sampling them, so they are built once per thread and reused by the
+    /// tasks of every chunk until the planet changes.
+    fn noise(&self) -> Rc<PlanetHeightNoise> {
+        PLANET_HEIGHT_NOISE.with(|cache| {
+            cache.get_or_build(*self, |planet| PlanetHeightNoise {
+                elevation: planet.elevation(),
+                rivers: SharedNoise::new(planet.river_positions()),
+            })
+        })
+    }
+
+    fn sample_point(&self, point: &TileCoord) -> [f64; 2] {
+        [
+            point.x as f64 * self.zoom_scale,
+            point.y as f64 * self.zoom_scale,
+        ]
+    }
+}
+
+thread_local! {
+    static PLANET_HEIGHT_NOISE: ChunkMapCache<PlanetHeight, PlanetHeightNoise> =
+        const { ChunkMapCache::new() };
+}
+
+struct PlanetHeightNoise {
+    elevation: SharedNoise,
+    rivers: SharedNoise,
+}
+
+/// A cached noise function that is shared by several modules of the graph.
+#[derive(Clone)]
+struct SharedNoise(Rc<dyn NoiseFn<f64, 2>>);
+
+impl SharedNoise {
+    fn new(source: impl NoiseFn<f64, 2> + 'static) -> Self {
+        Self(Rc::new(Cache::new(source)))
+    }
+}
+
+impl NoiseFn<f64, 2> for SharedNoise {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        self.0.get(point)
+    }
+}
+
+impl ChunkMapFunction<TileCoord, TileNoiseHeight> for PlanetHeight {
+    fn get(&self, point: TileCoord) -> TileNoiseHeight {
+        let noise = self.noise().elevation.get(self.sample_point(&point));
+        TileNoiseHeight(self.normalize(noise))
+    }
+}
+
+impl ChunkMapFunction<(TileCoord, TileNoiseHeight), TileRiver> for PlanetHeight {
+    fn get(&self, (point, height): (TileCoord, TileNoiseHeight)) -> TileRiver {
+        // Rivers only flow on land, the sea level is mapped to 0.5
+        if *height <= self.normalize(self.sea_level) {
+            return TileRiver(false);
+        }
 
-        let x = point.x as f64 * self.zoom_scale;
-        let y = point.y as f64 * self.zoom_scale;
+        TileRiver(self.noise().rivers.get(self.sample_point(&point)) <= RIVER_BED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    fn height(seed: u32, x: i32, y: i32) -> f64 {
+        *PlanetHeight::default()
+            .with_seed(seed)
+            .get(TileCoord(IVec2::new(x, y)))
+    }
+
+    #[test]
+    fn test_planet_height_snapshot() {
+        let snapshot = [
+            (0, 100, -250, 0.16926543004779276),
+            (0, -1234, 567, 0.6274057928575546),
+            (0, 4000, 4000, 0.49427486590615133),
+            (42, 100, -250, 0.7653492522063127),
+            (42, -1234, 567, 0.6378240161884222),
+            (42, 4000, 4000, 0.5391329159657424),
+            (1337, 100, -250, 0.7477574517121852),
+            (1337, -1234, 567, 0.49943180803337417),
+            (1337, 4000, 4000, 0.269099631247922),
+        ];
+
+        for (seed, x, y, expected) in snapshot {
+            let actual = height(seed, x, y);
+            assert!(
+                (actual - expected).abs() < 1e-9,
+                "seed {seed} at ({x}, {y}): expected {expected}, got {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_planet_height_range() {
+        for x in (-20_000..20_000).step_by(5_000) {
+            for y in (-20_000..20_000).step_by(5_000) {
+                let height = height(7, x, y);
+                assert!((0.0..=1.0).contains(&height), "{height} out of range");
+            }
+        }
+    }
+
+    fn river(seed: u32, x: i32, y: i32) -> bool {
+        let planet = PlanetHeight::default().with_seed(seed);
+        let point = TileCoord(IVec2::new(x, y));
+        let height = planet.get(point.clone());
+        *ChunkMapFunction::<_, TileRiver>::get(&planet, (point, height))
+    }
+
+    #[test]
+    fn test_planet_rivers() {
+        assert!(river(0, -1290, 500));
+        assert!(!river(0, -1240, 500));
+
+        // The sea is never tagged as a river
+        assert!(height(1337, 4000, 4000) < 0.5);
+        assert!(!river(1337, 4000, 4000));
+    }
 
-        let noise = base_continent_def.get([x, y]);
-        TileNoiseHeight((noise + 1.0) * 0.5)
+    #[test]
+    fn test_default_preset() {
+        let planet = PlanetHeight::default().with_preset(&TerrainGenerationPreset::default());
+        assert_eq!(planet, PlanetHeight::default());
     }
 }
@@ -0,0 +1,249 @@
+//! World generation presets.
+//!
+//! A preset holds the parameters of the planet that are not derived from the seed. The server
+//! sends it to the clients with the seed, so every client generates the same terrain.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::generator::*;
+
+#[derive(Resource, Component, Debug, Clone, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct TerrainGenerationPreset {
+    /// The name of the preset, shown in the new game menu.
+    pub name: String,
+    /// The generator of the terrain heights, the other parameters only apply to the planet.
+    pub generator: TerrainGeneratorKind,
+    /// Scale of the planet, lower values zoom in.
+    pub zoom_scale: f64,
+    /// Frequency of the continents, higher values produce smaller, more numerous continents.
+    pub continent_frequency: f64,
+    pub continent_lacunarity: f64,
+    pub mountain_lacunarity: f64,
+    pub hills_lacunarity: f64,
+    pub plains_lacunarity: f64,
+    pub badlands_lacunarity: f64,
+    /// The sea level, between -0.75 and 1.0. Higher values produce less land.
+    pub sea_level: f64,
+    /// The level of the continental shelves, between -0.75 and the sea level.
+    pub shelf_level: f64,
+    /// The amount of mountains, between 0.0 and 1.0.
+    pub mountains_amount: f64,
+    /// The amount of hills, between 0.0 and 1.0.
+    pub hills_amount: f64,
+    /// The amount of badlands, between 0.0 and 1.0.
+    pub badlands_amount: f64,
+    /// The maximum depth of the rivers, in planetary elevation units.
+    pub river_depth: f64,
+    /// Frequency of the feature patches, higher values produce smaller patches.
+    pub feature_patch_frequency: f64,
+    pub feature_patch_lacunarity: f64,
+    /// Frequency of the feature abundance, higher values produce smaller clusters.
+    pub feature_abundance_frequency: f64,
+    pub feature_abundance_lacunarity: f64,
+}
+
+impl Default for TerrainGenerationPreset {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            generator: TerrainGeneratorKind::Planet,
+            zoom_scale: 0.001,
+            continent_frequency: 1.0,
+            continent_lacunarity: 2.208984375,
+            mountain_lacunarity: 2.142578125,
+            hills_lacunarity: 2.162109375,
+            plains_lacunarity: 2.314453125,
+            badlands_lacunarity: 2.212890625,
+            sea_level: 0.0,
+            shelf_level: -0.375,
+            mountains_amount: 0.5,
+            hills_amount: 0.75,
+            badlands_amount: 0.3125,
+            river_depth: 0.0234375,
+            feature_patch_frequency: 0.125,
+            feature_patch_lacunarity: 2.1875,
+            feature_abundance_frequency: 0.4,
+            feature_abundance_lacunarity: 2.21875,
+        }
+    }
+}
+
+impl TerrainGenerationPreset {
+    /// Small islands scattered in a large ocean.
+    pub fn archipelago() -> Self {
+        Self {
+            name: "Archipelago".to_string(),
+            continent_frequency: 2.5,
+            sea_level: 0.03125,
+            shelf_level: -0.25,
+            mountains_amount: 0.25,
+            hills_amount: 0.5,
+            ..default()
+        }
+    }
+
+    /// Large continents covered in hills and mountains.
+    pub fn highlands() -> Self {
+        Self {
+            name: "Highlands".to_string(),
+            continent_frequency: 0.75,
+            sea_level: -0.125,
+            mountains_amount: 0.75,
+            hills_amount: 0.875,
+            badlands_amount: 0.125,
+            ..default()
+        }
+    }
+
+    /// A flat grass world, useful for testing.
+    pub fn flat() -> Self {
+        Self {
+            name: "Flat".to_string(),
+            generator: TerrainGeneratorKind::Flat(FLAT_HEIGHT),
+            ..default()
+        }
+    }
+
+    /// Check that the parameters can generate a world. The planet generator panics on some
+    /// values, so the presets that are loaded from files or received from a server must be
+    /// validated before they are used.
+    pub fn validate(&self) -> Result<(), String> {
+        let values = [
+            ("zoom_scale", self.zoom_scale),
+            ("continent_frequency", self.continent_frequency),
+            ("continent_lacunarity", self.continent_lacunarity),
+            ("mountain_lacunarity", self.mountain_lacunarity),
+            ("hills_lacunarity", self.hills_lacunarity),
+            ("plains_lacunarity", self.plains_lacunarity),
+            ("badlands_lacunarity", self.badlands_lacunarity),
+            ("sea_level", self.sea_level),
+            ("shelf_level", self.shelf_level),
+            ("mountains_amount", self.mountains_amount),
+            ("hills_amount", self.hills_amount),
+            ("badlands_amount", self.badlands_amount),
+            ("river_depth", self.river_depth),
+            ("feature_patch_frequency", self.feature_patch_frequency),
+            ("feature_patch_lacunarity", self.feature_patch_lacunarity),
+            (
+                "feature_abundance_frequency",
+                self.feature_abundance_frequency,
+            ),
+            (
+                "feature_abundance_lacunarity",
+                self.feature_abundance_lacunarity,
+            ),
+        ];
+        if let Some((name, value)) = values.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(format!("{name} must be a finite number, got {value}"));
+        }
+
+        if self.zoom_scale <= 0.0 {
+            return Err(format!(
+                "zoom_scale must be positive, got {}",
+                self.zoom_scale
+            ));
+        }
+
+        // The amounts are control points of the curves of the planet, which must be distinct
+        for (name, value) in [
+            ("mountains_amount", self.mountains_amount),
+            ("hills_amount", self.hills_amount),
+            ("badlands_amount", self.badlands_amount),
+        ] {
+            if value <= 0.0 || value >= 1.0 {
+                return Err(format!(
+                    "{name} must be between 0.0 and 1.0 exclusive, got {value}"
+                ));
+            }
+        }
+
+        if !(-0.75..1.0).contains(&self.sea_level) {
+            return Err(format!(
+                "sea_level must be between -0.75 and 1.0, got {}",
+                self.sea_level
+            ));
+        }
+        if self.shelf_level <= -0.75 || self.shelf_level >= self.sea_level {
+            return Err(format!(
+                "shelf_level must be between -0.75 and the sea level {}, got {}",
+                self.sea_level, self.shelf_level
+            ));
+        }
+
+        if self.river_depth < 0.0 {
+            return Err(format!(
+                "river_depth must not be negative, got {}",
+                self.river_depth
+            ));
+        }
+
+        if let TerrainGeneratorKind::Flat(height) = self.generator {
+            if !(0.0..=1.0).contains(&height) {
+                return Err(format!(
+                    "the height of the flat generator must be between 0.0 and 1.0, got {height}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The presets that can be chosen in the new game menu.
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::default(),
+            Self::archipelago(),
+            Self::highlands(),
+            Self::flat(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_presets_are_valid() {
+        for preset in TerrainGenerationPreset::builtin() {
+            assert_eq!(preset.validate(), Ok(()), "{}", preset.name);
+        }
+    }
+
+    #[test]
+    fn test_invalid_presets() {
+        let invalid = [
+            TerrainGenerationPreset {
+                mountains_amount: 0.0,
+                ..default()
+            },
+            TerrainGenerationPreset {
+                mountains_amount: 1.0,
+                ..default()
+            },
+            TerrainGenerationPreset {
+                zoom_scale: 0.0,
+                ..default()
+            },
+            TerrainGenerationPreset {
+                continent_frequency: f64::NAN,
+                ..default()
+            },
+            TerrainGenerationPreset {
+                shelf_level: 0.0,
+                sea_level: 0.0,
+                ..default()
+            },
+            TerrainGenerationPreset {
+                generator: TerrainGeneratorKind::Flat(f64::INFINITY),
+                ..default()
+            },
+        ];
+
+        for preset in invalid {
+            assert!(preset.validate().is_err(), "{preset:?}");
+        }
+    }
+}
@@ -11,7 +11,7 @@ use bevy::{
 
 #[cfg(feature = "debug")]
 use self::debug::*;
-use super::{components::*, resources::*};
+use super::{components::*, lod::*, resources::*};
 use crate::common::prelude::*;
 
 pub struct TerrainRenderPlugin {
@@ -45,6 +45,7 @@ impl Plugin for TerrainRenderPlugin {
         app.add_plugins(DebugPlugin);
 
         app.insert_resource(RenderSettings::new(self.tile_size, self.chunk_radius))
+            .insert_resource(TerrainRendered)
             .add_plugins(MaterialPlugin::<
                 ExtendedMaterial<StandardMaterial, ChunkMaterial>,
             >::default())
@@ -77,7 +78,10 @@ fn generate_chunk_render(
     mut meshes: ResMut<Assets<Mesh>>,
     layout: Res<RenderSettings>,
     q_tiles: Query<(&LocalTileCoord, &Tile), Without<TileBorder>>,
-    q_meshes: Query<(Entity, &ChunkMesh, &ChildOf), Without<ChunkRenderReady>>,
+    q_meshes: Query<
+        (Entity, &ChunkMesh, &ChildOf, Has<ChunkRenderReady>),
+        Or<(Without<ChunkRenderReady>, Changed<ChunkMesh>)>,
+    >,
     q_chunks: Query<&Children, With<ChunkCoord>>,
     assets: Res<TerrainAssets>,
 ) {
@@ -86,11 +90,19 @@ fn generate_chunk_render(
     }
     trace!(
         "Generating render data for {} chunk meshes",
-        q_meshes.iter().len()
+        q_meshes.iter().count()
     );
 
     let size = layout.chunk_radius * 2 + 1;
-    for (entity, ChunkMesh(mesh), ChildOf(chunk)) in q_meshes.iter() {
+    for (entity, ChunkMesh(mesh), ChildOf(chunk), ready) in q_meshes.iter() {
+        // The tiles of an edited chunk keep their kind, only the mesh is replaced
+        if ready {
+            commands
+                .entity(entity)
+                .insert(Mesh3d(meshes.add(mesh.clone())));
+            continue;
+        }
+
         if let Ok(children) = q_chunks.get(*chunk) {
             let mut height_data = vec![-1; (size * size) as usize];
 
@@ -166,8 +178,8 @@ mod debug {
         }
     }
 
-    fn toggle(kbd: Res<ButtonInput<KeyCode>>, mut show_grid: ResMut<ShowGrid>) {
-        if kbd.just_pressed(KeyCode::F11) {
+    fn toggle(input: ActionInput, mut show_grid: ResMut<ShowGrid>) {
+        if input.just_pressed(GameAction::ToggleDebug) {
             show_grid.0 = !show_grid.0;
         }
     }
@@ -12,18 +12,29 @@ impl TerrainAssets {
         Self { tiles }
     }
 
-    pub fn get_tile(&self, elevation: f64) -> Option<TileID> {
-        self.tiles
-            .iter()
-            .find(|tile| {
-                tile.generation
-                    .elevation_min
-                    .is_none_or(|min| elevation >= min)
-                    && tile
-                        .generation
-                        .elevation_max
-                        .is_none_or(|max| elevation <= max)
+    /// Find the tile for the given elevation and climate. River tiles are preferred for the tiles
+    /// of a river bed, then the biome tiles that match the climate, and the tiles that only depend
+    /// on the elevation are used when nothing else matches.
+    pub fn get_tile(
+        &self,
+        elevation: f64,
+        temperature: f64,
+        moisture: f64,
+        river: bool,
+    ) -> Option<TileID> {
+        let candidates = || {
+            self.tiles
+                .iter()
+                .filter(move |tile| tile.generation.matches(elevation, temperature, moisture))
+        };
+
+        river
+            .then(|| candidates().find(|tile| tile.generation.river))
+            .flatten()
+            .or_else(|| {
+                candidates().find(|tile| !tile.generation.river && tile.generation.is_biome())
             })
+            .or_else(|| candidates().find(|tile| !tile.generation.river))
             .map(|tile| tile.id.clone())
     }
 
@@ -41,8 +52,105 @@ pub struct TileAsset {
     pub generation: TileGeneration,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TileGeneration {
     pub elevation_min: Option<f64>,
     pub elevation_max: Option<f64>,
+    pub temperature_min: Option<f64>,
+    pub temperature_max: Option<f64>,
+    pub moisture_min: Option<f64>,
+    pub moisture_max: Option<f64>,
+    /// Whether the tile is only used for river beds.
+    pub river: bool,
+}
+
+impl TileGeneration {
+    /// Whether the elevation and the climate are within the bounds of the tile.
+    pub fn matches(&self, elevation: f64, temperature: f64, moisture: f64) -> bool {
+        self.elevation_min.is_none_or(|min| elevation >= min)
+            && self.elevation_max.is_none_or(|max| elevation <= max)
+            && self.temperature_min.is_none_or(|min| temperature >= min)
+            && self.temperature_max.is_none_or(|max| temperature <= max)
+            && self.moisture_min.is_none_or(|min| moisture >= min)
+            && self.moisture_max.is_none_or(|max| moisture <= max)
+    }
+
+    /// Whether the tile depends on the climate, and not only on the elevation.
+    pub fn is_biome(&self) -> bool {
+        self.temperature_min.is_some()
+            || self.temperature_max.is_some()
+            || self.moisture_min.is_some()
+            || self.moisture_max.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(id: &str, generation: TileGeneration) -> TileAsset {
+        TileAsset {
+            id: id.to_string(),
+            name: id.to_string(),
+            generation,
+        }
+    }
+
+    #[test]
+    fn test_get_tile() {
+        let assets = TerrainAssets::new(vec![
+            tile(
+                "water",
+                TileGeneration {
+                    elevation_max: Some(0.5),
+                    ..default()
+                },
+            ),
+            tile(
+                "grass",
+                TileGeneration {
+                    elevation_min: Some(0.5),
+                    ..default()
+                },
+            ),
+            tile(
+                "snow",
+                TileGeneration {
+                    elevation_min: Some(0.5),
+                    temperature_max: Some(0.2),
+                    ..default()
+                },
+            ),
+            tile(
+                "river",
+                TileGeneration {
+                    elevation_min: Some(0.5),
+                    river: true,
+                    ..default()
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            assets.get_tile(0.2, 0.1, 0.5, false).as_deref(),
+            Some("water")
+        );
+        assert_eq!(
+            assets.get_tile(0.7, 0.5, 0.5, false).as_deref(),
+            Some("grass")
+        );
+        assert_eq!(
+            assets.get_tile(0.7, 0.1, 0.5, false).as_deref(),
+            Some("snow")
+        );
+        assert_eq!(
+            assets.get_tile(0.7, 0.1, 0.5, true).as_deref(),
+            Some("river")
+        );
+        // There are no river tiles in the sea
+        assert_eq!(
+            assets.get_tile(0.2, 0.5, 0.5, true).as_deref(),
+            Some("water")
+        );
+    }
 }
@@ -64,6 +64,14 @@ pub struct TileMapStorage {
 }
 
 impl TileMapStorage {
+    pub fn new(tile_size: Vec2, chunk_radius: u32) -> Self {
+        Self {
+            tile_size,
+            chunk_radius,
+            chunks: HashMap::default(),
+        }
+    }
+
     fn discover_chunks(&self, center: IVec2, discover_radius: u32) -> Vec<IVec2> {
         let mut chunks = Vec::new();
         let step = self.chunk_radius as i32 * 2 + 1;
@@ -77,7 +85,14 @@ impl TileMapStorage {
         chunks
     }
 
-    fn chunk_tiles(&self, center: IVec2) -> Vec<IVec2> {
+    /// The number of tiles along a side of a chunk, which is also the distance between the
+    /// centers of neighbouring chunks.
+    pub fn chunk_size(&self) -> u32 {
+        self.chunk_radius * 2 + 1
+    }
+
+    /// The tiles of the chunk with the given center.
+    pub fn chunk_tiles(&self, center: IVec2) -> Vec<IVec2> {
         let mut tiles = Vec::new();
         for x in -(self.chunk_radius as i32)..=(self.chunk_radius as i32) {
             for y in -(self.chunk_radius as i32)..=(self.chunk_radius as i32) {
@@ -89,7 +104,9 @@ impl TileMapStorage {
         tiles
     }
 
-    fn chunk_border(&self, center: IVec2) -> Vec<IVec2> {
+    /// The ring of tiles around the chunk with the given center, including the corners. They
+    /// belong to the neighbouring chunks and are needed to mesh the edges of the chunk.
+    pub fn chunk_border(&self, center: IVec2) -> Vec<IVec2> {
         let mut border = Vec::new();
         let r = self.chunk_radius as i32 + 1;
 
@@ -123,7 +140,8 @@ impl TileMapStorage {
         )
     }
 
-    fn get_chunk(&self, tile: IVec2) -> Option<&Entity> {
+    /// The entity of the chunk with the given center, if it was spawned.
+    pub fn get_chunk(&self, tile: IVec2) -> Option<&Entity> {
         self.chunks.get(&tile)
     }
 
@@ -131,6 +149,12 @@ impl TileMapStorage {
         self.chunks.insert(center, chunk);
     }
 
+    /// Forget all the chunks, they will be spawned again when discovered. The caller is
+    /// responsible for despawning the chunk entities.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
     // TODO: implement pathfinding
 }
 
@@ -166,11 +190,7 @@ impl Plugin for TileMapPlugin {
 
         app.add_event::<TileDiscoverEvent>();
 
-        app.insert_resource(TileMapStorage {
-            tile_size: self.tile_size,
-            chunk_radius: self.chunk_radius,
-            chunks: HashMap::default(),
-        });
+        app.insert_resource(TileMapStorage::new(self.tile_size, self.chunk_radius));
 
         app.add_systems(Update, generate_chunks);
     }
@@ -253,6 +273,7 @@ mod debug {
     use bevy::prelude::*;
 
     use super::{ChunkCoord, TileMapStorage};
+    use crate::common::prelude::*;
 
     #[derive(Debug, Resource, Default, Clone, Deref, DerefMut)]
     struct ShowGrid(pub bool);
@@ -266,8 +287,8 @@ mod debug {
         }
     }
 
-    fn toggle(kbd: Res<ButtonInput<KeyCode>>, mut show_grid: ResMut<ShowGrid>) {
-        if kbd.just_pressed(KeyCode::F11) {
+    fn toggle(input: ActionInput, mut show_grid: ResMut<ShowGrid>) {
+        if input.just_pressed(GameAction::ToggleDebug) {
             show_grid.0 = !show_grid.0;
         }
     }
@@ -5,5 +5,8 @@ mod plugin;
 
 pub mod prelude {
     pub use super::common::prelude::*;
-    pub use super::plugin::{new_gui_app, new_headless_app, LauncherPlugin};
+    pub use super::plugin::{
+        generate_world_map, new_bot_app, new_gui_app, new_headless_app, BotConfig, BotReport,
+        BotSummary, LauncherPlugin, NetworkConditions, WorldMap, WorldMapConfig, WorldMapTile,
+    };
 }
@@ -0,0 +1,329 @@
+//! Server administration: kicking and banning players.
+//!
+//! The identities of the players are public, so the players also send a secret when they join.
+//! The first secret seen for an identity is stored in the identity file from the server config,
+//! and the later connections with that identity must send the same secret. Only the operators
+//! from the server config that proved their identity, the host of the game and the server
+//! console can use the admin commands.
+//!
+//! The bans are keyed by the persistent identity of the players and are stored in the ban file
+//! from the server config. A banned player can still come back with a new identity, the bans are
+//! a moderation tool and not an access control.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use lightyear::{
+    connection::host::HostClient,
+    prelude::{server::ClientOf, *},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{commands::*, config::*, network::*};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct BanEntry {
+    /// The username of the player when they were banned, if known.
+    pub username: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// The players that are not allowed to join the server, keyed by their persistent identity.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct BanList {
+    bans: BTreeMap<u64, BanEntry>,
+}
+
+impl BanList {
+    pub fn is_banned(&self, peer: PeerId) -> bool {
+        peer_identity(peer).is_some_and(|identity| self.bans.contains_key(&identity))
+    }
+
+    pub fn ban(&mut self, identity: u64, entry: BanEntry) {
+        self.bans.insert(identity, entry);
+    }
+
+    /// Remove a ban by identity or by the username of the banned player.
+    pub fn unban(&mut self, player: &str) -> Option<(u64, BanEntry)> {
+        let identity = player.parse::<u64>().ok().or_else(|| {
+            self.bans
+                .iter()
+                .find(|(_, entry)| {
+                    entry
+                        .username
+                        .as_ref()
+                        .is_some_and(|username| username.eq_ignore_ascii_case(player))
+                })
+                .map(|(identity, _)| *identity)
+        })?;
+
+        self.bans.remove(&identity).map(|entry| (identity, entry))
+    }
+}
+
+/// The secrets of the identities that joined the server, the first secret seen for an identity
+/// is the only one accepted afterwards.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct PlayerIdentities {
+    secrets: BTreeMap<u64, String>,
+}
+
+impl PlayerIdentities {
+    /// Check the secret sent for an identity, the unknown identities are registered with it.
+    pub fn verify(&mut self, identity: u64, secret: &str) -> bool {
+        if secret.is_empty() {
+            return false;
+        }
+
+        self.secrets
+            .entry(identity)
+            .or_insert_with(|| secret.to_string())
+            .as_str()
+            == secret
+    }
+}
+
+/// Server component on the connections of the peers that proved their identity.
+#[derive(Component, Debug, Clone, Copy)]
+pub(super) struct AuthenticatedPeer;
+
+pub(super) struct AdminPlugin;
+
+impl Plugin for AdminPlugin {
+    fn build(&self, app: &mut App) {
+        let ban_file = app.world().resource::<ServerConfig>().ban_file.clone();
+        let bans: BanList = load_config(&ban_file);
+        info!("Loaded {} ban(s) from {}", bans.bans.len(), ban_file);
+        app.insert_resource(bans);
+
+        let identity_file = app.world().resource::<ServerConfig>().identity_file.clone();
+        let identities: PlayerIdentities = load_config(&identity_file);
+        app.insert_resource(identities);
+
+        app.add_systems(
+            Update,
+            (
+                save_ban_list
+                    .run_if(resource_changed::<BanList>.and(not(resource_added::<BanList>))),
+                save_player_identities.run_if(
+                    resource_changed::<PlayerIdentities>
+                        .and(not(resource_added::<PlayerIdentities>)),
+                ),
+            ),
+        );
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "kick",
+                "Disconnect a player from the server",
+                PermissionLevel::Operator,
+            )
+            .arg("player", ArgKind::Word)
+            .optional_arg("reason", ArgKind::Text),
+            kick_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "ban",
+                "Ban a player by username or identity and disconnect them",
+                PermissionLevel::Operator,
+            )
+            .arg("player", ArgKind::Word)
+            .optional_arg("reason", ArgKind::Text),
+            ban_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "unban",
+                "Remove the ban of a player by username or identity",
+                PermissionLevel::Operator,
+            )
+            .arg("player", ArgKind::Word),
+            unban_command,
+        );
+    }
+}
+
+fn save_ban_list(bans: Res<BanList>, config: Res<ServerConfig>) {
+    match save_config(&config.ban_file, &*bans) {
+        Ok(()) => debug!("Saved ban list to {}", config.ban_file),
+        Err(err) => error!("Failed to save ban list to {}: {}", config.ban_file, err),
+    }
+}
+
+fn save_player_identities(identities: Res<PlayerIdentities>, config: Res<ServerConfig>) {
+    match save_config(&config.identity_file, &*identities) {
+        Ok(()) => debug!("Saved player identities to {}", config.identity_file),
+        Err(err) => error!(
+            "Failed to save player identities to {}: {}",
+            config.identity_file, err
+        ),
+    }
+}
+
+/// Find an online player by username, ignoring the case.
+pub(super) fn find_player(
+    name: &str,
+    q_players: &Query<(&PlayerId, &PlayerMetadata)>,
+) -> Option<(PeerId, String)> {
+    q_players
+        .iter()
+        .find(|(_, metadata)| metadata.username.eq_ignore_ascii_case(name))
+        .map(|(PlayerId(peer), metadata)| (*peer, metadata.username.clone()))
+}
+
+/// Disconnect the connection of a peer. The host client cannot be disconnected.
+fn disconnect_peer(
+    commands: &mut Commands,
+    peer: PeerId,
+    q_clients: &Query<(Entity, &RemoteId, Has<HostClient>), With<ClientOf>>,
+) -> Result<(), &'static str> {
+    let Some((entity, _, is_host)) = q_clients.iter().find(|(_, id, _)| id.0 == peer) else {
+        return Err("Player is not connected");
+    };
+    if is_host {
+        return Err("The host cannot be disconnected");
+    }
+
+    commands.trigger_targets(Disconnect, entity);
+    Ok(())
+}
+
+fn kick_command(
+    In(ctx): In<CommandContext>,
+    mut commands: Commands,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+    q_clients: Query<(Entity, &RemoteId, Has<HostClient>), With<ClientOf>>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let name = ctx.args.text(0).unwrap_or_default();
+    let Some((peer, username)) = find_player(name, &q_players) else {
+        ev_reply.write(ctx.reply(format!("No player named '{name}' is online")));
+        return;
+    };
+
+    match disconnect_peer(&mut commands, peer, &q_clients) {
+        Ok(()) => {
+            info!(
+                "Kicked {} ({:?}), reason: {}",
+                username,
+                peer,
+                ctx.args.text(1).unwrap_or("none")
+            );
+            ev_reply.write(ctx.reply(format!("Kicked {username}")));
+        }
+        Err(err) => {
+            ev_reply.write(ctx.reply(err));
+        }
+    }
+}
+
+fn ban_command(
+    In(ctx): In<CommandContext>,
+    mut commands: Commands,
+    mut bans: ResMut<BanList>,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+    q_clients: Query<(Entity, &RemoteId, Has<HostClient>), With<ClientOf>>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let name = ctx.args.text(0).unwrap_or_default();
+    let reason = ctx.args.text(1).map(str::to_string);
+
+    // Online players are banned by username, offline players by identity
+    let (identity, username, peer) = match find_player(name, &q_players) {
+        Some((peer, username)) => {
+            let Some(identity) = peer_identity(peer) else {
+                ev_reply.write(ctx.reply(format!("{username} has no persistent identity")));
+                return;
+            };
+            (identity, Some(username), Some(peer))
+        }
+        None => match name.parse::<u64>() {
+            Ok(identity) => (identity, None, None),
+            Err(_) => {
+                ev_reply.write(ctx.reply(format!("No player named '{name}' is online")));
+                return;
+            }
+        },
+    };
+
+    if let Some(peer) = peer {
+        if let Err(err) = disconnect_peer(&mut commands, peer, &q_clients) {
+            ev_reply.write(ctx.reply(err));
+            return;
+        }
+    }
+
+    let name = username.clone().unwrap_or_else(|| identity.to_string());
+    info!(
+        "Banned {} ({}), reason: {}",
+        name,
+        identity,
+        reason.as_deref().unwrap_or("none")
+    );
+    bans.ban(identity, BanEntry { username, reason });
+    ev_reply.write(ctx.reply(format!("Banned {name}")));
+}
+
+fn unban_command(
+    In(ctx): In<CommandContext>,
+    mut bans: ResMut<BanList>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let name = ctx.args.text(0).unwrap_or_default();
+
+    match bans.unban(name) {
+        Some((identity, entry)) => {
+            let username = entry.username.unwrap_or_else(|| identity.to_string());
+            info!("Unbanned {} ({})", username, identity);
+            ev_reply.write(ctx.reply(format!("Unbanned {username}")));
+        }
+        None => {
+            ev_reply.write(ctx.reply(format!("'{name}' is not banned")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unban_by_identity_and_username() {
+        let mut bans = BanList::default();
+        bans.ban(
+            42,
+            BanEntry {
+                username: Some("Griefer".to_string()),
+                reason: None,
+            },
+        );
+        bans.ban(
+            7,
+            BanEntry {
+                username: None,
+                reason: None,
+            },
+        );
+
+        assert!(bans.is_banned(PeerId::Netcode(42)));
+        assert!(bans.unban("griefer").is_some());
+        assert!(!bans.is_banned(PeerId::Netcode(42)));
+
+        assert!(bans.unban("7").is_some());
+        assert!(bans.unban("7").is_none());
+    }
+
+    #[test]
+    fn test_identity_secrets() {
+        let mut identities = PlayerIdentities::default();
+
+        // The first secret claims the identity
+        assert!(identities.verify(42, "secret"));
+        assert!(identities.verify(42, "secret"));
+        assert!(!identities.verify(42, "guess"));
+        assert!(!identities.verify(7, ""));
+    }
+}
@@ -25,6 +25,11 @@ impl Plugin for AssetsPlugin {
 fn setup_terrain_assets(mut commands: Commands) {
     debug!("Setting up terrain assets...");
 
+    commands.insert_resource(terrain_assets());
+}
+
+/// The tiles of the terrain. The order of the tiles is the tile kind used by `tile_chunk.wgsl`.
+pub(super) fn terrain_assets() -> TerrainAssets {
     // TODO: I want to load these from file, but for now, hardcode them
     // with some kind of cool syntax like:
     //
@@ -36,13 +41,14 @@ fn setup_terrain_assets(mut commands: Commands) {
     //         elevation_max=0.25
     //     [/generation]
     // [/terrain]
-    commands.insert_resource(TerrainAssets::new(vec![
+    TerrainAssets::new(vec![
         TileAsset {
             id: "deep_water".to_string(),
             name: "Deep Water".to_string(),
             generation: TileGeneration {
                 elevation_min: None,
                 elevation_max: Some(0.25),
+                ..default()
             },
         },
         TileAsset {
@@ -51,6 +57,7 @@ fn setup_terrain_assets(mut commands: Commands) {
             generation: TileGeneration {
                 elevation_min: Some(0.25),
                 elevation_max: Some(0.5),
+                ..default()
             },
         },
         TileAsset {
@@ -59,6 +66,7 @@ fn setup_terrain_assets(mut commands: Commands) {
             generation: TileGeneration {
                 elevation_min: Some(0.5),
                 elevation_max: Some(0.55),
+                ..default()
             },
         },
         TileAsset {
@@ -67,6 +75,7 @@ fn setup_terrain_assets(mut commands: Commands) {
             generation: TileGeneration {
                 elevation_min: Some(0.55),
                 elevation_max: Some(0.75),
+                ..default()
             },
         },
         TileAsset {
@@ -75,6 +84,7 @@ fn setup_terrain_assets(mut commands: Commands) {
             generation: TileGeneration {
                 elevation_min: Some(0.75),
                 elevation_max: Some(0.9),
+                ..default()
             },
         },
         TileAsset {
@@ -83,14 +93,73 @@ fn setup_terrain_assets(mut commands: Commands) {
             generation: TileGeneration {
                 elevation_min: Some(0.9),
                 elevation_max: None,
+                ..default()
             },
         },
-    ]));
+        TileAsset {
+            id: "river".to_string(),
+            name: "River".to_string(),
+            generation: TileGeneration {
+                elevation_min: Some(0.5),
+                elevation_max: None,
+                river: true,
+                ..default()
+            },
+        },
+        TileAsset {
+            id: "desert".to_string(),
+            name: "Desert".to_string(),
+            generation: TileGeneration {
+                elevation_min: Some(0.5),
+                elevation_max: Some(0.75),
+                temperature_min: Some(0.7),
+                moisture_max: Some(0.35),
+                ..default()
+            },
+        },
+        TileAsset {
+            id: "snow".to_string(),
+            name: "Snow".to_string(),
+            generation: TileGeneration {
+                elevation_min: Some(0.55),
+                temperature_max: Some(0.2),
+                ..default()
+            },
+        },
+        TileAsset {
+            id: "swamp".to_string(),
+            name: "Swamp".to_string(),
+            generation: TileGeneration {
+                elevation_min: Some(0.5),
+                elevation_max: Some(0.6),
+                temperature_min: Some(0.4),
+                moisture_min: Some(0.75),
+                ..default()
+            },
+        },
+        TileAsset {
+            id: "forest".to_string(),
+            name: "Forest".to_string(),
+            generation: TileGeneration {
+                elevation_min: Some(0.55),
+                elevation_max: Some(0.75),
+                moisture_min: Some(0.6),
+                ..default()
+            },
+        },
+    ])
 }
 
 fn setup_feature_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
     debug!("Setting up feature assets...");
 
+    commands.insert_resource(feature_assets(|path| asset_server.load(path)));
+}
+
+/// The features of the terrain, with the scenes of the variants loaded by `load_scene`.
+pub(super) fn feature_assets(
+    mut load_scene: impl FnMut(&'static str) -> Handle<Scene>,
+) -> FeatureAssets {
     // TODO: I want to load these from file, but for now, hardcode them
     // with some kind of cool syntax like:
     //
@@ -122,7 +191,7 @@ fn setup_feature_assets(mut commands: Commands, asset_server: Res<AssetServer>)
     //         scene="gltf/decoration/nature/trees_A_large.gltf#Scene0"
     //     [/variant]
     // [/feature]
-    commands.insert_resource(FeatureAssets::new(vec![FeatureAsset {
+    FeatureAssets::new(vec![FeatureAsset {
         id: "tree".to_string(),
         name: "Tree".to_string(),
         variants: vec![
@@ -130,28 +199,61 @@ fn setup_feature_assets(mut commands: Commands, asset_server: Res<AssetServer>)
                 id: "sand".to_string(),
                 name: "Palm Tree".to_string(),
                 threshold: 0.9,
-                scene: asset_server.load("gltf/decoration/nature/tree_single_A_cut.gltf#Scene0"),
+                scene: load_scene("gltf/decoration/nature/tree_single_A_cut.gltf#Scene0"),
+                ..default()
+            },
+            FeatureVariant {
+                id: "grass".to_string(),
+                name: "Pine Tree".to_string(),
+                threshold: 0.7,
+                scene: load_scene("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                temperature_max: Some(0.35),
+                ..default()
             },
             FeatureVariant {
                 id: "grass".to_string(),
                 name: "Oak Tree".to_string(),
                 threshold: 0.7,
-                scene: asset_server.load("gltf/decoration/nature/tree_single_A.gltf#Scene0"),
+                scene: load_scene("gltf/decoration/nature/tree_single_A.gltf#Scene0"),
+                ..default()
+            },
+            FeatureVariant {
+                id: "forest".to_string(),
+                name: "Oak Grove".to_string(),
+                threshold: 0.3,
+                scene: load_scene("gltf/decoration/nature/trees_A_medium.gltf#Scene0"),
+                ..default()
+            },
+            FeatureVariant {
+                id: "swamp".to_string(),
+                name: "Swamp Tree".to_string(),
+                threshold: 0.6,
+                scene: load_scene("gltf/decoration/nature/tree_single_A_cut.gltf#Scene0"),
+                ..default()
             },
             FeatureVariant {
                 id: "hills".to_string(),
                 name: "Pine Tree".to_string(),
                 threshold: 0.6,
-                scene: asset_server.load("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                scene: load_scene("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                ..default()
             },
             FeatureVariant {
                 id: "mountain".to_string(),
                 name: "Fir Tree".to_string(),
                 threshold: 0.8,
-                scene: asset_server.load("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                scene: load_scene("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                ..default()
+            },
+            FeatureVariant {
+                id: "snow".to_string(),
+                name: "Snowy Fir".to_string(),
+                threshold: 0.85,
+                scene: load_scene("gltf/decoration/nature/tree_single_B.gltf#Scene0"),
+                ..default()
             },
         ],
-    }]));
+    }])
 }
 
 fn setup_item_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
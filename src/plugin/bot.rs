@@ -0,0 +1,471 @@
+//! Headless bot clients used to load test the dedicated server.
+//!
+//! A bot is a normal client app without a window. Instead of going through the main menu it
+//! connects to the configured server right away, completes the welcome handshake and spawns its
+//! character like a player would. While playing it walks around randomly, spams the chat and
+//! crafts, and it reports how the connection went through a channel so many bots can run in the
+//! same process.
+
+use std::{collections::VecDeque, f32::consts::TAU, fmt::Display, time::Duration};
+
+use bevy::{input::InputSystem, prelude::*, time::common_conditions::on_timer};
+use crossbeam_channel::Sender;
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::*};
+use lightyear::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{controller::*, network::*, resources::*, states::*};
+use crate::prelude::*;
+
+/// How often the bots pick a new direction to walk in.
+const BOT_WALK_INTERVAL: f32 = 2.0;
+/// The chance that the bot stands still instead of walking after picking a direction.
+const BOT_IDLE_CHANCE: f64 = 0.2;
+/// The chance that the bot jumps when it picks a new direction.
+const BOT_JUMP_CHANCE: f64 = 0.3;
+/// How often the bots spawn some wood and try to craft with it.
+const BOT_CRAFT_INTERVAL: f32 = 10.0;
+/// How often the bots report the round trip time of their connection.
+const BOT_RTT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The configuration of a single bot client.
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// The index of the bot, used for its name and to seed its random behavior.
+    pub index: usize,
+    /// The address of the server to connect to.
+    pub address: String,
+    /// How long the bot stays connected before it disconnects and exits.
+    pub duration: Duration,
+    /// How long the bot waits for the handshake to complete before it gives up.
+    pub connect_timeout: Duration,
+    /// How often the bot sends a chat message.
+    pub chat_interval: Duration,
+    /// The channel where the bot sends its reports.
+    pub report: Sender<BotReport>,
+}
+
+/// What a bot reports about its connection.
+#[derive(Debug, Clone)]
+pub enum BotReport {
+    /// The bot received the welcome message of the server.
+    Joined { index: usize, elapsed: Duration },
+    /// The character of the bot was spawned by the server.
+    Spawned { index: usize },
+    /// The round trip time of the connection of the bot.
+    Rtt { index: usize, rtt: Duration },
+    /// The bot could not join the server.
+    Failed { index: usize, reason: String },
+    /// The bot was disconnected by the server before it finished.
+    Disconnected { index: usize },
+}
+
+/// Statistics collected from the reports of all the bots.
+#[derive(Debug, Clone, Default)]
+pub struct BotSummary {
+    bots: usize,
+    joined: usize,
+    spawned: usize,
+    failed: usize,
+    disconnected: usize,
+    join_times: Vec<Duration>,
+    rtt_samples: Vec<f32>,
+}
+
+impl BotSummary {
+    pub fn new(bots: usize) -> Self {
+        Self { bots, ..default() }
+    }
+
+    pub fn record(&mut self, report: &BotReport) {
+        match report {
+            BotReport::Joined { elapsed, .. } => {
+                self.joined += 1;
+                self.join_times.push(*elapsed);
+            }
+            BotReport::Spawned { .. } => self.spawned += 1,
+            BotReport::Rtt { rtt, .. } => self.rtt_samples.push(rtt.as_secs_f32() * 1000.0),
+            BotReport::Failed { .. } => self.failed += 1,
+            BotReport::Disconnected { .. } => self.disconnected += 1,
+        }
+    }
+
+    /// The fraction of the bots that joined the server and spawned their character.
+    pub fn success_rate(&self) -> f32 {
+        if self.bots == 0 {
+            return 0.0;
+        }
+
+        self.spawned as f32 / self.bots as f32
+    }
+
+    /// The round trip time in milliseconds below which the given fraction of the samples are.
+    pub fn rtt_percentile(&self, fraction: f32) -> Option<f32> {
+        if self.rtt_samples.is_empty() {
+            return None;
+        }
+
+        let mut samples = self.rtt_samples.clone();
+        samples.sort_by(f32::total_cmp);
+        let index = ((samples.len() - 1) as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+
+        Some(samples[index])
+    }
+}
+
+impl Display for BotSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "bots: {}, joined: {}, spawned: {}, failed: {}, dropped: {}, success rate: {:.1}%",
+            self.bots,
+            self.joined,
+            self.spawned,
+            self.failed,
+            self.disconnected,
+            self.success_rate() * 100.0
+        )?;
+
+        if let Some(max) = self.join_times.iter().max() {
+            let avg = self.join_times.iter().sum::<Duration>() / self.join_times.len() as u32;
+            writeln!(
+                f,
+                "join time: avg {} ms, max {} ms",
+                avg.as_millis(),
+                max.as_millis()
+            )?;
+        }
+
+        match (self.rtt_percentile(0.0), self.rtt_percentile(1.0)) {
+            (Some(min), Some(max)) => {
+                let avg = self.rtt_samples.iter().sum::<f32>() / self.rtt_samples.len() as f32;
+                write!(
+                    f,
+                    "rtt: min {:.1} ms, avg {:.1} ms, p95 {:.1} ms, max {:.1} ms ({} samples)",
+                    min,
+                    avg,
+                    self.rtt_percentile(0.95).unwrap_or(max),
+                    max,
+                    self.rtt_samples.len()
+                )
+            }
+            _ => write!(f, "rtt: no samples"),
+        }
+    }
+}
+
+/// The state of the bot driving the app.
+#[derive(Resource)]
+struct BotSession {
+    config: BotConfig,
+    rng: StdRng,
+    /// The time when the bot started connecting, cleared once it joined.
+    connecting_since: Option<f32>,
+    /// The time when the bot joined the server, its stay is counted from there.
+    joined_at: Option<f32>,
+    started: bool,
+    stopping: bool,
+    walk_timer: Timer,
+    chat_timer: Timer,
+    craft_timer: Timer,
+    direction: Vec2,
+    jump: bool,
+    /// The actions that are pressed one per frame, and the input that is currently held.
+    actions: VecDeque<GameAction>,
+    held: Option<InputBinding>,
+    messages: usize,
+}
+
+/// Plugin that replaces the player of a client app with a bot. When this plugin is added the
+/// launcher runs as a client without rendering.
+pub(super) struct BotPlugin(pub BotConfig);
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        let config = self.0.clone();
+        app.insert_resource(BotSession {
+            rng: StdRng::seed_from_u64(config.index as u64),
+            connecting_since: None,
+            joined_at: None,
+            started: false,
+            stopping: false,
+            walk_timer: Timer::from_seconds(BOT_WALK_INTERVAL, TimerMode::Repeating),
+            chat_timer: Timer::new(config.chat_interval, TimerMode::Repeating),
+            craft_timer: Timer::from_seconds(BOT_CRAFT_INTERVAL, TimerMode::Repeating),
+            direction: Vec2::ZERO,
+            jump: false,
+            actions: VecDeque::new(),
+            held: None,
+            messages: 0,
+            config,
+        });
+
+        app.add_systems(OnEnter(LauncherStates::MainMenu), start_bot);
+        app.add_systems(OnEnter(LauncherStates::Playing), report_bot_joined);
+        app.add_systems(
+            Update,
+            (
+                check_bot_timeouts,
+                report_bot_spawned,
+                report_bot_rtt.run_if(on_timer(BOT_RTT_INTERVAL)),
+                (update_bot_walk, update_bot_chat, update_bot_craft)
+                    .run_if(in_state(LauncherStates::Playing)),
+            ),
+        );
+        app.add_systems(
+            PreUpdate,
+            (
+                drive_bot_character.in_set(InputManagerSystem::ManualControl),
+                drive_bot_actions.after(InputSystem),
+            )
+                .run_if(in_state(LauncherStates::Playing)),
+        );
+    }
+}
+
+/// The bot skips the main menu and connects to the server. When it comes back to the menu it
+/// was disconnected, so it exits.
+fn start_bot(
+    time: Res<Time>,
+    mut session: ResMut<BotSession>,
+    mut mode: ResMut<LauncherMode>,
+    mut player_name: ResMut<PlayerNameSetting>,
+    mut next_state: ResMut<NextState<LauncherStates>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    let index = session.config.index;
+    if session.started {
+        if !session.stopping {
+            warn!("Bot {} was disconnected from the server", index);
+            let _ = session
+                .config
+                .report
+                .send(BotReport::Disconnected { index });
+        }
+        ev_exit.write(AppExit::Success);
+        return;
+    }
+
+    info!("Bot {} connecting to {}", index, session.config.address);
+    session.started = true;
+    session.connecting_since = Some(time.elapsed_secs());
+    player_name.0 = format!("Bot{}", index);
+    *mode = LauncherMode::Client(session.config.address.clone());
+    next_state.set(LauncherStates::Connecting);
+}
+
+fn report_bot_joined(time: Res<Time>, mut session: ResMut<BotSession>) {
+    let Some(since) = session.connecting_since.take() else {
+        return;
+    };
+
+    let index = session.config.index;
+    let elapsed = Duration::from_secs_f32(time.elapsed_secs() - since);
+    session.joined_at = Some(time.elapsed_secs());
+    debug!("Bot {} joined the server in {:?}", index, elapsed);
+    let _ = session
+        .config
+        .report
+        .send(BotReport::Joined { index, elapsed });
+}
+
+fn report_bot_spawned(
+    session: Res<BotSession>,
+    q_player: Query<
+        (),
+        (
+            Added<Predicted>,
+            With<Controlled>,
+            With<PlayerControllerMarker>,
+        ),
+    >,
+) {
+    if q_player.is_empty() {
+        return;
+    }
+
+    let index = session.config.index;
+    debug!("Bot {} spawned its character", index);
+    let _ = session.config.report.send(BotReport::Spawned { index });
+}
+
+fn report_bot_rtt(session: Res<BotSession>, q_link: Query<&Link, (With<Client>, With<Connected>)>) {
+    let index = session.config.index;
+    for link in &q_link {
+        let _ = session.config.report.send(BotReport::Rtt {
+            index,
+            rtt: link.stats.rtt,
+        });
+    }
+}
+
+/// Give up when the handshake takes too long, and disconnect once the bot ran for long enough.
+fn check_bot_timeouts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut session: ResMut<BotSession>,
+    q_client: Query<Entity, With<Client>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    let index = session.config.index;
+    if session.stopping {
+        // The disconnect had one frame to be sent
+        ev_exit.write(AppExit::Success);
+        return;
+    }
+
+    if let Some(since) = session.connecting_since {
+        if time.elapsed_secs() - since > session.config.connect_timeout.as_secs_f32() {
+            warn!("Bot {} timed out while connecting", index);
+            let _ = session.config.report.send(BotReport::Failed {
+                index,
+                reason: "timed out while connecting".to_string(),
+            });
+            ev_exit.write(AppExit::error());
+        }
+        return;
+    }
+
+    let Some(joined_at) = session.joined_at else {
+        return;
+    };
+    if time.elapsed_secs() - joined_at > session.config.duration.as_secs_f32() {
+        info!("Bot {} finished, disconnecting", index);
+        session.stopping = true;
+        for client in &q_client {
+            commands.trigger_targets(Disconnect, client);
+        }
+    }
+}
+
+/// Random walk: every few seconds pick a new direction, sometimes stand still or jump.
+fn update_bot_walk(time: Res<Time>, mut session: ResMut<BotSession>) {
+    if !session.walk_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let session = &mut *session;
+    session.direction = if session.rng.random_bool(BOT_IDLE_CHANCE) {
+        Vec2::ZERO
+    } else {
+        Vec2::from_angle(session.rng.random_range(0.0..TAU))
+    };
+    session.jump = session.rng.random_bool(BOT_JUMP_CHANCE);
+}
+
+fn update_bot_chat(
+    time: Res<Time>,
+    mut session: ResMut<BotSession>,
+    mut ev_chat: EventWriter<ClientChatMessageEvent>,
+) {
+    if !session.chat_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    session.messages += 1;
+    ev_chat.write(ClientChatMessageEvent {
+        message: format!(
+            "Hello from bot {} (message {})",
+            session.config.index, session.messages
+        ),
+    });
+}
+
+/// Spawn some wood in front of the character and press craft, like a player testing recipes.
+fn update_bot_craft(time: Res<Time>, mut session: ResMut<BotSession>) {
+    if !session.craft_timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    session.actions.extend([
+        GameAction::SpawnItem,
+        GameAction::SpawnItem,
+        GameAction::Craft,
+    ]);
+}
+
+/// Write the walk direction and jumps of the bot over the input of the player, after the
+/// input manager updated the action state.
+fn drive_bot_character(
+    mut session: ResMut<BotSession>,
+    mut q_character: Query<
+        &mut ActionState<CharacterAction>,
+        (
+            With<PlayerControllerMarker>,
+            With<InputMap<CharacterAction>>,
+        ),
+    >,
+) {
+    for mut action_state in &mut q_character {
+        action_state.set_axis_pair(&CharacterAction::Move, session.direction);
+        if session.jump {
+            action_state.press(&CharacterAction::Jump);
+        } else {
+            action_state.release(&CharacterAction::Jump);
+        }
+    }
+
+    session.jump = false;
+}
+
+/// Press the bindings of the queued actions, one per frame, so the `ActionInput` consumers see
+/// them as just pressed.
+fn drive_bot_actions(
+    mut session: ResMut<BotSession>,
+    bindings: Res<KeyBindings>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+) {
+    match session.held.take() {
+        Some(InputBinding::Keyboard(key)) => keyboard.release(key),
+        Some(InputBinding::Mouse(button)) => mouse.release(button),
+        None => {}
+    }
+
+    let Some(action) = session.actions.pop_front() else {
+        return;
+    };
+    let Some(input) = bindings.get(action).input else {
+        warn!("Bot cannot press {:?}, it is not bound", action);
+        return;
+    };
+
+    match input {
+        InputBinding::Keyboard(key) => keyboard.press(key),
+        InputBinding::Mouse(button) => mouse.press(button),
+    }
+    session.held = Some(input);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bot_summary() {
+        let mut summary = BotSummary::new(4);
+        for index in 0..3 {
+            summary.record(&BotReport::Joined {
+                index,
+                elapsed: Duration::from_millis(100),
+            });
+            summary.record(&BotReport::Spawned { index });
+        }
+        summary.record(&BotReport::Failed {
+            index: 3,
+            reason: "timeout".to_string(),
+        });
+        for rtt in 1..=100 {
+            summary.record(&BotReport::Rtt {
+                index: 0,
+                rtt: Duration::from_millis(rtt),
+            });
+        }
+
+        assert_eq!(summary.success_rate(), 0.75);
+        assert_eq!(summary.rtt_percentile(0.0), Some(1.0));
+        assert_eq!(summary.rtt_percentile(0.95), Some(95.0));
+        assert_eq!(summary.rtt_percentile(1.0), Some(100.0));
+        assert_eq!(BotSummary::new(1).rtt_percentile(0.5), None);
+    }
+}
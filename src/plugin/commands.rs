@@ -0,0 +1,600 @@
+//! Chat commands that are executed on the server.
+//!
+//! Chat messages that start with `/` are parsed into one of the registered commands instead of
+//! being broadcasted to the other players. Each command has an argument schema, a help text and
+//! the permission level required to run it. The commands are one-shot systems that take a
+//! `CommandContext` as input and reply privately to the sender with a `CommandReplyEvent`.
+//!
+//! Other plugins can register their own commands with `AppChatCommandExt::add_chat_command`.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use bevy::{ecs::system::SystemId, prelude::*};
+use lightyear::{connection::host::HostClient, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{admin::*, config::*, network::*};
+use crate::prelude::*;
+
+/// The prefix of the chat messages that are interpreted as commands.
+pub(super) const COMMAND_PREFIX: char = '/';
+
+/// The permission level required to run a command. Each level can also run the commands of the
+/// levels below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect)]
+pub(super) enum PermissionLevel {
+    Player,
+    Operator,
+    Console,
+}
+
+/// The kind of value that an argument accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ArgKind {
+    Int,
+    Float,
+    /// A single word.
+    Word,
+    /// The rest of the input, can only be used for the last argument.
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct CommandArg {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum ArgValue {
+    Int(i64),
+    Float(f32),
+    Text(String),
+}
+
+/// The parsed arguments of a command, in the order of the argument schema. Optional arguments
+/// that are missing are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct CommandArgs(Vec<Option<ArgValue>>);
+
+impl CommandArgs {
+    pub fn int(&self, index: usize) -> Option<i64> {
+        match self.0.get(index)? {
+            Some(ArgValue::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, index: usize) -> Option<f32> {
+        match self.0.get(index)? {
+            Some(ArgValue::Float(value)) => Some(*value),
+            Some(ArgValue::Int(value)) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, index: usize) -> Option<&str> {
+        match self.0.get(index)? {
+            Some(ArgValue::Text(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Who requested the execution of a command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum CommandSource {
+    /// A connected player, `owner` is the server side entity of the connection.
+    Player { peer: PeerId, owner: Entity },
+    /// The server console.
+    Console,
+}
+
+/// The input of the command systems.
+#[derive(Debug, Clone)]
+pub(super) struct CommandContext {
+    pub source: CommandSource,
+    pub permission: PermissionLevel,
+    pub args: CommandArgs,
+}
+
+impl CommandContext {
+    /// The peer that sent the command, if it was sent by a player.
+    pub fn peer(&self) -> Option<PeerId> {
+        match self.source {
+            CommandSource::Player { peer, .. } => Some(peer),
+            CommandSource::Console => None,
+        }
+    }
+
+    /// Create a reply to the source of the command.
+    pub fn reply(&self, message: impl Into<String>) -> CommandReplyEvent {
+        CommandReplyEvent {
+            target: self.source,
+            message: message.into(),
+        }
+    }
+}
+
+/// The description of a command: its name, help text, permission level and argument schema.
+#[derive(Debug, Clone)]
+pub(super) struct ChatCommand {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub permission: PermissionLevel,
+    pub args: Vec<CommandArg>,
+}
+
+impl ChatCommand {
+    pub fn new(name: &'static str, help: &'static str, permission: PermissionLevel) -> Self {
+        Self {
+            name,
+            help,
+            permission,
+            args: Vec::new(),
+        }
+    }
+
+    /// Add a required argument to the command.
+    pub fn arg(mut self, name: &'static str, kind: ArgKind) -> Self {
+        self.args.push(CommandArg {
+            name,
+            kind,
+            optional: false,
+        });
+        self
+    }
+
+    /// Add an optional argument to the command. Optional arguments must come after the required
+    /// ones.
+    pub fn optional_arg(mut self, name: &'static str, kind: ArgKind) -> Self {
+        self.args.push(CommandArg {
+            name,
+            kind,
+            optional: true,
+        });
+        self
+    }
+
+    /// The usage string of the command, e.g. `/give <item> [n]`.
+    pub fn usage(&self) -> String {
+        let mut usage = format!("{}{}", COMMAND_PREFIX, self.name);
+        for arg in &self.args {
+            if arg.optional {
+                usage.push_str(&format!(" [{}]", arg.name));
+            } else {
+                usage.push_str(&format!(" <{}>", arg.name));
+            }
+        }
+        usage
+    }
+
+    /// Parse the arguments of the command, without the command name.
+    pub fn parse_args(&self, input: &str) -> Result<CommandArgs, CommandError> {
+        let mut rest = input.trim();
+        let mut values = Vec::with_capacity(self.args.len());
+
+        for arg in &self.args {
+            if rest.is_empty() {
+                if !arg.optional {
+                    return Err(CommandError::MissingArgument {
+                        name: arg.name,
+                        usage: self.usage(),
+                    });
+                }
+                values.push(None);
+                continue;
+            }
+
+            let token = match arg.kind {
+                ArgKind::Text => std::mem::take(&mut rest),
+                _ => {
+                    let (token, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    rest = tail.trim_start();
+                    token
+                }
+            };
+
+            let invalid = || CommandError::InvalidArgument {
+                name: arg.name,
+                value: token.to_string(),
+            };
+            let value = match arg.kind {
+                ArgKind::Int => ArgValue::Int(token.parse().map_err(|_| invalid())?),
+                // NaN and infinite values would corrupt the state they are written to
+                ArgKind::Float => ArgValue::Float(
+                    token
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                        .ok_or_else(invalid)?,
+                ),
+                ArgKind::Word | ArgKind::Text => ArgValue::Text(token.to_string()),
+            };
+            values.push(Some(value));
+        }
+
+        if !rest.is_empty() {
+            return Err(CommandError::TooManyArguments {
+                usage: self.usage(),
+            });
+        }
+
+        Ok(CommandArgs(values))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum CommandError {
+    UnknownCommand(String),
+    PermissionDenied(String),
+    MissingArgument { name: &'static str, usage: String },
+    InvalidArgument { name: &'static str, value: String },
+    TooManyArguments { usage: String },
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::UnknownCommand(name) => write!(
+                f,
+                "Unknown command '{name}', type {COMMAND_PREFIX}help for a list of commands"
+            ),
+            CommandError::PermissionDenied(name) => {
+                write!(f, "You do not have permission to use '{name}'")
+            }
+            CommandError::MissingArgument { name, usage } => {
+                write!(f, "Missing argument '{name}', usage: {usage}")
+            }
+            CommandError::InvalidArgument { name, value } => {
+                write!(f, "Invalid value '{value}' for argument '{name}'")
+            }
+            CommandError::TooManyArguments { usage } => {
+                write!(f, "Too many arguments, usage: {usage}")
+            }
+        }
+    }
+}
+
+/// Resource that holds all the registered commands.
+#[derive(Resource, Default)]
+pub(super) struct ChatCommandRegistry {
+    commands: BTreeMap<&'static str, (ChatCommand, SystemId<In<CommandContext>>)>,
+}
+
+impl ChatCommandRegistry {
+    pub fn get(&self, name: &str) -> Option<&ChatCommand> {
+        self.commands.get(name).map(|(command, _)| command)
+    }
+
+    /// Iterate over the commands in alphabetical order.
+    pub fn iter(&self) -> impl Iterator<Item = &ChatCommand> {
+        self.commands.values().map(|(command, _)| command)
+    }
+
+    fn insert(&mut self, command: ChatCommand, system: SystemId<In<CommandContext>>) {
+        if self.commands.contains_key(command.name) {
+            warn!("Command {} is registered twice", command.name);
+        }
+        self.commands.insert(command.name, (command, system));
+    }
+
+    /// Parse a command input, e.g. `/give wood 5`, and check the permission of the source.
+    fn parse(
+        &self,
+        input: &str,
+        permission: PermissionLevel,
+    ) -> Result<(SystemId<In<CommandContext>>, CommandArgs), CommandError> {
+        let input = input.trim_start().trim_start_matches(COMMAND_PREFIX);
+        let (name, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+
+        let Some((command, system)) = self.commands.get(name) else {
+            return Err(CommandError::UnknownCommand(name.to_string()));
+        };
+        if permission < command.permission {
+            return Err(CommandError::PermissionDenied(name.to_string()));
+        }
+
+        Ok((*system, command.parse_args(args)?))
+    }
+}
+
+pub(super) trait AppChatCommandExt {
+    /// Register a new command. The system is run on the server each time the command is used.
+    fn add_chat_command<M>(
+        &mut self,
+        command: ChatCommand,
+        system: impl IntoSystem<In<CommandContext>, (), M> + 'static,
+    ) -> &mut Self;
+}
+
+impl AppChatCommandExt for App {
+    fn add_chat_command<M>(
+        &mut self,
+        command: ChatCommand,
+        system: impl IntoSystem<In<CommandContext>, (), M> + 'static,
+    ) -> &mut Self {
+        let system = self.register_system(system);
+        self.world_mut()
+            .get_resource_or_init::<ChatCommandRegistry>()
+            .insert(command, system);
+        self
+    }
+}
+
+/// Event used to request the execution of a command on the server.
+#[derive(Debug, Clone, Event)]
+pub(super) struct CommandInputEvent {
+    pub source: CommandSource,
+    pub input: String,
+}
+
+/// Event used to reply to the source of a command.
+#[derive(Debug, Clone, Event)]
+pub(super) struct CommandReplyEvent {
+    pub target: CommandSource,
+    pub message: String,
+}
+
+/// Event sent from the server to the client with the reply of a command.
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
+pub(super) struct ServerCommandReplyEvent {
+    pub message: String,
+}
+
+pub(super) struct CommandsPlugin {
+    pub dedicated: bool,
+}
+
+impl Plugin for CommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatCommandRegistry>();
+        app.insert_resource(ServerStartTime::default());
+
+        app.add_event::<CommandInputEvent>();
+        app.add_event::<CommandReplyEvent>();
+        app.add_server_event::<ServerCommandReplyEvent, MessageChannel>();
+
+        app.add_systems(Update, (execute_commands, send_command_replies).chain());
+        if !self.dedicated {
+            app.add_systems(Update, on_server_command_reply);
+        }
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "help",
+                "List the available commands or show the usage of a command",
+                PermissionLevel::Player,
+            )
+            .optional_arg("command", ArgKind::Word),
+            help_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new("time", "Show the server uptime", PermissionLevel::Player),
+            time_command,
+        );
+    }
+}
+
+/// The permission level of a command source. The host of the game is always an operator, the
+/// other operators must have proved their identity.
+fn source_permission(
+    source: &CommandSource,
+    q_links: &Query<(Has<HostClient>, Has<AuthenticatedPeer>)>,
+    config: &ServerConfig,
+) -> PermissionLevel {
+    let (host, authenticated) = match source {
+        CommandSource::Player { owner, .. } => q_links.get(*owner).unwrap_or_default(),
+        CommandSource::Console => (false, false),
+    };
+
+    match source {
+        CommandSource::Console => PermissionLevel::Console,
+        CommandSource::Player { .. } if host => PermissionLevel::Operator,
+        CommandSource::Player { peer, .. } if authenticated && config.is_operator(*peer) => {
+            PermissionLevel::Operator
+        }
+        CommandSource::Player { .. } => PermissionLevel::Player,
+    }
+}
+
+fn execute_commands(
+    mut commands: Commands,
+    mut ev_input: EventReader<CommandInputEvent>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+    registry: Res<ChatCommandRegistry>,
+    q_links: Query<(Has<HostClient>, Has<AuthenticatedPeer>)>,
+    config: Res<ServerConfig>,
+) {
+    for CommandInputEvent { source, input } in ev_input.read() {
+        debug!("Executing command {:?} from {:?}", input, source);
+
+        let permission = source_permission(source, &q_links, &config);
+        match registry.parse(input, permission) {
+            Ok((system, args)) => {
+                commands.run_system_with(
+                    system,
+                    CommandContext {
+                        source: *source,
+                        permission,
+                        args,
+                    },
+                );
+            }
+            Err(err) => {
+                ev_reply.write(CommandReplyEvent {
+                    target: *source,
+                    message: err.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn send_command_replies(
+    mut ev_reply: EventReader<CommandReplyEvent>,
+    mut ev_server: EventWriter<ToClient<ServerCommandReplyEvent>>,
+    mut ev_host: EventWriter<ServerCommandReplyEvent>,
+    q_host: Query<(), With<HostClient>>,
+) {
+    for CommandReplyEvent { target, message } in ev_reply.read() {
+        match target {
+            CommandSource::Console => info!("{}", message),
+            // NOTE: the host client does not receive messages, so write the event locally
+            CommandSource::Player { owner, .. } if q_host.contains(*owner) => {
+                ev_host.write(ServerCommandReplyEvent {
+                    message: message.clone(),
+                });
+            }
+            CommandSource::Player { peer, .. } => {
+                ev_server.write(ToClient {
+                    target: NetworkTarget::Single(*peer),
+                    event: ServerCommandReplyEvent {
+                        message: message.clone(),
+                    },
+                });
+            }
+        }
+    }
+}
+
+fn on_server_command_reply(
+    mut ev_reply: EventReader<ServerCommandReplyEvent>,
+    mut ev_history: EventWriter<AddChatHistoryItemEvent>,
+) {
+    for ev in ev_reply.read() {
+        debug!("Received command reply from server: {}", ev.message);
+
+        ev_history.write(AddChatHistoryItemEvent {
+            sender: "Server".to_string(),
+            message: ev.message.clone(),
+            kind: ChatMessageKind::System,
+        });
+    }
+}
+
+fn help_command(
+    In(ctx): In<CommandContext>,
+    registry: Res<ChatCommandRegistry>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    if let Some(name) = ctx.args.text(0) {
+        let message = match registry.get(name.trim_start_matches(COMMAND_PREFIX)) {
+            Some(command) => format!("{} - {}", command.usage(), command.help),
+            None => CommandError::UnknownCommand(name.to_string()).to_string(),
+        };
+        ev_reply.write(ctx.reply(message));
+        return;
+    }
+
+    let commands = registry
+        .iter()
+        .filter(|command| command.permission <= ctx.permission)
+        .map(|command| command.usage())
+        .collect::<Vec<_>>();
+    ev_reply.write(ctx.reply(format!("Commands: {}", commands.join(", "))));
+}
+
+/// The time at which the server plugin was built, used to compute the uptime.
+#[derive(Resource, Debug, Clone, Copy, Deref)]
+struct ServerStartTime(std::time::Instant);
+
+impl Default for ServerStartTime {
+    fn default() -> Self {
+        Self(std::time::Instant::now())
+    }
+}
+
+fn time_command(
+    In(ctx): In<CommandContext>,
+    start: Res<ServerStartTime>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let uptime = start.elapsed().as_secs();
+    ev_reply.write(ctx.reply(format!(
+        "Server uptime: {:02}:{:02}:{:02}",
+        uptime / 3600,
+        (uptime / 60) % 60,
+        uptime % 60
+    )));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn give() -> ChatCommand {
+        ChatCommand::new("give", "", PermissionLevel::Operator)
+            .arg("item", ArgKind::Word)
+            .optional_arg("n", ArgKind::Int)
+    }
+
+    #[test]
+    fn test_usage() {
+        assert_eq!(give().usage(), "/give <item> [n]");
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = give().parse_args("wood 5").unwrap();
+        assert_eq!(args.text(0), Some("wood"));
+        assert_eq!(args.int(1), Some(5));
+
+        let args = give().parse_args("  wood ").unwrap();
+        assert_eq!(args.text(0), Some("wood"));
+        assert_eq!(args.int(1), None);
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(matches!(
+            give().parse_args(""),
+            Err(CommandError::MissingArgument { name: "item", .. })
+        ));
+        assert!(matches!(
+            give().parse_args("wood five"),
+            Err(CommandError::InvalidArgument { name: "n", .. })
+        ));
+        assert!(matches!(
+            give().parse_args("wood 5 6"),
+            Err(CommandError::TooManyArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_text_arg() {
+        let command =
+            ChatCommand::new("say", "", PermissionLevel::Console).arg("message", ArgKind::Text);
+
+        let args = command.parse_args("hello  there").unwrap();
+        assert_eq!(args.text(0), Some("hello  there"));
+    }
+
+    #[test]
+    fn test_float_accepts_int() {
+        let command = ChatCommand::new("tp", "", PermissionLevel::Operator)
+            .arg("x", ArgKind::Float)
+            .arg("z", ArgKind::Float);
+
+        let args = command.parse_args("10 -2.5").unwrap();
+        assert_eq!(args.float(0), Some(10.0));
+        assert_eq!(args.float(1), Some(-2.5));
+    }
+
+    #[test]
+    fn test_float_rejects_non_finite() {
+        let command =
+            ChatCommand::new("tp", "", PermissionLevel::Operator).arg("x", ArgKind::Float);
+
+        for value in ["nan", "inf", "-inf", "1e39"] {
+            assert!(
+                matches!(
+                    command.parse_args(value),
+                    Err(CommandError::InvalidArgument { name: "x", .. })
+                ),
+                "{value} was accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Simulated network conditions with a seeded random generator, so the losses and the jitter of
+//! a run can be reproduced. The conditioner is added next to the `Link` of a connection and
+//! delays or drops the packets received by the link before the transport reads them.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use lightyear::{
+    link::{LinkReceiveSet, RecvPayload},
+    prelude::*,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+pub(super) struct LinkConditionerPlugin;
+
+impl Plugin for LinkConditionerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            apply_link_conditioners.in_set(LinkReceiveSet::ApplyConditioner),
+        );
+    }
+}
+
+/// Drops and delays the packets received by the link of the entity.
+#[derive(Component)]
+pub(super) struct SeededLinkConditioner {
+    config: LinkConditionerConfig,
+    rng: StdRng,
+    /// The delayed packets, with the elapsed time they are released at.
+    queue: Vec<(Duration, RecvPayload)>,
+}
+
+impl SeededLinkConditioner {
+    pub fn new(config: LinkConditionerConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            queue: Vec::new(),
+        }
+    }
+
+    /// Drop a received packet or queue it with the simulated latency.
+    fn condition(&mut self, payload: RecvPayload, now: Duration) {
+        if self.rng.random::<f32>() < self.config.incoming_loss {
+            return;
+        }
+
+        let jitter = self.config.incoming_jitter.as_secs_f32();
+        let offset = if jitter > 0.0 {
+            self.rng.random_range(-jitter..=jitter)
+        } else {
+            0.0
+        };
+        let latency = (self.config.incoming_latency.as_secs_f32() + offset).max(0.0);
+        self.queue
+            .push((now + Duration::from_secs_f32(latency), payload));
+    }
+
+    /// Take the packets that are released at the elapsed time, in the order of their release.
+    fn release(&mut self, now: Duration) -> Vec<RecvPayload> {
+        // The sort is stable, so the packets with the same release time keep their order
+        self.queue.sort_by_key(|(release, _)| *release);
+        let count = self.queue.partition_point(|(release, _)| *release <= now);
+        self.queue
+            .drain(..count)
+            .map(|(_, payload)| payload)
+            .collect()
+    }
+}
+
+fn apply_link_conditioners(
+    time: Res<Time<Real>>,
+    mut q_links: Query<(&mut Link, &mut SeededLinkConditioner)>,
+) {
+    let now = time.elapsed();
+    for (mut link, mut conditioner) in &mut q_links {
+        let received = link.recv.drain().collect::<Vec<_>>();
+        for payload in received {
+            conditioner.condition(payload, now);
+        }
+
+        for payload in conditioner.release(now) {
+            link.recv.push_raw(payload);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(seed: u64) -> Vec<RecvPayload> {
+        let mut conditioner = SeededLinkConditioner::new(
+            LinkConditionerConfig {
+                incoming_latency: Duration::from_millis(50),
+                incoming_jitter: Duration::from_millis(20),
+                incoming_loss: 0.25,
+            },
+            seed,
+        );
+
+        let mut received = Vec::new();
+        for i in 0..100u8 {
+            let now = Duration::from_millis(i as u64 * 10);
+            conditioner.condition(RecvPayload::from(vec![i]), now);
+            received.extend(conditioner.release(now));
+        }
+        received.extend(conditioner.release(Duration::from_secs(10)));
+        received
+    }
+
+    #[test]
+    fn test_seeded_conditions_are_reproducible() {
+        let received = run(42);
+        assert_eq!(received, run(42));
+
+        // Some packets are lost, and the others are all released eventually
+        assert!(received.len() < 100);
+        assert!(received.len() > 50);
+    }
+}
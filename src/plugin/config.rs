@@ -0,0 +1,424 @@
+//! Persistent configuration of the launcher, stored as RON files next to the executable.
+
+use std::{path::Path, str::FromStr, time::Duration};
+
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{conditioner::*, moderation::*, network::*, world::*};
+use crate::prelude::*;
+
+/// The path of the configuration file used by the game client.
+pub(super) const CLIENT_CONFIG_PATH: &str = "survicraft.ron";
+
+/// The path of the configuration file used by the server, both dedicated and hosted.
+pub(super) const SERVER_CONFIG_PATH: &str = "server.ron";
+
+/// The maximum simulated latency and jitter, in milliseconds.
+const MAX_SIMULATED_LATENCY_MS: u64 = 10_000;
+
+/// Simulated network conditions, applied to the incoming packets of every link. Used to test
+/// the game on bad networks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum NetworkConditions {
+    #[default]
+    None,
+    Good,
+    Average,
+    Poor,
+    Custom {
+        latency_ms: u64,
+        jitter_ms: u64,
+        /// The ratio of lost packets, between 0 and 1.
+        loss: f32,
+    },
+}
+
+impl NetworkConditions {
+    /// The seeded link conditioner for these conditions, `None` when the network is not degraded.
+    pub(super) fn conditioner(&self, seed: u64) -> Option<SeededLinkConditioner> {
+        self.conditioner_config()
+            .map(|config| SeededLinkConditioner::new(config, seed))
+    }
+
+    fn conditioner_config(&self) -> Option<LinkConditionerConfig> {
+        match *self {
+            NetworkConditions::None => None,
+            NetworkConditions::Good => Some(LinkConditionerConfig::good_condition()),
+            NetworkConditions::Average => Some(LinkConditionerConfig::average_condition()),
+            NetworkConditions::Poor => Some(LinkConditionerConfig::poor_condition()),
+            NetworkConditions::Custom {
+                latency_ms,
+                jitter_ms,
+                loss,
+            } => Some(LinkConditionerConfig {
+                incoming_latency: Duration::from_millis(latency_ms),
+                incoming_jitter: Duration::from_millis(jitter_ms),
+                incoming_loss: loss,
+            }),
+        }
+    }
+
+    /// Check that the custom conditions are in range, the presets are always valid.
+    pub(super) fn validate(&self) -> Result<(), String> {
+        let NetworkConditions::Custom {
+            latency_ms,
+            jitter_ms,
+            loss,
+        } = *self
+        else {
+            return Ok(());
+        };
+
+        if latency_ms > MAX_SIMULATED_LATENCY_MS {
+            return Err(format!(
+                "invalid latency {latency_ms} ms, expected at most {MAX_SIMULATED_LATENCY_MS} ms"
+            ));
+        }
+        if jitter_ms > MAX_SIMULATED_LATENCY_MS {
+            return Err(format!(
+                "invalid jitter {jitter_ms} ms, expected at most {MAX_SIMULATED_LATENCY_MS} ms"
+            ));
+        }
+        if !(0.0..=1.0).contains(&loss) {
+            return Err(format!(
+                "invalid loss {loss}, expected a ratio between 0 and 1"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The conditions from a config file or the command line, disabled when they are invalid.
+    fn checked(self) -> Self {
+        match self.validate() {
+            Ok(()) => self,
+            Err(err) => {
+                error!("Ignoring the simulated network conditions: {}", err);
+                NetworkConditions::None
+            }
+        }
+    }
+}
+
+/// Parse a preset (`none`, `good`, `average` or `poor`) or custom conditions as
+/// `<latency_ms>,<jitter_ms>,<loss>`.
+impl FromStr for NetworkConditions {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "none" => return Ok(NetworkConditions::None),
+            "good" => return Ok(NetworkConditions::Good),
+            "average" => return Ok(NetworkConditions::Average),
+            "poor" => return Ok(NetworkConditions::Poor),
+            _ => {}
+        }
+
+        let usage = "expected none, good, average, poor or <latency_ms>,<jitter_ms>,<loss>";
+        let [latency_ms, jitter_ms, loss] = s.split(',').map(str::trim).collect::<Vec<_>>()[..]
+        else {
+            return Err(format!("invalid network conditions '{s}', {usage}"));
+        };
+
+        let latency_ms = latency_ms
+            .parse()
+            .map_err(|_| format!("invalid latency '{latency_ms}', {usage}"))?;
+        let jitter_ms = jitter_ms
+            .parse()
+            .map_err(|_| format!("invalid jitter '{jitter_ms}', {usage}"))?;
+        let loss = loss
+            .parse()
+            .map_err(|_| format!("invalid loss '{loss}', {usage}"))?;
+
+        let conditions = NetworkConditions::Custom {
+            latency_ms,
+            jitter_ms,
+            loss,
+        };
+        conditions.validate()?;
+        Ok(conditions)
+    }
+}
+
+/// The network conditions simulated by the client.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct ClientNetworkConditions {
+    pub conditions: NetworkConditions,
+    pub seed: u64,
+}
+
+/// The configuration of the game client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct ClientConfig {
+    /// The persistent identity of the player, used as the netcode client id.
+    pub client_id: u64,
+    /// The secret that proves the identity of the player to the servers. Unlike the identity it
+    /// is never shared with the other players.
+    pub secret: String,
+    /// The key bindings of the player.
+    pub bindings: KeyBindings,
+    /// The simulated network conditions, for testing.
+    pub network_conditions: NetworkConditions,
+    /// The seed of the simulated losses and jitter, the same seed gives the same conditions.
+    pub network_seed: u64,
+    /// The distances of the levels of detail of the terrain around the player.
+    pub terrain_lod: TerrainLodSettings,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            client_id: rand::random(),
+            secret: format!("{:032x}", rand::random::<u128>()),
+            bindings: KeyBindings::default(),
+            network_conditions: NetworkConditions::default(),
+            network_seed: 0,
+            terrain_lod: TerrainLodSettings::default(),
+        }
+    }
+}
+
+/// The persistent identity of the local player.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Deref)]
+pub(super) struct ClientIdentity(pub u64);
+
+/// The secret of the local player, sent to the server to prove the identity.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Deref)]
+pub(super) struct ClientSecret(pub String);
+
+/// The configuration of the server.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct ServerConfig {
+    /// The identities of the players that can use operator commands. The players only get the
+    /// permission once they proved their identity with their secret.
+    pub operators: Vec<u64>,
+    /// The path of the file where the bans are stored.
+    pub ban_file: String,
+    /// The path of the file where the secrets of the known identities are stored.
+    pub identity_file: String,
+    /// The limits applied to the chat messages of the players.
+    pub chat: ChatModerationConfig,
+    /// Who can edit the terrain, and how often.
+    pub terrain_edits: TerrainEditConfig,
+    /// The simulated network conditions for the links of the clients, for testing.
+    pub network_conditions: NetworkConditions,
+    /// The seed of the simulated losses and jitter, the same seed gives the same conditions.
+    pub network_seed: u64,
+    /// The local address of the Prometheus metrics endpoint, for example `127.0.0.1:9100`. The
+    /// endpoint is disabled when this is not set.
+    pub metrics_address: Option<String>,
+    /// The world generation preset used by the dedicated server for new worlds. Existing worlds
+    /// keep the preset stored in the world save.
+    pub world_preset: TerrainGenerationPreset,
+    /// The distances of the levels of detail of the terrain used by the dedicated server, which
+    /// only meshes the chunks that need a collider.
+    pub terrain_lod: TerrainLodSettings,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            operators: Vec::new(),
+            ban_file: "bans.ron".to_string(),
+            identity_file: "identities.ron".to_string(),
+            chat: ChatModerationConfig::default(),
+            terrain_edits: TerrainEditConfig::default(),
+            network_conditions: NetworkConditions::default(),
+            network_seed: 0,
+            metrics_address: None,
+            world_preset: TerrainGenerationPreset::default(),
+            terrain_lod: TerrainLodSettings::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Whether the identity of the peer is an operator. The callers must also check that the
+    /// connection of the peer is an `AuthenticatedPeer`, the identities are public.
+    pub fn is_operator(&self, peer: PeerId) -> bool {
+        peer_identity(peer).is_some_and(|identity| self.operators.contains(&identity))
+    }
+}
+
+/// Load a configuration file, falling back to the default configuration if the file is missing
+/// or cannot be parsed.
+pub(super) fn load_config<T: DeserializeOwned + Default>(path: impl AsRef<Path>) -> T {
+    let path = path.as_ref();
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            debug!("Could not read config file {}: {}", path.display(), err);
+            return T::default();
+        }
+    };
+
+    match ron::from_str(&content) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("Could not parse config file {}: {}", path.display(), err);
+            T::default()
+        }
+    }
+}
+
+/// Save a configuration file in a human readable format.
+pub(super) fn save_config<T: Serialize>(path: impl AsRef<Path>, config: &T) -> anyhow::Result<()> {
+    let content = ron::ser::to_string_pretty(config, ron::ser::PrettyConfig::default())?;
+    std::fs::write(path, content)?;
+
+    Ok(())
+}
+
+pub(super) struct ConfigPlugin {
+    pub dedicated: bool,
+    /// Bots use a fresh identity and the default bindings, and never touch the config file.
+    pub bot: bool,
+    /// Network conditions from the command line, used instead of the configured ones.
+    pub network_conditions: Option<NetworkConditions>,
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        // The server config is saved right away so the defaults can be edited by the admins
+        let mut server_config: ServerConfig = load_config(SERVER_CONFIG_PATH);
+        if !Path::new(SERVER_CONFIG_PATH).exists() {
+            if let Err(err) = save_config(SERVER_CONFIG_PATH, &server_config) {
+                error!(
+                    "Failed to save server config to {}: {}",
+                    SERVER_CONFIG_PATH, err
+                );
+            }
+        }
+        if let Some(network_conditions) = self.network_conditions {
+            server_config.network_conditions = network_conditions;
+        }
+        server_config.network_conditions = server_config.network_conditions.checked();
+        if let Err(err) = server_config.world_preset.validate() {
+            error!(
+                "Invalid world preset in {}, using the default preset: {}",
+                SERVER_CONFIG_PATH, err
+            );
+            server_config.world_preset = TerrainGenerationPreset::default();
+        }
+        if server_config.network_conditions != NetworkConditions::None {
+            warn!(
+                "Simulating network conditions on the server: {:?}",
+                server_config.network_conditions
+            );
+        }
+        let terrain_lod = server_config.terrain_lod.clone();
+        app.insert_resource(server_config);
+
+        if self.dedicated {
+            app.insert_resource(terrain_lod);
+            // The dedicated server has no player input, but the shared systems still expect
+            // the bindings to exist.
+            app.init_resource::<KeyBindings>();
+            return;
+        }
+
+        // The client config is saved right away so the generated identity is persisted
+        let config = if self.bot {
+            ClientConfig::default()
+        } else {
+            let config: ClientConfig = load_config(CLIENT_CONFIG_PATH);
+            if let Err(err) = save_config(CLIENT_CONFIG_PATH, &config) {
+                error!(
+                    "Failed to save client config to {}: {}",
+                    CLIENT_CONFIG_PATH, err
+                );
+            }
+            config
+        };
+        app.insert_resource(ClientIdentity(config.client_id));
+        app.insert_resource(ClientSecret(config.secret));
+        app.insert_resource(config.bindings);
+        app.insert_resource(config.terrain_lod);
+
+        let network_conditions = self
+            .network_conditions
+            .unwrap_or(config.network_conditions)
+            .checked();
+        if network_conditions != NetworkConditions::None {
+            warn!(
+                "Simulating network conditions on the client: {:?}",
+                network_conditions
+            );
+        }
+        app.insert_resource(ClientNetworkConditions {
+            conditions: network_conditions,
+            seed: config.network_seed,
+        });
+
+        if self.bot {
+            return;
+        }
+
+        app.add_systems(
+            Update,
+            save_client_config
+                .run_if(resource_changed::<KeyBindings>.and(not(resource_added::<KeyBindings>))),
+        );
+    }
+}
+
+fn save_client_config(
+    identity: Res<ClientIdentity>,
+    secret: Res<ClientSecret>,
+    bindings: Res<KeyBindings>,
+) {
+    // NOTE: the network conditions are read again so the command line override is not saved
+    let saved: ClientConfig = load_config(CLIENT_CONFIG_PATH);
+    let config = ClientConfig {
+        client_id: **identity,
+        secret: (**secret).clone(),
+        bindings: bindings.clone(),
+        network_conditions: saved.network_conditions,
+        network_seed: saved.network_seed,
+        terrain_lod: saved.terrain_lod,
+    };
+
+    match save_config(CLIENT_CONFIG_PATH, &config) {
+        Ok(()) => debug!("Saved client config to {}", CLIENT_CONFIG_PATH),
+        Err(err) => error!(
+            "Failed to save client config to {}: {}",
+            CLIENT_CONFIG_PATH, err
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network_conditions() {
+        assert_eq!("none".parse(), Ok(NetworkConditions::None));
+        assert_eq!("Poor".parse(), Ok(NetworkConditions::Poor));
+        assert_eq!(
+            "100, 10, 0.05".parse(),
+            Ok(NetworkConditions::Custom {
+                latency_ms: 100,
+                jitter_ms: 10,
+                loss: 0.05,
+            })
+        );
+
+        assert!("terrible".parse::<NetworkConditions>().is_err());
+        assert!("100,10".parse::<NetworkConditions>().is_err());
+        assert!("100,10,2".parse::<NetworkConditions>().is_err());
+        assert!("100,10,NaN".parse::<NetworkConditions>().is_err());
+        assert!("100000,10,0".parse::<NetworkConditions>().is_err());
+        assert!(NetworkConditions::Custom {
+            latency_ms: 0,
+            jitter_ms: 0,
+            loss: -0.5,
+        }
+        .validate()
+        .is_err());
+    }
+}
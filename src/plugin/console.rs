@@ -0,0 +1,109 @@
+//! Interactive console of the dedicated server.
+//!
+//! A thread reads the lines from stdin and sends them through a channel to the app, where they
+//! are executed as commands with console permissions. The leading `/` is optional.
+
+use std::io::BufRead;
+
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, Sender};
+use lightyear::prelude::*;
+
+use super::{commands::*, network::*, shutdown::*};
+use crate::prelude::*;
+
+#[derive(Resource, Debug, Clone)]
+struct ConsoleInput(Receiver<String>);
+
+pub(super) struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        spawn_console_thread(sender);
+        app.insert_resource(ConsoleInput(receiver));
+
+        app.add_systems(Update, read_console_input);
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "say",
+                "Send a chat message to all the players as Server",
+                PermissionLevel::Console,
+            )
+            .arg("message", ArgKind::Text),
+            say_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "stop",
+                "Disconnect the players, save the world and stop the server",
+                PermissionLevel::Console,
+            ),
+            stop_command,
+        );
+    }
+}
+
+fn spawn_console_thread(sender: Sender<String>) {
+    let result = std::thread::Builder::new()
+        .name("console".to_string())
+        .spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        error!("Failed to read console input: {}", err);
+                        break;
+                    }
+                }
+            }
+            debug!("Console input closed");
+        });
+
+    if let Err(err) = result {
+        error!("Failed to start the console thread: {}", err);
+    }
+}
+
+fn read_console_input(console: Res<ConsoleInput>, mut ev_command: EventWriter<CommandInputEvent>) {
+    for line in console.0.try_iter() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        ev_command.write(CommandInputEvent {
+            source: CommandSource::Console,
+            input: line.to_string(),
+        });
+    }
+}
+
+fn say_command(
+    In(ctx): In<CommandContext>,
+    mut ev_server: EventWriter<ToClient<ServerChatMessageEvent>>,
+) {
+    let message = ctx.args.text(0).unwrap_or_default().to_string();
+    info!("[Server] {}", message);
+
+    ev_server.write(ToClient {
+        target: NetworkTarget::All,
+        event: ServerChatMessageEvent {
+            sender: PeerId::Server,
+            recipient: None,
+            kind: ChatMessageKind::System,
+            message,
+        },
+    });
+}
+
+fn stop_command(In(_ctx): In<CommandContext>, mut ev_shutdown: EventWriter<ServerShutdownEvent>) {
+    ev_shutdown.write(ServerShutdownEvent {
+        reason: "Stopped from the console".to_string(),
+    });
+}
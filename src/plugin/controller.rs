@@ -4,7 +4,7 @@ use leafwing_input_manager::prelude::*;
 use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use super::network::*;
+use super::{commands::*, network::*, states::*};
 use crate::prelude::*;
 
 /// Marker component for the player character entity. Spawn this when you
@@ -47,6 +47,7 @@ impl Plugin for PlayerControllerPlugin {
             Update,
             (
                 client_handle_player_spawned,
+                client_update_character_input_map.run_if(resource_changed::<KeyBindings>),
                 client_update_character_input,
                 client_update_head_input,
                 server_handle_spawn_player,
@@ -54,6 +55,21 @@ impl Plugin for PlayerControllerPlugin {
                 sync_character_rotation,
             ),
         );
+
+        app.add_systems(OnEnter(PlayingState::Paused), disable_player_input);
+        app.add_systems(OnExit(PlayingState::Paused), enable_player_input);
+        app.add_systems(OnExit(LauncherStates::Playing), cleanup_player_characters);
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "tp",
+                "Teleport your character to the given position",
+                PermissionLevel::Operator,
+            )
+            .arg("x", ArgKind::Float)
+            .arg("z", ArgKind::Float),
+            tp_command,
+        );
     }
 }
 
@@ -110,12 +126,32 @@ fn server_handle_spawn_player(
     }
 }
 
+fn character_input_map(bindings: &KeyBindings) -> InputMap<CharacterAction> {
+    let mut input_map =
+        InputMap::default().with_dual_axis(CharacterAction::Move, GamepadStick::LEFT);
+    if let Some(dpad) = bindings.movement_dpad() {
+        input_map.insert_dual_axis(CharacterAction::Move, dpad);
+    } else {
+        warn!("Movement actions are not all bound to keyboard keys, keyboard movement disabled");
+    }
+    bindings.insert_buttons(&mut input_map, GameAction::Jump, CharacterAction::Jump);
+
+    input_map
+}
+
+fn head_input_map() -> InputMap<HeadAction> {
+    InputMap::default()
+        .with_dual_axis(HeadAction::Look, GamepadStick::RIGHT)
+        .with_dual_axis(HeadAction::Look, MouseMove::default())
+}
+
 fn client_handle_player_spawned(
     mut commands: Commands,
     q_player: Query<
         (Entity, &PlayerId, Has<Controlled>),
         (Added<Predicted>, With<PlayerControllerMarker>),
     >,
+    bindings: Res<KeyBindings>,
 ) {
     for (entity, PlayerId(peer), is_controlled) in &q_player {
         debug!("Adding KinematicCharacterBundle to entity {entity:?}");
@@ -134,9 +170,7 @@ fn client_handle_player_spawned(
                 Name::new("Head"),
                 HeadControllerMarker,
                 Camera3d::default(),
-                InputMap::default()
-                    .with_dual_axis(HeadAction::Look, GamepadStick::RIGHT)
-                    .with_dual_axis(HeadAction::Look, MouseMove::default()),
+                head_input_map(),
                 // Head controller related components
                 HeadControllerInput::default(),
                 HeadController {
@@ -150,17 +184,98 @@ fn client_handle_player_spawned(
                 Replicate::to_server(),
             ));
 
-            commands.entity(entity).insert((InputMap::default()
-                .with(CharacterAction::Jump, KeyCode::Space)
-                .with(CharacterAction::Jump, GamepadButton::South)
-                .with_dual_axis(CharacterAction::Move, GamepadStick::LEFT)
-                .with_dual_axis(CharacterAction::Move, VirtualDPad::wasd()),));
+            commands
+                .entity(entity)
+                .insert(character_input_map(&bindings));
         } else {
             debug!("Remote character predicted for us: {entity:?}");
         }
     }
 }
 
+fn client_update_character_input_map(
+    mut q_player: Query<&mut InputMap<CharacterAction>, With<PlayerControllerMarker>>,
+    bindings: Res<KeyBindings>,
+) {
+    for mut input_map in q_player.iter_mut() {
+        debug!("Updating character input map from key bindings");
+        *input_map = character_input_map(&bindings);
+    }
+}
+
+fn disable_player_input(
+    mut q_character: Query<&mut ActionState<CharacterAction>, With<InputMap<CharacterAction>>>,
+    mut q_head: Query<&mut ActionState<HeadAction>, With<InputMap<HeadAction>>>,
+) {
+    debug!("Disabling player input while paused");
+
+    for mut action_state in q_character.iter_mut() {
+        action_state.disable();
+    }
+    for mut action_state in q_head.iter_mut() {
+        action_state.disable();
+    }
+}
+
+fn enable_player_input(
+    mut q_character: Query<&mut ActionState<CharacterAction>, With<InputMap<CharacterAction>>>,
+    mut q_head: Query<&mut ActionState<HeadAction>, With<InputMap<HeadAction>>>,
+) {
+    debug!("Enabling player input after pause");
+
+    for mut action_state in q_character.iter_mut() {
+        action_state.enable();
+    }
+    for mut action_state in q_head.iter_mut() {
+        action_state.enable();
+    }
+}
+
+fn cleanup_player_characters(
+    mut commands: Commands,
+    q_player: Query<Entity, Or<(With<PlayerControllerMarker>, With<HeadControllerMarker>)>>,
+) {
+    debug!("Cleaning up player characters");
+
+    for entity in &q_player {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// The height at which the characters are teleported, so they fall on top of the terrain.
+const TELEPORT_HEIGHT: f32 = 30.0;
+/// The largest coordinate the characters can be teleported to, far enough for any world while
+/// keeping the physics precise.
+const MAX_TELEPORT_DISTANCE: f32 = 100_000.0;
+
+fn tp_command(
+    In(ctx): In<CommandContext>,
+    mut q_player: Query<(&PlayerId, &mut Position), With<PlayerControllerMarker>>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let Some(peer) = ctx.peer() else {
+        ev_reply.write(ctx.reply("Only players can be teleported"));
+        return;
+    };
+    let Some((_, mut position)) = q_player.iter_mut().find(|(id, _)| id.0 == peer) else {
+        ev_reply.write(ctx.reply("You do not have a character"));
+        return;
+    };
+
+    let x = ctx.args.float(0).unwrap_or_default();
+    let z = ctx.args.float(1).unwrap_or_default();
+    if x.abs() > MAX_TELEPORT_DISTANCE || z.abs() > MAX_TELEPORT_DISTANCE {
+        ev_reply.write(ctx.reply(format!(
+            "The coordinates must be between -{MAX_TELEPORT_DISTANCE} and {MAX_TELEPORT_DISTANCE}"
+        )));
+        return;
+    }
+    position.0 = Vec3::new(x, TELEPORT_HEIGHT, z);
+
+    debug!("Teleported peer {:?} to {:?}", peer, position.0);
+    ev_reply.write(ctx.reply(format!("Teleported to {x:.1} {z:.1}")));
+}
+
 fn server_add_head_controller_to_new_players(
     mut commands: Commands,
     q_head: Query<(Entity, &PlayerId), (With<HeadControllerMarker>, Without<HeadControllerTarget>)>,
@@ -0,0 +1,435 @@
+//! Network diagnostics: packet loss, jitter, bandwidth, rollbacks and input buffer depth.
+//!
+//! Every link, on the client and on the server, gets a `LinkDiagnostics` component that is
+//! updated once per second. The client shows the diagnostics of its link in the status bar when
+//! enabled in the display settings, and the dedicated server logs a summary of every link
+//! periodically. The packet loss is measured with unreliable probes sent by the server.
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use leafwing_input_manager::prelude::*;
+use lightyear::{
+    link::LinkSet,
+    prelude::{input::InputBuffer, server::ClientOf, *},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{controller::*, network::*, states::*};
+use crate::prelude::*;
+
+/// How often the rates of the diagnostics are computed.
+const DIAGNOSTICS_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the dedicated server logs a summary of the links.
+const SERVER_SUMMARY_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the server sends a probe to measure the packet loss.
+const PROBE_INTERVAL: Duration = Duration::from_millis(100);
+/// The number of round trip time samples used to compute the jitter.
+const RTT_SAMPLES: usize = 16;
+
+/// Unreliable message sent periodically by the server. Missing sequence numbers are counted as
+/// lost packets by the client.
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
+pub(super) struct ServerProbeEvent {
+    pub sequence: u32,
+}
+
+struct ProbeChannel;
+
+/// The diagnostics of a link, measured over the last interval.
+#[derive(Component, Debug, Clone, Default)]
+pub(super) struct LinkDiagnostics {
+    rtt_samples: VecDeque<f32>,
+    bytes_in: usize,
+    bytes_out: usize,
+    probes_expected: u32,
+    probes_received: u32,
+    last_probe: Option<u32>,
+
+    pub rtt_ms: f32,
+    /// The mean deviation of the round trip time.
+    pub jitter_ms: f32,
+    /// The ratio of lost packets, between 0 and 1.
+    pub packet_loss: f32,
+    pub bytes_in_per_sec: f32,
+    pub bytes_out_per_sec: f32,
+}
+
+impl LinkDiagnostics {
+    fn record_probe(&mut self, sequence: u32) {
+        self.probes_received += 1;
+        match self.last_probe {
+            Some(last) if sequence > last => {
+                self.probes_expected += sequence - last;
+                self.last_probe = Some(sequence);
+            }
+            // Late probes were already counted as expected
+            Some(_) => {}
+            None => {
+                self.probes_expected += 1;
+                self.last_probe = Some(sequence);
+            }
+        }
+    }
+
+    /// Compute the rates over the elapsed interval and start a new interval.
+    fn update(&mut self, rtt: Duration, elapsed: f32) {
+        self.rtt_ms = rtt.as_secs_f32() * 1000.0;
+        self.rtt_samples.push_back(self.rtt_ms);
+        if self.rtt_samples.len() > RTT_SAMPLES {
+            self.rtt_samples.pop_front();
+        }
+
+        let mean = self.rtt_samples.iter().sum::<f32>() / self.rtt_samples.len() as f32;
+        self.jitter_ms = self
+            .rtt_samples
+            .iter()
+            .map(|sample| (sample - mean).abs())
+            .sum::<f32>()
+            / self.rtt_samples.len() as f32;
+
+        if self.probes_expected > 0 {
+            let received = self.probes_received.min(self.probes_expected);
+            self.packet_loss = 1.0 - received as f32 / self.probes_expected as f32;
+        }
+
+        let elapsed = elapsed.max(f32::EPSILON);
+        self.bytes_in_per_sec = self.bytes_in as f32 / elapsed;
+        self.bytes_out_per_sec = self.bytes_out as f32 / elapsed;
+
+        self.bytes_in = 0;
+        self.bytes_out = 0;
+        self.probes_expected = 0;
+        self.probes_received = 0;
+    }
+}
+
+/// The prediction diagnostics of the local player, measured over the last interval.
+#[derive(Resource, Debug, Clone, Default)]
+pub(super) struct PredictionDiagnostics {
+    rollbacks: u32,
+
+    pub rollbacks_per_sec: f32,
+    /// The number of ticks of `CharacterAction` inputs buffered ahead of the current tick.
+    pub input_buffer_depth: i32,
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+struct ProbeSequence(u32);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+enum NetworkStatusItem {
+    PacketLoss,
+    Jitter,
+    BytesIn,
+    BytesOut,
+    Rollbacks,
+    InputBuffer,
+}
+
+pub(super) struct DiagnosticsPlugin {
+    pub dedicated: bool,
+}
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        // The protocol must be the same on the client and on the server
+        app.add_server_event::<ServerProbeEvent, ProbeChannel>();
+        app.add_channel::<ProbeChannel>(ChannelSettings {
+            mode: ChannelMode::UnorderedUnreliable,
+            ..default()
+        })
+        .add_direction(NetworkDirection::ServerToClient);
+
+        app.init_resource::<ProbeSequence>();
+        app.add_observer(on_link_added);
+
+        app.add_systems(PreUpdate, measure_bytes_in.after(LinkSet::Receive));
+        app.add_systems(PostUpdate, measure_bytes_out.before(LinkSet::Send));
+        app.add_systems(
+            Update,
+            (
+                send_probes
+                    .run_if(any_with_component::<Server>)
+                    .run_if(on_timer(PROBE_INTERVAL)),
+                update_link_diagnostics.run_if(on_timer(DIAGNOSTICS_INTERVAL)),
+            ),
+        );
+
+        if self.dedicated {
+            app.add_systems(
+                Update,
+                log_server_summary.run_if(on_timer(SERVER_SUMMARY_INTERVAL)),
+            );
+            return;
+        }
+
+        app.init_resource::<PredictionDiagnostics>();
+        app.add_observer(on_rollback);
+        app.add_systems(
+            Update,
+            (
+                receive_probes,
+                update_prediction_diagnostics.run_if(on_timer(DIAGNOSTICS_INTERVAL)),
+                setup_network_status.run_if(
+                    resource_changed::<DisplayNetworkStatsSetting>
+                        .or(any_match_filter::<Added<StatusBarRoot>>),
+                ),
+                update_network_status,
+            )
+                .chain()
+                .run_if(in_state(LauncherStates::Playing)),
+        );
+    }
+}
+
+fn on_link_added(trigger: Trigger<OnAdd, Link>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(LinkDiagnostics::default());
+}
+
+// The link buffers can only be drained, so the payloads are put back in the same order. The
+// payloads are reference counted and are not copied.
+fn measure_bytes_in(mut q_links: Query<(&mut Link, &mut LinkDiagnostics)>) {
+    for (mut link, mut diagnostics) in &mut q_links {
+        let received = link.recv.drain().collect::<Vec<_>>();
+        diagnostics.bytes_in += received.iter().map(|payload| payload.len()).sum::<usize>();
+        for payload in received {
+            link.recv.push_raw(payload);
+        }
+    }
+}
+
+fn measure_bytes_out(mut q_links: Query<(&mut Link, &mut LinkDiagnostics)>) {
+    for (mut link, mut diagnostics) in &mut q_links {
+        let sent = link.send.drain().collect::<Vec<_>>();
+        diagnostics.bytes_out += sent.iter().map(|payload| payload.len()).sum::<usize>();
+        for payload in sent {
+            link.send.push(payload);
+        }
+    }
+}
+
+fn update_link_diagnostics(mut q_links: Query<(&Link, &mut LinkDiagnostics)>) {
+    let elapsed = DIAGNOSTICS_INTERVAL.as_secs_f32();
+    for (link, mut diagnostics) in &mut q_links {
+        diagnostics.update(link.stats.rtt, elapsed);
+    }
+}
+
+fn send_probes(
+    mut sequence: ResMut<ProbeSequence>,
+    mut ev_probe: EventWriter<ToClient<ServerProbeEvent>>,
+) {
+    sequence.0 = sequence.0.wrapping_add(1);
+    ev_probe.write(ToClient {
+        target: NetworkTarget::All,
+        event: ServerProbeEvent {
+            sequence: sequence.0,
+        },
+    });
+}
+
+fn receive_probes(
+    mut ev_probe: EventReader<ServerProbeEvent>,
+    mut q_link: Query<&mut LinkDiagnostics, With<Client>>,
+) {
+    for ev in ev_probe.read() {
+        for mut diagnostics in &mut q_link {
+            diagnostics.record_probe(ev.sequence);
+        }
+    }
+}
+
+fn on_rollback(_: Trigger<OnAdd, Rollback>, mut prediction: ResMut<PredictionDiagnostics>) {
+    prediction.rollbacks += 1;
+}
+
+fn update_prediction_diagnostics(
+    mut prediction: ResMut<PredictionDiagnostics>,
+    timeline: Single<&LocalTimeline, With<Client>>,
+    q_buffer: Query<
+        &InputBuffer<ActionState<CharacterAction>>,
+        (With<PlayerControllerMarker>, With<Predicted>),
+    >,
+) {
+    let tick = timeline.tick();
+
+    prediction.rollbacks_per_sec = prediction.rollbacks as f32 / DIAGNOSTICS_INTERVAL.as_secs_f32();
+    prediction.rollbacks = 0;
+    prediction.input_buffer_depth = q_buffer
+        .iter()
+        .filter_map(|buffer| buffer.get_last_with_tick().map(|(last, _)| last - tick))
+        .map(i32::from)
+        .max()
+        .unwrap_or(0)
+        .max(0);
+}
+
+fn log_server_summary(
+    q_links: Query<(&RemoteId, &LinkDiagnostics), (With<ClientOf>, With<Connected>)>,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+) {
+    info!(
+        "Network summary: {} client(s) connected",
+        q_links.iter().count()
+    );
+
+    for (RemoteId(peer), diagnostics) in &q_links {
+        let username = q_players
+            .iter()
+            .find(|(PlayerId(id), _)| id == peer)
+            .map(|(_, metadata)| metadata.username.as_str())
+            .unwrap_or("unknown");
+
+        info!(
+            "  {} ({:?}): rtt {:.0} ms, jitter {:.1} ms, in {:.1} KB/s, out {:.1} KB/s",
+            username,
+            peer,
+            diagnostics.rtt_ms,
+            diagnostics.jitter_ms,
+            diagnostics.bytes_in_per_sec / 1024.0,
+            diagnostics.bytes_out_per_sec / 1024.0,
+        );
+    }
+}
+
+fn setup_network_status(
+    mut commands: Commands,
+    show: Res<DisplayNetworkStatsSetting>,
+    q_items: Query<Entity, With<NetworkStatusItem>>,
+) {
+    for entity in &q_items {
+        commands.entity(entity).despawn();
+    }
+    if !**show {
+        return;
+    }
+
+    let items = [
+        (
+            NetworkStatusItem::PacketLoss,
+            "% loss",
+            StatusValue::float(0.0, 1),
+            vec![
+                (Some(1.0), Color::srgb(0.0, 1.0, 0.0)),
+                (Some(5.0), Color::srgb(1.0, 1.0, 0.0)),
+                (None, Color::srgb(1.0, 0.0, 0.0)),
+            ],
+        ),
+        (
+            NetworkStatusItem::Jitter,
+            "ms jitter",
+            StatusValue::float(0.0, 1),
+            vec![
+                (Some(10.0), Color::srgb(0.0, 1.0, 0.0)),
+                (Some(30.0), Color::srgb(1.0, 1.0, 0.0)),
+                (None, Color::srgb(1.0, 0.0, 0.0)),
+            ],
+        ),
+        (
+            NetworkStatusItem::BytesIn,
+            "KB/s in",
+            StatusValue::float(0.0, 1),
+            vec![],
+        ),
+        (
+            NetworkStatusItem::BytesOut,
+            "KB/s out",
+            StatusValue::float(0.0, 1),
+            vec![],
+        ),
+        (
+            NetworkStatusItem::Rollbacks,
+            "rollbacks/s",
+            StatusValue::float(0.0, 0),
+            vec![
+                (Some(2.0), Color::srgb(0.0, 1.0, 0.0)),
+                (Some(10.0), Color::srgb(1.0, 1.0, 0.0)),
+                (None, Color::srgb(1.0, 0.0, 0.0)),
+            ],
+        ),
+        (
+            NetworkStatusItem::InputBuffer,
+            "input ticks",
+            StatusValue::Int(0),
+            vec![],
+        ),
+    ];
+
+    for (kind, label, value, mapping) in items {
+        commands.spawn((
+            Name::new(format!("StatusBarItem {kind:?}")),
+            kind,
+            StatusBarItem {
+                icon: None,
+                value,
+                label: label.to_string(),
+                mapping,
+            },
+            StateScoped(LauncherStates::Playing),
+        ));
+    }
+}
+
+fn update_network_status(
+    q_link: Query<&LinkDiagnostics, With<Client>>,
+    prediction: Res<PredictionDiagnostics>,
+    mut q_items: Query<(&NetworkStatusItem, &mut StatusBarItem)>,
+) {
+    let Some(link) = q_link.iter().next() else {
+        return;
+    };
+
+    for (kind, mut item) in &mut q_items {
+        let value = match kind {
+            NetworkStatusItem::PacketLoss => StatusValue::float(link.packet_loss as f64 * 100.0, 1),
+            NetworkStatusItem::Jitter => StatusValue::float(link.jitter_ms as f64, 1),
+            NetworkStatusItem::BytesIn => {
+                StatusValue::float(link.bytes_in_per_sec as f64 / 1024.0, 1)
+            }
+            NetworkStatusItem::BytesOut => {
+                StatusValue::float(link.bytes_out_per_sec as f64 / 1024.0, 1)
+            }
+            NetworkStatusItem::Rollbacks => {
+                StatusValue::float(prediction.rollbacks_per_sec as f64, 0)
+            }
+            NetworkStatusItem::InputBuffer => {
+                StatusValue::Int(prediction.input_buffer_depth as i64)
+            }
+        };
+
+        if item.value != value {
+            item.value = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_loss_from_probes() {
+        let mut diagnostics = LinkDiagnostics::default();
+        for sequence in [1, 2, 4, 5, 3, 8] {
+            diagnostics.record_probe(sequence);
+        }
+        diagnostics.update(Duration::from_millis(50), 1.0);
+
+        // 8 probes were expected, 6 were received
+        assert!((diagnostics.packet_loss - 0.25).abs() < f32::EPSILON);
+        assert_eq!(diagnostics.rtt_ms, 50.0);
+    }
+
+    #[test]
+    fn test_jitter_from_rtt_samples() {
+        let mut diagnostics = LinkDiagnostics::default();
+        for rtt in [40, 60, 40, 60] {
+            diagnostics.update(Duration::from_millis(rtt), 1.0);
+        }
+
+        assert!((diagnostics.jitter_ms - 10.0).abs() < 1e-3);
+    }
+}
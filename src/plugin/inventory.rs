@@ -1,7 +1,12 @@
+use avian3d::prelude::*;
 use bevy::prelude::*;
+use lightyear::connection::host::HostClient;
 
 use crate::common::prelude::*;
-use super::controller::*;
+use super::{commands::*, controller::*, network::*, states::*};
+
+/// The maximum number of items that can be spawned with a single give command.
+const MAX_GIVE_ITEMS: i64 = 64;
 
 pub(super) struct GameInventoryPlugin {
     pub render: bool,
@@ -18,20 +23,84 @@ impl Plugin for GameInventoryPlugin {
 
         app.add_systems(Update, testing_spawn_item);
         app.add_systems(Update, test_f_input);
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "give",
+                "Spawn items in front of your character, only for the host until items are replicated",
+                PermissionLevel::Operator,
+            )
+            .arg("item", ArgKind::Word)
+            .optional_arg("n", ArgKind::Int),
+            give_command,
+        );
+    }
+}
+
+fn give_command(
+    In(ctx): In<CommandContext>,
+    q_player: Query<(&PlayerId, &Position, &Rotation), With<PlayerControllerMarker>>,
+    q_host: Query<(), With<HostClient>>,
+    item_assets: Res<ItemAssets>,
+    mut commands: Commands,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let CommandSource::Player { peer, owner } = ctx.source else {
+        ev_reply.write(ctx.reply("Only players can receive items"));
+        return;
+    };
+    // Items are not replicated yet, the items spawned for the other players would only exist on
+    // the server
+    if !q_host.contains(owner) {
+        ev_reply.write(ctx.reply("Only the host can receive items until items are replicated"));
+        return;
+    }
+    let Some((_, position, rotation)) = q_player.iter().find(|(id, _, _)| id.0 == peer) else {
+        ev_reply.write(ctx.reply("You do not have a character"));
+        return;
+    };
+
+    let id = ctx.args.text(0).unwrap_or_default().to_string();
+    let Some(item) = item_assets.get_item(&id) else {
+        let ids = item_assets
+            .items
+            .iter()
+            .map(|item| item.id.as_str())
+            .collect::<Vec<_>>();
+        ev_reply.write(ctx.reply(format!(
+            "Unknown item '{}', available items: {}",
+            id,
+            ids.join(", ")
+        )));
+        return;
+    };
+    let count = ctx.args.int(1).unwrap_or(1).clamp(1, MAX_GIVE_ITEMS);
+
+    let forward = (rotation.0 * Vec3::NEG_Z).xz().normalize_or_zero().extend(0.0).xzy();
+    for i in 0..count {
+        commands.spawn((
+            Name::new("Item"),
+            Item(item.id.clone()),
+            Transform::from_translation(position.0 + forward * 3.0 + Vec3::Y * i as f32),
+            StateScoped(LauncherStates::Playing),
+        ));
     }
+
+    debug!("Gave {} x {} to peer {:?}", count, item.id, peer);
+    ev_reply.write(ctx.reply(format!("Gave {} x {}", count, item.name)));
 }
 
 // NOTE: These are for debug/testing purposes only
 
 // testing system:
-// - press Z will spawn 1 wood item at the player position
+// - press SpawnItem (Z) will spawn 1 wood item at the player position
 fn testing_spawn_item(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    input: ActionInput,
     player_query: Query<&Transform, With<PlayerControllerMarker>>,
     item_assets: Res<ItemAssets>,
     mut commands: Commands,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyZ) {
+    if input.just_pressed(GameAction::SpawnItem) {
         for transform in player_query.iter() {
             let Some(wood_item) = item_assets.get_item(&"wood".to_string()) else {
                 error!("No wood item found");
@@ -42,15 +111,17 @@ fn testing_spawn_item(
                 Name::new("Item"),
                 Item(wood_item.id.clone()),
                 Transform::from_translation(transform.translation + transform.forward().xz().extend(0.0).xzy() * 3.0),
+                StateScoped(LauncherStates::Playing),
             ));
         }
     }
 }
 
 // testing system:
-// press F will set the craft input to true for 1 frame
-fn test_f_input(mut query: Query<&mut CrafterInput>, keyboard_input: Res<ButtonInput<KeyCode>>) {
-    for mut input in &mut query {
-        input.craft = keyboard_input.just_pressed(KeyCode::KeyF);
+// press Craft (F) will set the craft input to true for 1 frame
+fn test_f_input(mut query: Query<&mut CrafterInput>, input: ActionInput) {
+    let craft = input.just_pressed(GameAction::Craft);
+    for mut crafter in &mut query {
+        crafter.craft = craft;
     }
 }
@@ -0,0 +1,155 @@
+//! Headless generation of world maps.
+//!
+//! The tiles are sampled with the same generators, climate and features as the chunks of the
+//! game, so the maps can be used to compare seeds and tune presets without playing.
+
+use std::thread;
+
+use bevy::prelude::*;
+
+use super::assets::*;
+use crate::prelude::*;
+
+/// The part of the world to generate.
+#[derive(Debug, Clone)]
+pub struct WorldMapConfig {
+    pub seed: u32,
+    pub preset: TerrainGenerationPreset,
+    /// The tiles of the map, the max corner is exclusive.
+    pub rect: IRect,
+    /// The number of tiles per pixel, along each axis.
+    pub scale: u32,
+}
+
+/// A sampled tile of the world map.
+#[derive(Debug, Clone, Default)]
+pub struct WorldMapTile {
+    pub height: f64,
+    /// The index of the tile in the terrain assets, which is the tile kind used by the shader.
+    pub kind: Option<usize>,
+    /// The index of the feature in the feature assets.
+    pub feature: Option<usize>,
+}
+
+/// The sampled tiles of the world map, by rows.
+#[derive(Debug, Clone)]
+pub struct WorldMap {
+    pub width: u32,
+    pub height: u32,
+    pub tiles: Vec<WorldMapTile>,
+}
+
+impl WorldMap {
+    pub fn get(&self, x: u32, y: u32) -> &WorldMapTile {
+        &self.tiles[(y * self.width + x) as usize]
+    }
+}
+
+/// Sample the tiles of the world map. The rows are split between the available threads. Fails
+/// if the preset is invalid or uses a custom generator, which are not available without the game.
+pub fn generate_world_map(config: &WorldMapConfig) -> anyhow::Result<WorldMap> {
+    config
+        .preset
+        .validate()
+        .map_err(|err| anyhow::anyhow!("Invalid preset {}: {err}", config.preset.name))?;
+    let generator = TerrainGenerators::default()
+        .create(config.seed, &config.preset)
+        .map_err(anyhow::Error::msg)?;
+
+    let terrain = terrain_assets();
+    let features = feature_assets(|_| Handle::default());
+
+    let climate = PlanetClimate::default()
+        .with_preset(&config.preset)
+        .with_seed(config.seed);
+    let mut planet_features = PlanetFeatures::default()
+        .with_preset(&config.preset)
+        .with_seed(config.seed + 1);
+    planet_features.map = features.clone();
+
+    let scale = config.scale.max(1);
+    let width = (config.rect.width().max(0) as u32).div_ceil(scale);
+    let height = (config.rect.height().max(0) as u32).div_ceil(scale);
+
+    let sample = |x: u32, y: u32| {
+        let offset = IVec2::new((x * scale) as i32, (y * scale) as i32);
+        let point = TileCoord(config.rect.min + offset);
+
+        let height = generator.height(&point);
+        let river = generator.river(&point, height);
+        let TileTemperature(temperature) = climate.get((point.clone(), TileNoiseHeight(height)));
+        let TileMoisture(moisture) = climate.get(point.clone());
+
+        let Some(tile) = terrain.get_tile(height, temperature, moisture, river) else {
+            return WorldMapTile {
+                height,
+                ..default()
+            };
+        };
+        let TileFeature(feature) = planet_features.get((
+            point,
+            Tile(tile.clone()),
+            TileTemperature(temperature),
+            TileMoisture(moisture),
+        ));
+
+        WorldMapTile {
+            height,
+            kind: terrain.get_tile_index(&tile),
+            feature: feature.and_then(|id| features.features.iter().position(|f| f.id == id)),
+        }
+    };
+
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get()) as u32;
+    let rows_per_thread = height.div_ceil(threads).max(1);
+    let tiles = thread::scope(|scope| {
+        let sample = &sample;
+        let handles = (0..height)
+            .step_by(rows_per_thread as usize)
+            .map(|start| {
+                let end = (start + rows_per_thread).min(height);
+                scope.spawn(move || {
+                    (start..end)
+                        .flat_map(|y| (0..width).map(move |x| sample(x, y)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("World map thread panicked"))
+            .collect()
+    });
+
+    Ok(WorldMap {
+        width,
+        height,
+        tiles,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_flat_world_map() {
+        let map = generate_world_map(&WorldMapConfig {
+            seed: 42,
+            preset: TerrainGenerationPreset::flat(),
+            rect: IRect::new(-10, -10, 10, 5),
+            scale: 2,
+        })
+        .unwrap();
+
+        assert_eq!((map.width, map.height), (10, 8));
+        assert_eq!(map.tiles.len(), 80);
+
+        // Every tile of the flat world is land at the same height
+        let height = map.get(0, 0).height;
+        assert!(height > 0.5);
+        assert!(map.tiles.iter().all(|tile| tile.height == height));
+        assert!(map.tiles.iter().all(|tile| tile.kind.is_some()));
+    }
+}
@@ -0,0 +1,383 @@
+//! Prometheus metrics for the dedicated server.
+//!
+//! When `metrics_address` is set in the server config, a small HTTP server is started on that
+//! address and serves the metrics on `/metrics` in the Prometheus text format. The metrics are
+//! rendered by the app every second, so scraping never blocks the game loop.
+
+use std::{
+    fmt::{Display, Write as _},
+    io::{BufRead, BufReader, Write as _},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::entity::Entities, prelude::*, time::common_conditions::on_timer};
+use lightyear::prelude::{server::ClientOf, *};
+
+use super::{config::*, controller::*};
+use crate::prelude::*;
+
+/// How often the metrics served to the scrapers are rendered.
+const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a scraper has to send its request.
+const METRICS_READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// The upper bounds of the buckets of the tick duration histograms, in seconds.
+const TICK_BUCKETS: [f64; 9] = [0.001, 0.0025, 0.005, 0.01, 0.016, 0.025, 0.05, 0.1, 0.25];
+
+/// A Prometheus histogram with fixed buckets.
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// The number of observations in each bucket, the last one is the `+Inf` bucket.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum {}", self.sum);
+        let _ = writeln!(out, "{name}_count {}", self.count);
+    }
+}
+
+/// Write a metric with a single sample.
+fn render_metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl Display) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "{name} {value}");
+}
+
+/// How long the frames and the fixed ticks of the server take.
+#[derive(Resource, Debug, Clone)]
+struct TickTimings {
+    frame_start: Option<Instant>,
+    fixed_start: Option<Instant>,
+    frame: Histogram,
+    fixed: Histogram,
+    /// The fixed ticks that took longer than the fixed timestep.
+    overruns: u64,
+}
+
+impl Default for TickTimings {
+    fn default() -> Self {
+        Self {
+            frame_start: None,
+            fixed_start: None,
+            frame: Histogram::new(&TICK_BUCKETS),
+            fixed: Histogram::new(&TICK_BUCKETS),
+            overruns: 0,
+        }
+    }
+}
+
+/// The last rendered metrics, shared with the HTTP server thread.
+#[derive(Resource, Debug, Clone, Default, Deref)]
+struct MetricsSnapshot(Arc<Mutex<String>>);
+
+pub(super) struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(address) = app
+            .world()
+            .resource::<ServerConfig>()
+            .metrics_address
+            .clone()
+        else {
+            return;
+        };
+
+        let listener = match TcpListener::bind(&address) {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!(
+                    "Failed to start the metrics endpoint on {}: {}",
+                    address, err
+                );
+                return;
+            }
+        };
+        info!("Serving metrics on http://{}/metrics", address);
+
+        let snapshot = MetricsSnapshot::default();
+        let shared = snapshot.0.clone();
+        if let Err(err) = std::thread::Builder::new()
+            .name("metrics".to_string())
+            .spawn(move || serve_metrics(listener, shared))
+        {
+            error!("Failed to spawn the metrics thread: {}", err);
+            return;
+        }
+
+        app.insert_resource(snapshot);
+        app.init_resource::<TickTimings>();
+        // The events are only counted when the metrics are served
+        app.init_resource::<ChannelTraffic>();
+
+        app.add_systems(First, start_frame);
+        app.add_systems(
+            Last,
+            (end_frame, render_metrics.run_if(on_timer(METRICS_INTERVAL))).chain(),
+        );
+        app.add_systems(FixedFirst, start_fixed_tick);
+        app.add_systems(FixedLast, end_fixed_tick);
+    }
+}
+
+fn start_frame(mut timings: ResMut<TickTimings>) {
+    timings.frame_start = Some(Instant::now());
+}
+
+fn end_frame(mut timings: ResMut<TickTimings>) {
+    if let Some(start) = timings.frame_start.take() {
+        timings.frame.observe(start.elapsed().as_secs_f64());
+    }
+}
+
+fn start_fixed_tick(mut timings: ResMut<TickTimings>) {
+    timings.fixed_start = Some(Instant::now());
+}
+
+fn end_fixed_tick(mut timings: ResMut<TickTimings>, time: Res<Time<Fixed>>) {
+    let Some(start) = timings.fixed_start.take() else {
+        return;
+    };
+
+    let elapsed = start.elapsed();
+    timings.fixed.observe(elapsed.as_secs_f64());
+    if elapsed > time.timestep() {
+        timings.overruns += 1;
+    }
+}
+
+fn render_metrics(
+    snapshot: Res<MetricsSnapshot>,
+    timings: Res<TickTimings>,
+    entities: &Entities,
+    q_clients: Query<(), (With<ClientOf>, With<Connected>)>,
+    q_chunks: Query<(), With<ChunkCoord>>,
+    q_tiles: Query<(), With<TileCoord>>,
+    q_items: Query<(), With<Item>>,
+    q_players: Query<(), With<PlayerControllerMarker>>,
+    chunk_tasks: Option<Res<ChunkMapTasks>>,
+    traffic: Res<ChannelTraffic>,
+) {
+    let mut out = String::new();
+
+    timings.frame.render(
+        &mut out,
+        "survicraft_frame_duration_seconds",
+        "Time spent running one frame of the server.",
+    );
+    timings.fixed.render(
+        &mut out,
+        "survicraft_fixed_tick_duration_seconds",
+        "Time spent running one FixedUpdate tick of the server.",
+    );
+    render_metric(
+        &mut out,
+        "survicraft_fixed_update_overruns_total",
+        "counter",
+        "FixedUpdate ticks that took longer than the fixed timestep.",
+        timings.overruns,
+    );
+    render_metric(
+        &mut out,
+        "survicraft_connected_clients",
+        "gauge",
+        "Clients connected to the server.",
+        q_clients.iter().count(),
+    );
+    render_metric(
+        &mut out,
+        "survicraft_world_entities",
+        "gauge",
+        "Entities in the world of the server.",
+        entities.len(),
+    );
+
+    let name = "survicraft_entities";
+    let _ = writeln!(out, "# HELP {name} Entities of the server, per kind.");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for (kind, count) in [
+        ("chunk", q_chunks.iter().count()),
+        ("tile", q_tiles.iter().count()),
+        ("item", q_items.iter().count()),
+        ("player", q_players.iter().count()),
+    ] {
+        let _ = writeln!(out, "{name}{{kind=\"{kind}\"}} {count}");
+    }
+
+    if let Some(chunk_tasks) = chunk_tasks {
+        let name = "survicraft_chunk_map_pending_tasks";
+        let _ = writeln!(
+            out,
+            "# HELP {name} Chunks waiting for a compute task, per output."
+        );
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        for (output, pending) in chunk_tasks.iter() {
+            let _ = writeln!(out, "{name}{{output=\"{output}\"}} {pending}");
+        }
+    }
+
+    let name = "survicraft_event_payload_bytes_total";
+    let _ = writeln!(
+        out,
+        "# HELP {name} Encoded payload bytes of the events, per channel. Broadcasts are \
+         counted once, and the protocol overhead and the replication are not included."
+    );
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for (channel, bytes) in traffic.iter() {
+        let _ = writeln!(
+            out,
+            "{name}{{channel=\"{channel}\",direction=\"sent\"}} {}",
+            bytes.sent
+        );
+        let _ = writeln!(
+            out,
+            "{name}{{channel=\"{channel}\",direction=\"received\"}} {}",
+            bytes.received
+        );
+    }
+
+    match snapshot.lock() {
+        Ok(mut snapshot) => *snapshot = out,
+        Err(err) => error!("Failed to update the metrics snapshot: {}", err),
+    }
+}
+
+/// Answer the scrapers with the last rendered metrics. Scrapes are rare, so the requests are
+/// handled one at a time.
+fn serve_metrics(listener: TcpListener, snapshot: Arc<Mutex<String>>) {
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| handle_metrics_request(stream, &snapshot));
+        if let Err(err) = result {
+            debug!("Failed to answer a metrics request: {}", err);
+        }
+    }
+}
+
+fn handle_metrics_request(stream: TcpStream, snapshot: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(METRICS_READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers, the request has no body
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let (status, body) = if method == "GET" && path == "/metrics" {
+        let body = snapshot.lock().map(|s| s.clone()).unwrap_or_default();
+        ("200 OK", body)
+    } else {
+        ("404 Not Found", "Not Found\n".to_string())
+    };
+
+    let mut writer = &stream;
+    write!(
+        writer,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_histogram_render() {
+        let mut histogram = Histogram::new(&[0.01, 0.1]);
+        histogram.observe(0.005);
+        histogram.observe(0.05);
+        histogram.observe(1.0);
+
+        let mut out = String::new();
+        histogram.render(&mut out, "tick", "Tick duration.");
+
+        assert!(out.contains("# TYPE tick histogram"));
+        assert!(out.contains("tick_bucket{le=\"0.01\"} 1\n"));
+        assert!(out.contains("tick_bucket{le=\"0.1\"} 2\n"));
+        assert!(out.contains("tick_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("tick_count 3\n"));
+    }
+
+    fn scrape(address: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_scrape_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let snapshot = Arc::new(Mutex::new(String::new()));
+
+        let mut out = String::new();
+        render_metric(
+            &mut out,
+            "survicraft_connected_clients",
+            "gauge",
+            "Clients.",
+            2,
+        );
+        *snapshot.lock().unwrap() = out;
+
+        let shared = snapshot.clone();
+        std::thread::spawn(move || serve_metrics(listener, shared));
+
+        let response = scrape(address, "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("text/plain; version=0.0.4"));
+        assert!(response.ends_with("survicraft_connected_clients 2\n"));
+
+        let response = scrape(address, "/other");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+    }
+}
@@ -1,13 +1,26 @@
 //! A Bevy plugin that serves as the main entry point for the game launcher.
 
+mod admin;
 mod assets;
+mod bot;
+mod commands;
 mod components;
+mod conditioner;
+mod config;
+mod console;
 mod controller;
+mod diagnostics;
+mod mapgen;
+mod metrics;
+mod moderation;
 mod network;
+mod players;
 mod progress;
 mod render;
 mod resources;
+mod save;
 mod setup;
+mod shutdown;
 mod states;
 mod ui;
 mod world;
@@ -16,21 +29,31 @@ mod status;
 
 use avian3d::prelude::*;
 use bevy::prelude::*;
-pub use setup::{new_gui_app, new_headless_app};
+pub use bot::{BotConfig, BotReport, BotSummary};
+pub use config::NetworkConditions;
+pub use mapgen::{generate_world_map, WorldMap, WorldMapConfig, WorldMapTile};
+pub use setup::{new_bot_app, new_gui_app, new_headless_app};
 
 pub struct LauncherPlugin {
     pub render: bool,
+    /// Simulated network conditions, used instead of the ones from the config files.
+    pub network_conditions: Option<NetworkConditions>,
 }
 
 impl LauncherPlugin {
     fn is_dedicated_server(&self, app: &App) -> bool {
         app.is_plugin_added::<setup::DedicatedServerPlugin>()
     }
+
+    fn is_bot(&self, app: &App) -> bool {
+        app.is_plugin_added::<bot::BotPlugin>()
+    }
 }
 
 impl Plugin for LauncherPlugin {
     fn build(&self, app: &mut App) {
         let dedicated = self.is_dedicated_server(app);
+        let bot = self.is_bot(app);
         if dedicated && self.render {
             warn!("Dedicated server mode with rendering enabled. This is unusual.");
         }
@@ -39,6 +62,8 @@ impl Plugin for LauncherPlugin {
             "Launcher mode: {}",
             if dedicated {
                 "Dedicated Server"
+            } else if bot {
+                "Bot"
             } else {
                 "Client/Host"
             }
@@ -54,9 +79,19 @@ impl Plugin for LauncherPlugin {
         // Initialize the state machine
         app.init_state::<states::LauncherStates>();
         app.enable_state_scoped_entities::<states::LauncherStates>();
+        app.add_sub_state::<states::PlayingState>();
+        app.enable_state_scoped_entities::<states::PlayingState>();
 
         // Add core plugins
+        app.add_plugins(config::ConfigPlugin {
+            dedicated,
+            bot,
+            network_conditions: self.network_conditions,
+        });
         app.add_plugins(network::NetworkPlugin { dedicated });
+        app.add_plugins(commands::CommandsPlugin { dedicated });
+        app.add_plugins(admin::AdminPlugin);
+        app.add_plugins(moderation::ModerationPlugin);
         app.add_plugins(progress::LauncherProgressPlugin);
         if !dedicated {
             app.add_plugins(ui::UIPlugin);
@@ -65,6 +100,12 @@ impl Plugin for LauncherPlugin {
         app.add_plugins(world::WorldGenerationPlugin {
             render: self.render,
         });
+        if dedicated {
+            app.add_plugins(save::WorldSavePlugin);
+            app.add_plugins(shutdown::ShutdownPlugin);
+            app.add_plugins(console::ConsolePlugin);
+            app.add_plugins(metrics::MetricsPlugin);
+        }
         app.add_plugins(
             PhysicsPlugins::default()
                 .build()
@@ -79,6 +120,8 @@ impl Plugin for LauncherPlugin {
             render: self.render,
         });
         app.add_plugins(status::StatusPlugin);
+        app.add_plugins(players::PlayersPlugin { dedicated });
+        app.add_plugins(diagnostics::DiagnosticsPlugin { dedicated });
         if self.render {
             app.add_plugins(render::CommonRendererPlugin);
         }
@@ -0,0 +1,370 @@
+//! Server-side moderation of the chat.
+//!
+//! Every chat message of a player is sanitized, checked against the length and rate limits from
+//! the server config, and filtered with the optional word filter before it is broadcast. Muted
+//! players can still use commands, but their messages are not broadcast.
+
+use std::collections::HashMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use lightyear::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{admin::*, commands::*, config::*, network::*};
+
+/// The limits applied to the chat messages of the players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct ChatModerationConfig {
+    /// The maximum number of characters in a message.
+    pub max_message_length: usize,
+    /// The number of messages a player can send per second on average.
+    pub messages_per_second: f32,
+    /// The number of messages a player can send in a burst.
+    pub burst: f32,
+    /// The path of a file with one filtered word per line.
+    pub word_filter_file: Option<String>,
+}
+
+impl Default for ChatModerationConfig {
+    fn default() -> Self {
+        Self {
+            max_message_length: 256,
+            messages_per_second: 1.0,
+            burst: 5.0,
+            word_filter_file: None,
+        }
+    }
+}
+
+/// A token bucket used to rate limit the messages and the commands of a player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct TokenBucket {
+    tokens: f32,
+    last: f64,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: f32, now: f64) -> Self {
+        Self {
+            tokens: capacity,
+            last: now,
+        }
+    }
+
+    /// Refill the bucket and take a token from it, if there is one.
+    pub fn try_take(&mut self, now: f64, rate: f32, capacity: f32) -> bool {
+        let elapsed = (now - self.last).max(0.0) as f32;
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.last = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+struct ChatRateLimits(HashMap<PeerId, TokenBucket>);
+
+/// The muted players with the time their mute expires, if it expires.
+#[derive(Resource, Debug, Clone, Default)]
+struct MuteList(HashMap<PeerId, Option<f64>>);
+
+/// The words that are masked in the chat messages, in lowercase.
+#[derive(Resource, Debug, Clone, Default)]
+struct WordFilter(Vec<String>);
+
+impl WordFilter {
+    fn parse(content: &str) -> Self {
+        Self(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_ascii_lowercase)
+                .collect(),
+        )
+    }
+
+    /// Replace every filtered word in the message with asterisks, ignoring the ASCII case.
+    fn apply(&self, message: &str) -> String {
+        let mut message = message.to_string();
+        for word in &self.0 {
+            // NOTE: ASCII lowercase keeps the byte offsets of the original message
+            let mut start = 0;
+            while let Some(offset) = message.to_ascii_lowercase()[start..].find(word.as_str()) {
+                let begin = start + offset;
+                let end = begin + word.len();
+                let mask = "*".repeat(message[begin..end].chars().count());
+                message.replace_range(begin..end, &mask);
+                start = begin + mask.len();
+            }
+        }
+        message
+    }
+}
+
+/// Remove the control characters from a message, so players cannot break the chat layout.
+fn sanitize_message(message: &str) -> String {
+    message
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Parse a duration such as `30`, `30s`, `5m` or `1h` into seconds.
+fn parse_duration(input: &str) -> Option<f64> {
+    let (value, unit) = match input.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => input.split_at(index),
+        None => (input, "s"),
+    };
+    let value = value.parse::<f64>().ok().filter(|value| *value > 0.0)?;
+
+    match unit {
+        "s" => Some(value),
+        "m" => Some(value * 60.0),
+        "h" => Some(value * 60.0 * 60.0),
+        _ => None,
+    }
+}
+
+/// System param used to check the chat messages of the players.
+#[derive(SystemParam)]
+pub(super) struct ChatModerator<'w> {
+    config: Res<'w, ServerConfig>,
+    time: Res<'w, Time>,
+    limits: ResMut<'w, ChatRateLimits>,
+    mutes: ResMut<'w, MuteList>,
+    filter: Res<'w, WordFilter>,
+}
+
+impl ChatModerator<'_> {
+    /// Check a chat message of a player. Returns the message to use, or the reason the message
+    /// was rejected. Commands are only rate limited here, the commands that send chat messages
+    /// check the mutes and filter their text with `check_muted` and `filter`.
+    pub fn moderate(&mut self, peer: PeerId, message: &str) -> Result<String, String> {
+        let config = &self.config.chat;
+        let now = self.time.elapsed_secs_f64();
+
+        let message = sanitize_message(message);
+        if message.is_empty() {
+            return Err("Your message is empty".to_string());
+        }
+        if message.chars().count() > config.max_message_length {
+            return Err(format!(
+                "Your message is too long, the limit is {} characters",
+                config.max_message_length
+            ));
+        }
+
+        let bucket = self
+            .limits
+            .0
+            .entry(peer)
+            .or_insert_with(|| TokenBucket::new(config.burst, now));
+        if !bucket.try_take(now, config.messages_per_second, config.burst) {
+            return Err("You are sending messages too fast".to_string());
+        }
+
+        if message.starts_with(COMMAND_PREFIX) {
+            return Ok(message);
+        }
+
+        self.check_muted(peer)?;
+        Ok(self.filter(&message))
+    }
+
+    /// Check that a player is not muted, the expired mutes are removed.
+    pub fn check_muted(&mut self, peer: PeerId) -> Result<(), String> {
+        let now = self.time.elapsed_secs_f64();
+
+        match self.mutes.0.get(&peer).copied() {
+            Some(None) => Err("You are muted".to_string()),
+            Some(Some(until)) if until > now => {
+                Err(format!("You are muted for {:.0} more seconds", until - now))
+            }
+            Some(Some(_)) => {
+                self.mutes.0.remove(&peer);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Mask the filtered words of a message.
+    pub fn filter(&self, message: &str) -> String {
+        self.filter.apply(message)
+    }
+}
+
+pub(super) struct ModerationPlugin;
+
+impl Plugin for ModerationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatRateLimits>();
+        app.init_resource::<MuteList>();
+
+        let filter_file = app
+            .world()
+            .resource::<ServerConfig>()
+            .chat
+            .word_filter_file
+            .clone();
+        let filter = match filter_file {
+            Some(path) => match std::fs::read_to_string(&path) {
+                Ok(content) => {
+                    let filter = WordFilter::parse(&content);
+                    info!("Loaded {} filtered word(s) from {}", filter.0.len(), path);
+                    filter
+                }
+                Err(err) => {
+                    warn!("Could not read word filter file {}: {}", path, err);
+                    WordFilter::default()
+                }
+            },
+            None => WordFilter::default(),
+        };
+        app.insert_resource(filter);
+
+        app.add_observer(on_peer_disconnected);
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "mute",
+                "Prevent a player from chatting, for a duration such as 30s, 5m or 1h",
+                PermissionLevel::Operator,
+            )
+            .arg("player", ArgKind::Word)
+            .optional_arg("duration", ArgKind::Word),
+            mute_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "unmute",
+                "Allow a muted player to chat again",
+                PermissionLevel::Operator,
+            )
+            .arg("player", ArgKind::Word),
+            unmute_command,
+        );
+    }
+}
+
+/// Forget the rate limit of the peers that disconnect. Mutes are kept until they expire.
+fn on_peer_disconnected(
+    trigger: Trigger<OnRemove, Connected>,
+    q_connected: Query<&RemoteId>,
+    mut limits: ResMut<ChatRateLimits>,
+) {
+    if let Ok(RemoteId(peer)) = q_connected.get(trigger.target()) {
+        limits.0.remove(peer);
+    }
+}
+
+fn mute_command(
+    In(ctx): In<CommandContext>,
+    time: Res<Time>,
+    mut mutes: ResMut<MuteList>,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+    mut chat: ChatSender,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let name = ctx.args.text(0).unwrap_or_default();
+    let Some((peer, username)) = find_player(name, &q_players) else {
+        ev_reply.write(ctx.reply(format!("No player named '{name}' is online")));
+        return;
+    };
+
+    let duration = match ctx.args.text(1) {
+        Some(input) => match parse_duration(input) {
+            Some(duration) => Some(duration),
+            None => {
+                ev_reply.write(ctx.reply(format!("Invalid duration '{input}'")));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    mutes.0.insert(
+        peer,
+        duration.map(|duration| time.elapsed_secs_f64() + duration),
+    );
+
+    let message = match duration {
+        Some(duration) => format!("You were muted for {duration:.0} seconds"),
+        None => "You were muted".to_string(),
+    };
+    chat.notice(peer, message);
+
+    info!("Muted {} ({:?}) for {:?} seconds", username, peer, duration);
+    ev_reply.write(ctx.reply(format!("Muted {username}")));
+}
+
+fn unmute_command(
+    In(ctx): In<CommandContext>,
+    mut mutes: ResMut<MuteList>,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+    mut chat: ChatSender,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let name = ctx.args.text(0).unwrap_or_default();
+    let Some((peer, username)) = find_player(name, &q_players) else {
+        ev_reply.write(ctx.reply(format!("No player named '{name}' is online")));
+        return;
+    };
+
+    if mutes.0.remove(&peer).is_none() {
+        ev_reply.write(ctx.reply(format!("{username} is not muted")));
+        return;
+    }
+
+    chat.notice(peer, "You are no longer muted");
+    info!("Unmuted {} ({:?})", username, peer);
+    ev_reply.write(ctx.reply(format!("Unmuted {username}")));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_refills_over_time() {
+        let mut bucket = TokenBucket::new(2.0, 0.0);
+
+        assert!(bucket.try_take(0.0, 1.0, 2.0));
+        assert!(bucket.try_take(0.0, 1.0, 2.0));
+        assert!(!bucket.try_take(0.5, 1.0, 2.0));
+        assert!(bucket.try_take(1.0, 1.0, 2.0));
+
+        // The bucket never holds more than its capacity
+        assert!(bucket.try_take(100.0, 1.0, 2.0));
+        assert!(bucket.try_take(100.0, 1.0, 2.0));
+        assert!(!bucket.try_take(100.0, 1.0, 2.0));
+    }
+
+    #[test]
+    fn test_word_filter_masks_words() {
+        let filter = WordFilter::parse("# comment\nbad\n\nWorse\n");
+
+        assert_eq!(filter.apply("this is BAD, worse!"), "this is ***, *****!");
+        assert_eq!(filter.apply("nothing here"), "nothing here");
+    }
+
+    #[test]
+    fn test_sanitize_and_parse_duration() {
+        assert_eq!(sanitize_message(" hi\u{7}\nthere\t"), "hithere");
+
+        assert_eq!(parse_duration("30"), Some(30.0));
+        assert_eq!(parse_duration("5m"), Some(300.0));
+        assert_eq!(parse_duration("1h"), Some(3600.0));
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("5d"), None);
+    }
+}
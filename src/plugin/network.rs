@@ -2,11 +2,11 @@ use std::{
     fmt::Debug,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::Duration,
 };
 
 use avian3d::prelude::*;
-use bevy::{prelude::*, reflect::GetTypeRegistration};
+use bevy::{ecs::system::SystemParam, prelude::*, reflect::GetTypeRegistration};
 use leafwing_input_manager::prelude::*;
 use lightyear::{
     connection::host::HostClient,
@@ -15,13 +15,16 @@ use lightyear::{
     prelude::{
         client::ClientPlugins,
         input::leafwing::InputPlugin,
-        server::{ClientOf, ServerPlugins, ServerUdpIo, Start},
+        server::{ClientOf, ServerPlugins, ServerUdpIo, Start, Stop},
         *,
     },
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use super::{resources::*, states::*};
+use super::{
+    admin::*, commands::*, conditioner::*, config::*, moderation::*, resources::*, shutdown::*,
+    states::*,
+};
 use crate::prelude::*;
 
 // --- Contants ---
@@ -57,6 +60,7 @@ impl Plugin for NetworkPlugin {
         });
 
         app.add_plugins(ProtocolPlugin);
+        app.add_plugins(LinkConditionerPlugin);
         app.add_plugins(WelcomePlugin);
         app.add_plugins(ControllerPlugin {
             dedicated: self.dedicated,
@@ -70,6 +74,7 @@ impl Plugin for NetworkPlugin {
 
         // Create connections when entering the Connecting state.
         app.add_systems(OnEnter(LauncherStates::Connecting), setup_connections);
+        app.add_systems(OnExit(LauncherStates::Playing), cleanup_players);
     }
 }
 
@@ -143,6 +148,12 @@ impl Plugin for ClientPlugin {
 
         app.add_observer(on_client_connection_added);
         app.add_observer(on_host_connection_added);
+        app.add_observer(on_client_disconnected);
+
+        app.add_systems(
+            Update,
+            handle_disconnect_pressed.run_if(in_state(LauncherStates::Playing)),
+        );
     }
 }
 
@@ -151,6 +162,8 @@ fn on_client_connection_added(
     q_connection: Query<&ClientConnection, Added<ClientConnection>>,
     mut commands: Commands,
     mut client_ready: ResMut<ClientNetworkStateReady>,
+    identity: Res<ClientIdentity>,
+    network_conditions: Res<ClientNetworkConditions>,
 ) -> Result {
     let entity = trigger.target();
     let connection = q_connection.get(entity)?;
@@ -162,18 +175,20 @@ fn on_client_connection_added(
 
     let auth = Authentication::Manual {
         server_addr: connection.address,
-        client_id: get_client_id(),
+        client_id: **identity,
         private_key: Key::default(),
         protocol_id: PROTOCOL_ID,
     };
 
-    let conditioner = LinkConditionerConfig::average_condition();
+    let conditioner = network_conditions
+        .conditions
+        .conditioner(network_conditions.seed);
     let client = commands
         .entity(entity)
         .insert((
             Name::new("Client"),
             Client::default(),
-            Link::new(Some(RecvLinkConditioner::new(conditioner.clone()))),
+            Link::new(None),
             LocalAddr(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)),
             PeerAddr(connection.address),
             ReplicationReceiver::default(),
@@ -188,6 +203,9 @@ fn on_client_connection_added(
             false,
         ))
         .id();
+    if let Some(conditioner) = conditioner {
+        commands.entity(client).insert(conditioner);
+    }
 
     commands.trigger_targets(Connect, client);
 
@@ -221,6 +239,74 @@ fn on_host_connection_added(
     Ok(())
 }
 
+fn on_client_disconnected(
+    trigger: Trigger<OnAdd, Disconnected>,
+    q_client: Query<&Disconnected, With<Client>>,
+    state: Res<State<LauncherStates>>,
+    mut next_state: ResMut<NextState<LauncherStates>>,
+    mut message: ResMut<MainMenuMessage>,
+) {
+    let Ok(disconnected) = q_client.get(trigger.target()) else {
+        return;
+    };
+
+    // The client is already going back to the menu when it disconnected on its own
+    if matches!(*next_state, NextState::Pending(_)) {
+        return;
+    }
+
+    // The server can refuse the connection before the welcome, e.g. for a banned player
+    if matches!(
+        **state,
+        LauncherStates::Connecting | LauncherStates::Generating | LauncherStates::Playing
+    ) {
+        let reason = disconnected.reason.as_deref().unwrap_or("unknown reason");
+        info!(
+            "Client disconnected from server ({}), returning to main menu",
+            reason
+        );
+        next_state.set(LauncherStates::MainMenu);
+        **message = Some(format!("Disconnected from the server: {reason}"));
+    }
+}
+
+/// Disconnect the client from the server, and in host mode also stop the local server. The
+/// connection entities are despawned when leaving the Playing state.
+fn handle_disconnect_pressed(
+    mut commands: Commands,
+    mut ev_disconnect: EventReader<ClientDisconnectClickEvent>,
+    q_client: Query<Entity, With<Client>>,
+    q_server: Query<Entity, With<Server>>,
+) {
+    for _ in ev_disconnect.read() {
+        for client in &q_client {
+            info!("Disconnecting client {:?}", client);
+            commands.trigger_targets(Disconnect, client);
+        }
+
+        for server in &q_server {
+            info!("Stopping local server {:?}", server);
+            commands.trigger_targets(Stop, server);
+        }
+    }
+}
+
+fn cleanup_players(
+    mut commands: Commands,
+    q_metadata: Query<Entity, With<PlayerMetadata>>,
+    client_ready: Option<ResMut<ClientNetworkStateReady>>,
+) {
+    debug!("Cleaning up player metadata");
+
+    for entity in &q_metadata {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some(mut client_ready) = client_ready {
+        **client_ready = false;
+    }
+}
+
 /// Server component to mark the server entity.
 /// Add this component to an entity to make it a server.
 #[derive(Debug, Clone, Component)]
@@ -254,10 +340,16 @@ fn on_server_listener_added(trigger: Trigger<OnAdd, ServerListener>, mut command
 fn on_new_client(
     trigger: Trigger<OnAdd, LinkOf>,
     mut commands: Commands,
+    config: Res<ServerConfig>,
     _server: Single<&Server>,
 ) {
     debug!("New client connected: {:?}", trigger.target());
 
+    // The conditioner is added next to the link, so the packets already received are kept
+    if let Some(conditioner) = config.network_conditions.conditioner(config.network_seed) {
+        commands.entity(trigger.target()).insert(conditioner);
+    }
+
     commands
         .entity(trigger.target())
         .insert(Name::new("Client"))
@@ -330,11 +422,13 @@ impl<A: LeafwingUserAction> Plugin for NetworkInputPlugin<A> {
 
 // --- Utility Functions ---
 
-fn get_client_id() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_nanos() as u64
+/// The persistent identity of a peer, which is the client id used in the netcode handshake.
+/// The host client and the server do not have one.
+pub(super) fn peer_identity(peer: PeerId) -> Option<u64> {
+    match peer {
+        PeerId::Netcode(id) | PeerId::Steam(id) => Some(id),
+        _ => None,
+    }
 }
 
 fn position_should_rollback(this: &Position, that: &Position) -> bool {
@@ -356,6 +450,19 @@ pub(super) struct PlayerMetadata {
     pub username: String,
 }
 
+/// Component used to store the connection stats of a player, updated by the server.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Reflect)]
+pub(super) struct PlayerStats {
+    pub rtt_ms: u32,
+    pub host: bool,
+    pub operator: bool,
+}
+
+/// Component with the time the player joined at, in seconds since the unix epoch. It is set once
+/// by the server and the clients compute how long the player has been connected.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Deref, Reflect)]
+pub(super) struct PlayerJoinedAt(pub i64);
+
 /// Protocol Plugin
 pub struct ProtocolPlugin;
 
@@ -363,7 +470,9 @@ impl Plugin for ProtocolPlugin {
     fn build(&self, app: &mut App) {
         // Components for player
         app.register_type::<PlayerId>()
-            .register_type::<PlayerMetadata>();
+            .register_type::<PlayerMetadata>()
+            .register_type::<PlayerStats>()
+            .register_type::<PlayerJoinedAt>();
 
         // Register components for replication
         app.register_component::<Name>()
@@ -378,6 +487,10 @@ impl Plugin for ProtocolPlugin {
             .add_prediction(PredictionMode::Once)
             .add_interpolation(InterpolationMode::Once);
 
+        // Updated periodically by the server, only shown in the player list
+        app.register_component::<PlayerStats>();
+        app.register_component::<PlayerJoinedAt>();
+
         // Fully replicated, but not visual, so no need for lerp/corrections:
         app.register_component::<LinearVelocity>()
             .add_prediction(PredictionMode::Full);
@@ -429,18 +542,21 @@ impl Plugin for ProtocolPlugin {
 //
 // This plugin handles the initial welcome message sent from the server to the client
 // when it connects. The welcome message contains important information such as the
-// world seed.
+// world seed and the world generation preset.
 //
 // Then the client responds with its metadata (e.g. username) as an ACK to the server.
 
 #[derive(Debug, Clone, Event, Serialize, Deserialize)]
 struct ServerWelcomeEvent {
     pub seed: u32,
+    pub preset: TerrainGenerationPreset,
 }
 
 #[derive(Debug, Clone, Event, Serialize, Deserialize)]
 struct ClientMetadataEvent {
     pub username: String,
+    /// The secret that proves the persistent identity of the player, see `PlayerIdentities`.
+    pub secret: String,
 }
 
 struct WelcomeChannel;
@@ -453,6 +569,7 @@ impl Plugin for WelcomePlugin {
         app.add_client_event::<ClientMetadataEvent, WelcomeChannel>();
 
         app.add_observer(on_new_connection);
+        app.add_observer(on_connection_removed);
         app.add_systems(Update, on_server_welcome_message);
         app.add_systems(Update, on_client_metadata_message);
 
@@ -466,44 +583,114 @@ impl Plugin for WelcomePlugin {
 
 fn on_new_connection(
     trigger: Trigger<OnAdd, Connected>,
+    mut commands: Commands,
     q_connected: Query<(&RemoteId, Has<HostClient>), With<ClientOf>>,
     mut ev_server: EventWriter<ToClient<ServerWelcomeEvent>>,
     mut ev_host: EventWriter<ServerWelcomeEvent>,
     world_seed: Res<TerrainGenerationSeed>,
+    world_preset: Res<TerrainGenerationPreset>,
+    bans: Res<BanList>,
+    shutdown: Option<Res<ServerShutdown>>,
     _: Single<&Server>,
 ) -> Result {
     debug!("New connection established: {:?}", trigger.target());
 
     let entity = trigger.target();
     let (RemoteId(peer), is_host) = q_connected.get(entity)?;
+    if bans.is_banned(*peer) {
+        info!("Rejecting banned peer {:?}", peer);
+
+        commands.trigger_targets(Disconnect, entity);
+        return Ok(());
+    }
+    if shutdown.is_some() {
+        info!("Rejecting peer {:?}, the server is shutting down", peer);
+
+        commands.trigger_targets(Disconnect, entity);
+        return Ok(());
+    }
+
     if is_host {
         debug!("This is a host client connection");
 
-        ev_host.write(ServerWelcomeEvent { seed: **world_seed });
+        ev_host.write(ServerWelcomeEvent {
+            seed: **world_seed,
+            preset: world_preset.clone(),
+        });
     } else {
         ev_server.write(ToClient {
             target: NetworkTarget::Single(*peer),
-            event: ServerWelcomeEvent { seed: **world_seed },
+            event: ServerWelcomeEvent {
+                seed: **world_seed,
+                preset: world_preset.clone(),
+            },
         });
     }
 
     Ok(())
 }
 
+/// Remove the metadata of the players that disconnect from the server.
+fn on_connection_removed(
+    trigger: Trigger<OnRemove, Connected>,
+    mut commands: Commands,
+    q_connected: Query<&RemoteId, With<ClientOf>>,
+    q_metadata: Query<(Entity, &PlayerId), With<PlayerMetadata>>,
+) {
+    let Ok(RemoteId(peer)) = q_connected.get(trigger.target()) else {
+        return;
+    };
+    debug!("Connection closed for peer {:?}", peer);
+
+    for (entity, PlayerId(id)) in &q_metadata {
+        if id == peer {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 fn on_server_welcome_message(
+    mut commands: Commands,
     mut ev_welcome: EventReader<ServerWelcomeEvent>,
     mut ev_metadata: EventWriter<ClientMetadataEvent>,
     player_name: Res<PlayerNameSetting>,
+    secret: Option<Res<ClientSecret>>,
     mut client_ready: ResMut<ClientNetworkStateReady>,
     mut world_seed: ResMut<TerrainGenerationSeed>,
+    mut world_preset: ResMut<TerrainGenerationPreset>,
+    generators: Res<TerrainGenerators>,
+    q_client: Query<Entity, With<Client>>,
+    mut next_state: ResMut<NextState<LauncherStates>>,
+    mut message: ResMut<MainMenuMessage>,
 ) {
-    for &ServerWelcomeEvent { seed } in ev_welcome.read() {
+    for ServerWelcomeEvent { seed, preset } in ev_welcome.read() {
         debug!("Received welcome message from server");
+        let supported = preset
+            .validate()
+            .map_err(|err| format!("invalid world preset: {err}"))
+            .and_then(|()| generators.validate(&preset.generator));
+        if let Err(err) = supported {
+            error!("Refusing to join the server: {}", err);
+            // The welcome arrives while connecting, where the disconnect button is not handled
+            for client in &q_client {
+                commands.trigger_targets(Disconnect, client);
+            }
+            next_state.set(LauncherStates::MainMenu);
+            **message = Some(format!("Refused to join the server: {err}"));
+            continue;
+        }
+
         **client_ready = true;
-        **world_seed = seed;
+        **world_seed = *seed;
+        // Avoid triggering a regeneration of the terrain when the preset is the same
+        world_preset.set_if_neq(preset.clone());
 
         ev_metadata.write(ClientMetadataEvent {
             username: player_name.to_string(),
+            secret: secret
+                .as_ref()
+                .map(|secret| secret.0.clone())
+                .unwrap_or_default(),
         });
     }
 }
@@ -511,9 +698,31 @@ fn on_server_welcome_message(
 fn on_client_metadata_message(
     mut commands: Commands,
     mut ev_metadata: EventReader<FromClient<ClientMetadataEvent>>,
+    mut identities: ResMut<PlayerIdentities>,
+    q_clients: Query<(Entity, &RemoteId), With<ClientOf>>,
 ) {
     for FromClient { peer, event, .. } in ev_metadata.read() {
-        debug!("Spawn player metadata for peer {:?}: {:?}", peer, event);
+        let Some((entity, _)) = q_clients.iter().find(|(_, id)| id.0 == *peer) else {
+            continue;
+        };
+
+        // The host client and the server do not have a persistent identity to prove
+        if let Some(identity) = peer_identity(*peer) {
+            if !identities.verify(identity, &event.secret) {
+                warn!(
+                    "Rejecting peer {:?}, the secret does not match its identity",
+                    peer
+                );
+                commands.trigger_targets(Disconnect, entity);
+                continue;
+            }
+        }
+        commands.entity(entity).insert(AuthenticatedPeer);
+
+        debug!(
+            "Spawn player metadata for peer {:?}: {}",
+            peer, event.username
+        );
 
         commands.spawn((
             Name::new("PlayerMetadata"),
@@ -521,6 +730,8 @@ fn on_client_metadata_message(
             PlayerMetadata {
                 username: event.username.clone(),
             },
+            PlayerStats::default(),
+            PlayerJoinedAt(chrono::Utc::now().timestamp()),
             Replicate::to_clients(NetworkTarget::All),
         ));
     }
@@ -573,10 +784,13 @@ pub struct ClientChatMessageEvent {
 #[derive(Debug, Clone, Event, Serialize, Deserialize)]
 pub struct ServerChatMessageEvent {
     pub sender: PeerId,
+    /// The player that receives a whisper.
+    pub recipient: Option<PeerId>,
+    pub kind: ChatMessageKind,
     pub message: String,
 }
 
-struct MessageChannel;
+pub(super) struct MessageChannel;
 
 pub struct ChatPlugin;
 
@@ -600,6 +814,25 @@ impl Plugin for ChatPlugin {
             ..default()
         })
         .add_direction(NetworkDirection::Bidirectional);
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "list",
+                "List the connected players",
+                PermissionLevel::Player,
+            ),
+            list_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "msg",
+                "Send a private message to a player",
+                PermissionLevel::Player,
+            )
+            .arg("player", ArgKind::Word)
+            .arg("message", ArgKind::Text),
+            msg_command,
+        );
     }
 }
 
@@ -619,52 +852,202 @@ fn on_chat_message_submit(
     }
 }
 
+/// System param used to send chat messages from the server to the players.
+#[derive(SystemParam)]
+pub(super) struct ChatSender<'w, 's> {
+    q_clients: Query<'w, 's, (&'static RemoteId, Has<HostClient>), With<ClientOf>>,
+    ev_server: EventWriter<'w, ToClient<ServerChatMessageEvent>>,
+    ev_host: EventWriter<'w, ServerChatMessageEvent>,
+}
+
+impl ChatSender<'_, '_> {
+    /// Send a chat message to all the players.
+    pub fn broadcast(&mut self, event: ServerChatMessageEvent) {
+        self.ev_server.write(ToClient {
+            target: NetworkTarget::All,
+            event: event.clone(),
+        });
+
+        // NOTE: if this is a host client, also write the event locally
+        if self.q_clients.iter().any(|(_, is_host)| is_host) {
+            self.ev_host.write(event);
+        }
+    }
+
+    /// Send a chat message to a single player.
+    pub fn send(&mut self, target: PeerId, event: ServerChatMessageEvent) {
+        let is_host = self
+            .q_clients
+            .iter()
+            .any(|(RemoteId(peer), is_host)| *peer == target && is_host);
+
+        // NOTE: the host client does not receive messages, so write the event locally
+        if is_host {
+            self.ev_host.write(event);
+        } else {
+            self.ev_server.write(ToClient {
+                target: NetworkTarget::Single(target),
+                event,
+            });
+        }
+    }
+
+    /// Send a private system notice to a single player.
+    pub fn notice(&mut self, target: PeerId, message: impl Into<String>) {
+        self.send(
+            target,
+            ServerChatMessageEvent {
+                sender: PeerId::Server,
+                recipient: None,
+                kind: ChatMessageKind::System,
+                message: message.into(),
+            },
+        );
+    }
+}
+
 fn on_server_chat_message(
     mut ev_client: EventReader<FromClient<ClientChatMessageEvent>>,
-    mut ev_server: EventWriter<ToClient<ServerChatMessageEvent>>,
-    mut ev_host: EventWriter<ServerChatMessageEvent>,
+    mut ev_command: EventWriter<CommandInputEvent>,
+    mut moderator: ChatModerator,
+    mut chat: ChatSender,
 ) -> Result {
-    for FromClient { peer, event, .. } in ev_client.read() {
+    for FromClient { owner, peer, event } in ev_client.read() {
         debug!("Received chat message from {:?}: {}", peer, event.message);
 
-        ev_server.write(ToClient {
-            target: NetworkTarget::All,
-            event: ServerChatMessageEvent {
-                sender: *peer,
-                message: event.message.clone(),
-            },
-        });
+        let message = match moderator.moderate(*peer, &event.message) {
+            Ok(message) => message,
+            Err(reason) => {
+                debug!("Rejected chat message from {:?}: {}", peer, reason);
+                chat.notice(*peer, reason);
+                continue;
+            }
+        };
+
+        // Commands are executed on the server and are not broadcasted
+        if message.starts_with(COMMAND_PREFIX) {
+            ev_command.write(CommandInputEvent {
+                source: CommandSource::Player {
+                    peer: *peer,
+                    owner: *owner,
+                },
+                input: message,
+            });
+            continue;
+        }
 
-        // NOTE: if this is a host client, also write the event locally
-        ev_host.write(ServerChatMessageEvent {
+        chat.broadcast(ServerChatMessageEvent {
             sender: *peer,
-            message: event.message.clone(),
+            recipient: None,
+            kind: ChatMessageKind::Player,
+            message,
         });
     }
 
     Ok(())
 }
 
+/// The display name of the sender of a chat message. Messages from the server console are not
+/// sent by a player.
+fn chat_sender_name(
+    peer: PeerId,
+    q_players: &Query<(&PlayerMetadata, &PlayerId)>,
+) -> Option<String> {
+    if peer == PeerId::Server {
+        return Some("Server".to_string());
+    }
+
+    q_players
+        .iter()
+        .find(|(_, id)| id.0 == peer)
+        .map(|(metadata, _)| metadata.username.clone())
+}
+
 fn on_client_chat_message(
     mut ev_client: EventReader<ServerChatMessageEvent>,
     mut ev_history: EventWriter<AddChatHistoryItemEvent>,
     q_players: Query<(&PlayerMetadata, &PlayerId)>,
 ) {
     for ev in ev_client.read() {
-        if let Some((PlayerMetadata { username, .. }, _)) =
-            q_players.iter().find(|(_, id)| id.0 == ev.sender)
-        {
-            debug!("Received chat message from {}: {}", username, ev.message);
-
-            ev_history.write(AddChatHistoryItemEvent {
-                sender: username.clone(),
-                message: ev.message.clone(),
-            });
-        } else {
+        let Some(mut sender) = chat_sender_name(ev.sender, &q_players) else {
             error!(
                 "Received chat message from unknown player ID {:?}",
                 ev.sender
             );
+            continue;
+        };
+        debug!(
+            "Received {:?} chat message from {}: {}",
+            ev.kind, sender, ev.message
+        );
+
+        if let Some(recipient) = ev.recipient {
+            let recipient =
+                chat_sender_name(recipient, &q_players).unwrap_or_else(|| "Unknown".to_string());
+            sender = format!("{sender} -> {recipient}");
         }
+
+        ev_history.write(AddChatHistoryItemEvent {
+            sender,
+            message: ev.message.clone(),
+            kind: ev.kind,
+        });
+    }
+}
+
+fn list_command(
+    In(ctx): In<CommandContext>,
+    q_players: Query<&PlayerMetadata>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let usernames = q_players
+        .iter()
+        .map(|metadata| metadata.username.as_str())
+        .collect::<Vec<_>>();
+
+    ev_reply.write(ctx.reply(format!(
+        "{} player(s) online: {}",
+        usernames.len(),
+        usernames.join(", ")
+    )));
+}
+
+fn msg_command(
+    In(ctx): In<CommandContext>,
+    q_players: Query<(&PlayerId, &PlayerMetadata)>,
+    mut moderator: ChatModerator,
+    mut chat: ChatSender,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    // The command itself was rate limited, the whisper is moderated like a chat message
+    if let Some(Err(reason)) = ctx.peer().map(|peer| moderator.check_muted(peer)) {
+        ev_reply.write(ctx.reply(reason));
+        return;
+    }
+
+    let name = ctx.args.text(0).unwrap_or_default();
+    let Some((recipient, username)) = find_player(name, &q_players) else {
+        ev_reply.write(ctx.reply(format!("No player named '{name}' is online")));
+        return;
+    };
+
+    let sender = ctx.peer().unwrap_or(PeerId::Server);
+    let event = ServerChatMessageEvent {
+        sender,
+        recipient: Some(recipient),
+        kind: ChatMessageKind::Whisper,
+        message: moderator.filter(ctx.args.text(1).unwrap_or_default()),
+    };
+    debug!(
+        "Whisper from {:?} to {}: {}",
+        sender, username, event.message
+    );
+
+    // The whisper is echoed back to the sender, the console only logs it
+    match ctx.peer() {
+        Some(peer) if peer != recipient => chat.send(peer, event.clone()),
+        Some(_) => {}
+        None => info!("[Server -> {}] {}", username, event.message),
     }
+    chat.send(recipient, event);
 }
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use lightyear::{
+    connection::host::HostClient,
+    prelude::{server::ClientOf, *},
+};
+
+use super::{admin::*, config::*, network::*, states::*};
+use crate::prelude::*;
+
+/// How often the server updates the stats shown in the player list.
+const PLAYER_STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) struct PlayersPlugin {
+    pub dedicated: bool,
+}
+
+impl Plugin for PlayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_player_stats
+                .run_if(any_with_component::<Server>)
+                .run_if(on_timer(PLAYER_STATS_INTERVAL)),
+        );
+
+        if self.dedicated {
+            return;
+        }
+
+        app.add_plugins(PlayerListPlugin);
+        app.add_systems(
+            Update,
+            (sync_player_list_items, show_player_list).run_if(in_state(LauncherStates::Playing)),
+        );
+    }
+}
+
+fn update_player_stats(
+    config: Res<ServerConfig>,
+    q_links: Query<
+        (
+            &RemoteId,
+            Option<&Link>,
+            Has<HostClient>,
+            Has<AuthenticatedPeer>,
+        ),
+        With<ClientOf>,
+    >,
+    mut q_players: Query<(&PlayerId, &mut PlayerStats)>,
+) {
+    for (PlayerId(peer), mut stats) in &mut q_players {
+        let Some((_, link, host, authenticated)) =
+            q_links.iter().find(|(id, _, _, _)| id.0 == *peer)
+        else {
+            continue;
+        };
+
+        // Only changed stats are replicated
+        stats.set_if_neq(PlayerStats {
+            rtt_ms: link.map_or(0, |link| link.stats.rtt.as_millis() as u32),
+            host,
+            operator: host || (authenticated && config.is_operator(*peer)),
+        });
+    }
+}
+
+fn sync_player_list_items(
+    mut commands: Commands,
+    q_players: Query<
+        (Entity, &PlayerMetadata, &PlayerStats, &PlayerJoinedAt),
+        Or<(Changed<PlayerMetadata>, Changed<PlayerStats>)>,
+    >,
+) {
+    for (entity, metadata, stats, joined_at) in &q_players {
+        commands.entity(entity).insert(PlayerListItem {
+            username: metadata.username.clone(),
+            rtt_ms: stats.rtt_ms,
+            joined_at: **joined_at,
+            host: stats.host,
+            operator: stats.operator,
+        });
+    }
+}
+
+/// Show the player list while the player list action is held.
+fn show_player_list(
+    action_input: ActionInput,
+    chat_enabled: Res<ChatEnabled>,
+    playing_state: Res<State<PlayingState>>,
+    mut root: Single<&mut Visibility, With<PlayerListRoot>>,
+) {
+    let visible = action_input.pressed(GameAction::PlayerList)
+        && !chat_enabled.0
+        && *playing_state.get() == PlayingState::Running;
+
+    root.set_if_neq(if visible {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    });
+}
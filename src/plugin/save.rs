@@ -0,0 +1,117 @@
+//! Persistence of the world of the dedicated server.
+//!
+//! The world save is loaded when the server starts and written when the server stops, so the
+//! same world is generated again after a restart, with the edits of the terrain.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use super::{config::*, states::*};
+use crate::prelude::*;
+
+/// The path of the world save of the dedicated server.
+pub(super) const WORLD_SAVE_PATH: &str = "world.ron";
+
+/// The persisted state of the world.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct WorldSave {
+    pub seed: u32,
+    /// The world generation preset, missing in the saves of older versions.
+    pub preset: Option<TerrainGenerationPreset>,
+    /// The height deltas of the edited tiles.
+    pub terrain: TerrainEdits,
+}
+
+/// System param used to write the world save from the state of the world.
+#[derive(SystemParam)]
+pub(super) struct WorldSaver<'w> {
+    seed: Res<'w, TerrainGenerationSeed>,
+    preset: Res<'w, TerrainGenerationPreset>,
+    edits: Res<'w, TerrainEdits>,
+}
+
+impl WorldSaver<'_> {
+    pub fn save(&self) -> anyhow::Result<()> {
+        let save = WorldSave {
+            seed: **self.seed,
+            preset: Some(self.preset.clone()),
+            terrain: self.edits.clone(),
+        };
+        save_config(WORLD_SAVE_PATH, &save)?;
+
+        info!("Saved world to {}", WORLD_SAVE_PATH);
+        Ok(())
+    }
+}
+
+pub(super) struct WorldSavePlugin;
+
+impl Plugin for WorldSavePlugin {
+    fn build(&self, app: &mut App) {
+        // New worlds use the preset from the server config
+        let mut preset = app.world().resource::<ServerConfig>().world_preset.clone();
+        if std::path::Path::new(WORLD_SAVE_PATH).exists() {
+            let save: WorldSave = load_config(WORLD_SAVE_PATH);
+            info!(
+                "Loaded world from {} with seed {}",
+                WORLD_SAVE_PATH, save.seed
+            );
+            app.insert_resource(TerrainGenerationSeed(save.seed));
+            if let Some(saved) = save.preset {
+                // The saved world cannot be generated with this preset, and must not be overwritten
+                match saved.validate() {
+                    Ok(()) => preset = saved,
+                    Err(err) => refuse_to_start(
+                        app,
+                        format!("invalid world preset in {WORLD_SAVE_PATH}: {err}"),
+                    ),
+                }
+            }
+            app.insert_resource(save.terrain);
+        }
+        info!("Using world generation preset {}", preset.name);
+        app.insert_resource(preset);
+
+        // The custom generators are registered by the plugins of the mods
+        app.add_systems(Startup, check_world_generator);
+
+        // Save right away, so a new world is not lost if the server crashes
+        app.add_systems(
+            OnEnter(LauncherStates::Playing),
+            save_world.run_if(not(resource_exists::<WorldRefused>)),
+        );
+    }
+}
+
+/// Stop the server before it starts, without saving the world.
+fn refuse_to_start(app: &mut App, reason: String) {
+    error!("Refusing to start the server: {}", reason);
+    app.insert_resource(WorldRefused);
+    app.add_systems(Startup, |mut ev_exit: EventWriter<AppExit>| {
+        ev_exit.write(AppExit::error());
+    });
+}
+
+/// Marks a world that could not be loaded, so it is not saved.
+#[derive(Resource)]
+struct WorldRefused;
+
+fn check_world_generator(
+    mut commands: Commands,
+    generators: Res<TerrainGenerators>,
+    preset: Res<TerrainGenerationPreset>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    if let Err(err) = generators.validate(&preset.generator) {
+        error!("Refusing to start the server: {}", err);
+        commands.insert_resource(WorldRefused);
+        ev_exit.write(AppExit::error());
+    }
+}
+
+fn save_world(saver: WorldSaver) {
+    if let Err(err) = saver.save() {
+        error!("Failed to save world to {}: {}", WORLD_SAVE_PATH, err);
+    }
+}
@@ -12,6 +12,8 @@ use bevy::{
 
 #[cfg(feature = "debug")]
 use self::debug::{InpsectorDebugPlugin, LoggingDebugPlugin};
+use super::bot::*;
+use crate::prelude::*;
 
 fn window_plugin() -> WindowPlugin {
     WindowPlugin {
@@ -48,28 +50,52 @@ pub fn new_gui_app() -> App {
             .set(window_plugin()),
     );
 
+    // The key bindings are shared by all the input systems, the launcher will overwrite them
+    // with the ones from the config file.
+    app.init_resource::<KeyBindings>();
+
     #[cfg(feature = "debug")]
     app.add_plugins((InpsectorDebugPlugin, LoggingDebugPlugin));
 
-    // NOTE: Just for non UI, lock cursor on left click and unlock on escape
-    app.add_systems(Update, (lock_on_left_click, unlock_on_escape));
+    // NOTE: Just for non UI, lock cursor on right click and unlock on escape
+    app.add_systems(Update, (lock_cursor, unlock_cursor));
 
     app
 }
 
 pub fn new_headless_app() -> App {
+    let mut app = headless_app(true);
+    app.add_plugins(DedicatedServerPlugin);
+
+    app
+}
+
+/// Create a headless client app that is played by a bot. Many bots can run in the same process,
+/// but the global logger can only be set once, so only the first bot sets up logging.
+pub fn new_bot_app(config: BotConfig) -> App {
+    let mut app = headless_app(config.index == 0);
+    app.add_plugins(BotPlugin(config));
+
+    app
+}
+
+fn headless_app(log: bool) -> App {
+    let mut plugins = DefaultPlugins
+        .build()
+        .set(AssetPlugin {
+            meta_check: bevy::asset::AssetMetaCheck::Never,
+            ..default()
+        })
+        .set(log_plugin())
+        .disable::<WinitPlugin>();
+    if !log {
+        plugins = plugins.disable::<LogPlugin>();
+    }
+
     let mut app = App::new();
     app.add_plugins((
-        DefaultPlugins
-            .build()
-            .set(AssetPlugin {
-                meta_check: bevy::asset::AssetMetaCheck::Never,
-                ..default()
-            })
-            .set(log_plugin())
-            .disable::<WinitPlugin>(),
+        plugins,
         ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 64.0)),
-        DedicatedServerPlugin,
     ));
 
     #[cfg(feature = "debug")]
@@ -78,12 +104,9 @@ pub fn new_headless_app() -> App {
     app
 }
 
-fn lock_on_left_click(
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
-    mouse: Res<ButtonInput<MouseButton>>,
-) {
+fn lock_cursor(mut windows: Query<&mut Window, With<PrimaryWindow>>, input: ActionInput) {
     // TODO: Not for UI
-    if mouse.just_pressed(MouseButton::Right) {
+    if input.just_pressed(GameAction::LockCursor) {
         if let Ok(mut window) = windows.single_mut() {
             window.cursor_options.grab_mode = CursorGrabMode::Locked;
             window.cursor_options.visible = false;
@@ -91,11 +114,8 @@ fn lock_on_left_click(
     }
 }
 
-fn unlock_on_escape(
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
-    keys: Res<ButtonInput<KeyCode>>,
-) {
-    if keys.just_pressed(KeyCode::Escape) {
+fn unlock_cursor(mut windows: Query<&mut Window, With<PrimaryWindow>>, input: ActionInput) {
+    if input.just_pressed(GameAction::Pause) {
         if let Ok(mut window) = windows.single_mut() {
             window.cursor_options.grab_mode = CursorGrabMode::None;
             window.cursor_options.visible = true;
@@ -124,7 +144,10 @@ mod debug {
         prelude::{input::InputBuffer, server::ClientOf, *},
     };
 
-    use crate::plugin::controller::{CharacterAction, PlayerControllerMarker};
+    use crate::{
+        plugin::controller::{CharacterAction, PlayerControllerMarker},
+        prelude::*,
+    };
 
     pub struct InpsectorDebugPlugin;
 
@@ -171,10 +194,10 @@ mod debug {
     fn toggle(
         mut commands: Commands,
         q_root: Query<Entity, With<PerfUiRoot>>,
-        kbd: Res<ButtonInput<KeyCode>>,
+        input: ActionInput,
         mut show_axes: ResMut<ShowAxes>,
     ) {
-        if kbd.just_pressed(KeyCode::F11) {
+        if input.just_pressed(GameAction::ToggleDebug) {
             if let Ok(e) = q_root.single() {
                 // despawn the existing Perf UI
                 commands.entity(e).despawn();
@@ -0,0 +1,161 @@
+//! Graceful shutdown of the dedicated server.
+//!
+//! A shutdown is started by SIGINT/SIGTERM or by the `stop` console command. The server stops
+//! accepting new connections, tells the players that it is shutting down, disconnects them,
+//! saves the world and then exits. A second signal exits right away.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::prelude::*;
+use crossbeam_channel::Receiver;
+use lightyear::prelude::{
+    server::{ClientOf, Stop},
+    *,
+};
+
+use super::{network::*, save::*};
+use crate::prelude::*;
+
+/// The time the players have to receive the shutdown notice before they are disconnected.
+const SHUTDOWN_NOTICE_DELAY: f32 = 1.0;
+/// The time the disconnects have to be sent before the app exits.
+const SHUTDOWN_DISCONNECT_DELAY: f32 = 0.5;
+
+/// The exit code used when the server is stopped by a second signal.
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Event used to start the shutdown of the server.
+#[derive(Event, Debug, Clone)]
+pub(super) struct ServerShutdownEvent {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShutdownPhase {
+    /// The shutdown notice was broadcast, waiting for it to reach the players.
+    Notice,
+    /// The players were disconnected, waiting for the disconnects to be sent.
+    Disconnect,
+}
+
+/// Present while the server is shutting down. New connections are rejected while it exists.
+#[derive(Resource, Debug, Clone)]
+pub(super) struct ServerShutdown {
+    phase: ShutdownPhase,
+    timer: Timer,
+    exit: AppExit,
+}
+
+#[derive(Resource, Debug, Clone)]
+struct ShutdownSignal(Receiver<()>);
+
+pub(super) struct ShutdownPlugin;
+
+impl Plugin for ShutdownPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ServerShutdownEvent>();
+
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let signaled = AtomicBool::new(false);
+        let result = ctrlc::set_handler(move || {
+            if signaled.swap(true, Ordering::SeqCst) {
+                warn!("Received second shutdown signal, exiting now");
+                std::process::exit(FORCED_EXIT_CODE);
+            }
+
+            let _ = sender.try_send(());
+        });
+        if let Err(err) = result {
+            error!("Failed to set the shutdown signal handler: {}", err);
+        }
+        app.insert_resource(ShutdownSignal(receiver));
+
+        app.add_systems(
+            Update,
+            (
+                read_shutdown_signal,
+                start_shutdown.run_if(not(resource_exists::<ServerShutdown>)),
+                update_shutdown.run_if(resource_exists::<ServerShutdown>),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn read_shutdown_signal(
+    signal: Res<ShutdownSignal>,
+    mut ev_shutdown: EventWriter<ServerShutdownEvent>,
+) {
+    if signal.0.try_recv().is_ok() {
+        ev_shutdown.write(ServerShutdownEvent {
+            reason: "Received shutdown signal".to_string(),
+        });
+    }
+}
+
+fn start_shutdown(
+    mut commands: Commands,
+    mut ev_shutdown: EventReader<ServerShutdownEvent>,
+    mut ev_server: EventWriter<ToClient<ServerChatMessageEvent>>,
+) {
+    let Some(ev) = ev_shutdown.read().last() else {
+        return;
+    };
+    info!("Shutting down the server: {}", ev.reason);
+
+    ev_server.write(ToClient {
+        target: NetworkTarget::All,
+        event: ServerChatMessageEvent {
+            sender: PeerId::Server,
+            recipient: None,
+            kind: ChatMessageKind::System,
+            message: "The server is shutting down".to_string(),
+        },
+    });
+
+    commands.insert_resource(ServerShutdown {
+        phase: ShutdownPhase::Notice,
+        timer: Timer::from_seconds(SHUTDOWN_NOTICE_DELAY, TimerMode::Once),
+        exit: AppExit::Success,
+    });
+}
+
+fn update_shutdown(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shutdown: ResMut<ServerShutdown>,
+    saver: WorldSaver,
+    q_clients: Query<Entity, (With<ClientOf>, With<Connected>)>,
+    q_server: Query<Entity, With<Server>>,
+    mut ev_exit: EventWriter<AppExit>,
+) {
+    if !shutdown.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    match shutdown.phase {
+        ShutdownPhase::Notice => {
+            for client in &q_clients {
+                debug!("Disconnecting client {:?}", client);
+                commands.trigger_targets(Disconnect, client);
+            }
+
+            if let Err(err) = saver.save() {
+                error!("Failed to save world to {}: {}", WORLD_SAVE_PATH, err);
+                shutdown.exit = AppExit::error();
+            }
+
+            shutdown.phase = ShutdownPhase::Disconnect;
+            shutdown.timer = Timer::from_seconds(SHUTDOWN_DISCONNECT_DELAY, TimerMode::Once);
+        }
+        ShutdownPhase::Disconnect => {
+            for server in &q_server {
+                debug!("Stopping server {:?}", server);
+                commands.trigger_targets(Stop, server);
+            }
+
+            info!("Server stopped");
+            ev_exit.write(shutdown.exit.clone());
+        }
+    }
+}
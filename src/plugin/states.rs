@@ -9,3 +9,13 @@ pub(super) enum LauncherStates {
     Generating,
     Playing,
 }
+
+/// The state of the game while playing. The game keeps running in the background while paused,
+/// only the input of the local player is ignored.
+#[derive(Clone, Eq, PartialEq, Debug, Hash, Default, SubStates)]
+#[source(LauncherStates = LauncherStates::Playing)]
+pub(super) enum PlayingState {
+    #[default]
+    Running,
+    Paused,
+}
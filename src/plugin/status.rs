@@ -14,6 +14,16 @@ impl Plugin for StatusPlugin {
         }
 
         app.add_systems(OnEnter(LauncherStates::Playing), setup_status);
+        // The items are added and removed when the settings change from the pause menu
+        app.add_systems(
+            Update,
+            setup_status
+                .run_if(in_state(LauncherStates::Playing))
+                .run_if(
+                    resource_changed::<DisplayFPSSetting>
+                        .or(resource_changed::<DisplayLatencySetting>),
+                ),
+        );
         app.add_systems(
             Update,
             update_fps_status.run_if(in_state(LauncherStates::Playing)),
@@ -36,18 +46,24 @@ fn setup_status(
     show_fps: Res<DisplayFPSSetting>,
     show_latency: Res<DisplayLatencySetting>,
     asset_server: Res<AssetServer>,
+    q_fps: Query<Entity, With<StatusBarItemFPS>>,
+    q_latency: Query<Entity, With<StatusBarItemLatency>>,
 ) {
-    if **show_fps {
+    if !**show_fps {
+        for entity in &q_fps {
+            commands.entity(entity).despawn();
+        }
+    } else if q_fps.is_empty() {
         commands.spawn((
             Name::new("StatusBarItem FPS"),
             StatusBarItemFPS,
             StatusBarItem {
                 icon: Some(asset_server.load("textures/GameIcons/fps.png")),
-                value: 0,
+                value: StatusValue::Int(0),
                 label: "fps".to_string(),
                 mapping: vec![
-                    (Some(30), Color::srgb(1.0, 0.0, 0.0)),
-                    (Some(60), Color::srgb(1.0, 1.0, 0.0)),
+                    (Some(30.0), Color::srgb(1.0, 0.0, 0.0)),
+                    (Some(60.0), Color::srgb(1.0, 1.0, 0.0)),
                     (None, Color::srgb(0.0, 1.0, 0.0)),
                 ],
             },
@@ -55,19 +71,19 @@ fn setup_status(
         ));
     }
 
-    if **show_latency {
+    if !**show_latency {
+        for entity in &q_latency {
+            commands.entity(entity).despawn();
+        }
+    } else if q_latency.is_empty() {
         commands.spawn((
             Name::new("StatusBarItem Latency"),
             StatusBarItemLatency,
             StatusBarItem {
                 icon: Some(asset_server.load("textures/GameIcons/latency.png")),
-                value: 0,
+                value: StatusValue::Int(0),
                 label: "ms".to_string(),
-                mapping: vec![
-                    (Some(100), Color::srgb(0.0, 1.0, 0.0)),
-                    (Some(200), Color::srgb(1.0, 1.0, 0.0)),
-                    (None, Color::srgb(1.0, 0.0, 0.0)),
-                ],
+                mapping: LATENCY_MAPPING.to_vec(),
             },
             StateScoped(LauncherStates::Playing),
         ));
@@ -81,11 +97,11 @@ fn update_fps_status(
     let fps = store
         .get(&bevy::diagnostic::FrameTimeDiagnosticsPlugin::FPS)
         .and_then(|d| d.average())
-        .map(|v| v.round() as u32)
+        .map(|v| v.round() as i64)
         .unwrap_or(0);
 
     for mut status in &mut q_status {
-        status.value = fps;
+        status.value = StatusValue::Int(fps);
     }
 }
 
@@ -95,11 +111,11 @@ fn update_latency_status(
 ) {
     let latency = q_link
         .iter()
-        .map(|link| link.stats.rtt.as_millis() as u32)
+        .map(|link| link.stats.rtt.as_millis() as i64)
         .max()
         .unwrap_or(0);
 
     for mut status in &mut q_status {
-        status.value = latency;
+        status.value = StatusValue::Int(latency);
     }
 }
@@ -33,6 +33,23 @@ impl Plugin for UIPlugin {
         // Chat setup. We set up chat UI and related systems.
         app.add_systems(OnEnter(LauncherStates::Playing), setup_playing_ui);
         app.add_plugins(ChatPlugin);
+
+        // Pause menu setup. The chat input is disabled while the game is paused, and the pause
+        // input is ignored while the chat is open.
+        app.configure_sets(Update, ChatInputSet.run_if(in_state(PlayingState::Running)));
+        app.add_systems(
+            Update,
+            handle_pause_pressed
+                .before(ChatInputSet)
+                .run_if(in_state(PlayingState::Running))
+                .run_if(resource_equals(ChatEnabled(false))),
+        );
+        app.add_systems(OnEnter(PlayingState::Paused), setup_pause_ui);
+        app.add_systems(
+            Update,
+            (handle_resume_pressed, handle_disconnect_pressed)
+                .run_if(in_state(PlayingState::Paused)),
+        );
     }
 }
 
@@ -98,6 +115,33 @@ fn handle_multiplayer_pressed(
     }
 }
 
+fn handle_pause_pressed(input: ActionInput, mut next_state: ResMut<NextState<PlayingState>>) {
+    if input.just_pressed(GameAction::Pause) {
+        debug!("Pausing the game");
+        next_state.set(PlayingState::Paused);
+    }
+}
+
+fn handle_resume_pressed(
+    mut ev_resume: EventReader<ClientResumeClickEvent>,
+    mut next_state: ResMut<NextState<PlayingState>>,
+) {
+    for _ in ev_resume.read() {
+        debug!("Resuming the game");
+        next_state.set(PlayingState::Running);
+    }
+}
+
+fn handle_disconnect_pressed(
+    mut ev_disconnect: EventReader<ClientDisconnectClickEvent>,
+    mut next_state: ResMut<NextState<LauncherStates>>,
+) {
+    for _ in ev_disconnect.read() {
+        // The connections are closed by the network plugin, here we only go back to the menu
+        next_state.set(LauncherStates::MainMenu);
+    }
+}
+
 fn setup_connecting_ui(mut commands: Commands) {
     commands.spawn((
         Name::new("CameraConnectingUI"),
@@ -256,5 +300,49 @@ fn setup_playing_ui(mut commands: Commands) {
             align_items: AlignItems::FlexEnd,
             ..default()
         },
+        StateScoped(LauncherStates::Playing),
+    ));
+
+    // --- Player list in top-middle, shown while the player list key is held ---
+    commands.spawn((
+        Name::new("PlayerListUIRoot"),
+        PlayerListRoot,
+        Node {
+            width: Val::Px(400.0),
+            height: Val::Auto,
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            left: Val::Percent(50.0),
+            margin: UiRect::left(Val::Px(-200.0)), // half width
+            padding: UiRect::all(Val::Px(8.0)),
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.15, 0.15, 0.15, 0.75)),
+        Visibility::Hidden,
+        StateScoped(LauncherStates::Playing),
+    ));
+}
+
+fn setup_pause_ui(mut commands: Commands) {
+    debug!("Setting up pause menu...");
+
+    // Spawn the pause menu UI root node scoped to the Paused state.
+    // This will trigger the MainMenuPlugin systems to populate it.
+    commands.spawn((
+        Name::new("PauseMenuUI"),
+        PauseMenuRoot,
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            flex_direction: FlexDirection::Column,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        GlobalZIndex(1),
+        StateScoped(PlayingState::Paused),
     ));
 }
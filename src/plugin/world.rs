@@ -1,12 +1,61 @@
+use std::collections::HashMap;
+
 use avian3d::prelude::*;
 use bevy::prelude::*;
+use itertools::Itertools;
+use lightyear::{
+    connection::host::HostClient,
+    prelude::{server::ClientOf, *},
+};
+use serde::{Deserialize, Serialize};
 
-use super::{controller::*, states::*};
+use super::{commands::*, config::*, controller::*, moderation::*, network::*, states::*};
 use crate::common::prelude::*;
 
 const DISCOVER_RADIUS: u32 = 5;
 const INITIAL_TERRAIN_RADIUS: u32 = 5;
 
+/// The distance in front of the character of the tiles edited by the terrain command.
+const TERRAIN_EDIT_DISTANCE: f32 = 3.0;
+/// The maximum radius of the square of tiles edited by a single terrain command.
+const MAX_TERRAIN_EDIT_RADIUS: i64 = 4;
+
+/// The limits of the terrain edits of the players.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(super) struct TerrainEditConfig {
+    /// Whether every player can edit the terrain, otherwise only the operators can.
+    pub allow_players: bool,
+    /// The number of terrain commands a player can use per second on average.
+    pub edits_per_second: f32,
+    /// The number of terrain commands a player can use in a burst.
+    pub burst: f32,
+}
+
+impl Default for TerrainEditConfig {
+    fn default() -> Self {
+        Self {
+            allow_players: false,
+            edits_per_second: 0.5,
+            burst: 5.0,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Default)]
+struct TerrainEditRateLimits(HashMap<PeerId, TokenBucket>);
+
+/// The edits of the terrain, sent by the server when the terrain is edited and to the players
+/// that join the world.
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
+struct ServerTerrainEditsEvent {
+    /// The height deltas of the edited chunks, by chunk center. The edits of a chunk are removed
+    /// when its deltas are empty.
+    chunks: Vec<(IVec2, HashMap<IVec2, f64>)>,
+}
+
+struct TerrainChannel;
+
 pub(super) struct WorldGenerationPlugin {
     pub render: bool,
 }
@@ -23,6 +72,51 @@ impl Plugin for WorldGenerationPlugin {
 
         app.add_systems(OnEnter(LauncherStates::Generating), setup_initial_terrain);
         app.add_systems(Update, discover_terrain_at_player_position);
+        app.add_observer(add_terrain_viewer);
+        app.add_systems(OnExit(LauncherStates::Playing), cleanup_terrain);
+
+        // The protocol must be the same on the client and on the server
+        app.add_server_event::<ServerTerrainEditsEvent, TerrainChannel>();
+        app.add_channel::<TerrainChannel>(ChannelSettings {
+            mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+            ..default()
+        })
+        .add_direction(NetworkDirection::ServerToClient);
+
+        app.add_observer(send_terrain_edits_to_new_client);
+        app.add_systems(Update, on_server_terrain_edits);
+
+        app.init_resource::<TerrainEditRateLimits>();
+        app.add_observer(forget_terrain_edit_limits);
+        let terrain_permission = if app
+            .world()
+            .resource::<ServerConfig>()
+            .terrain_edits
+            .allow_players
+        {
+            PermissionLevel::Player
+        } else {
+            PermissionLevel::Operator
+        };
+
+        app.add_chat_command(
+            ChatCommand::new(
+                "seed",
+                "Show the seed of the world",
+                PermissionLevel::Player,
+            ),
+            seed_command,
+        );
+        app.add_chat_command(
+            ChatCommand::new(
+                "terrain",
+                "Raise, lower or flatten the terrain in front of your character",
+                terrain_permission,
+            )
+            .arg("edit", ArgKind::Word)
+            .optional_arg("radius", ArgKind::Int),
+            terrain_command,
+        );
     }
 }
 
@@ -39,3 +133,181 @@ fn discover_terrain_at_player_position(
         ev_discover.write(TileDiscoverEvent::new(tile_pos, DISCOVER_RADIUS));
     }
 }
+
+/// The terrain around the player characters is meshed at full detail and has colliders.
+fn add_terrain_viewer(trigger: Trigger<OnAdd, PlayerControllerMarker>, mut commands: Commands) {
+    commands.entity(trigger.target()).insert(TerrainViewer);
+}
+
+fn cleanup_terrain(
+    mut commands: Commands,
+    q_chunks: Query<Entity, With<ChunkCoord>>,
+    mut storage: ResMut<TileMapStorage>,
+    mut edits: ResMut<TerrainEdits>,
+) {
+    debug!("Cleaning up {} terrain chunks", q_chunks.iter().len());
+
+    for entity in &q_chunks {
+        commands.entity(entity).despawn();
+    }
+    storage.clear();
+    edits.clear();
+}
+
+/// Send the edits of the terrain to the players that join the world. The host client shares the
+/// edits with the server.
+fn send_terrain_edits_to_new_client(
+    trigger: Trigger<OnAdd, Connected>,
+    q_connected: Query<(&RemoteId, Has<HostClient>), With<ClientOf>>,
+    edits: Res<TerrainEdits>,
+    mut ev_edits: EventWriter<ToClient<ServerTerrainEditsEvent>>,
+    _: Single<&Server>,
+) {
+    let Ok((RemoteId(peer), is_host)) = q_connected.get(trigger.target()) else {
+        return;
+    };
+    if is_host || edits.iter().next().is_none() {
+        return;
+    }
+    debug!("Sending the terrain edits to peer {:?}", peer);
+
+    ev_edits.write(ToClient {
+        target: NetworkTarget::Single(*peer),
+        event: ServerTerrainEditsEvent {
+            chunks: edits
+                .iter()
+                .map(|(center, deltas)| (*center, deltas.clone()))
+                .collect(),
+        },
+    });
+}
+
+fn on_server_terrain_edits(
+    mut ev_edits: EventReader<ServerTerrainEditsEvent>,
+    mut edits: ResMut<TerrainEdits>,
+) {
+    for ev in ev_edits.read() {
+        debug!("Received the edits of {} terrain chunks", ev.chunks.len());
+
+        for (center, deltas) in &ev.chunks {
+            edits.insert_chunk(*center, deltas.clone());
+        }
+    }
+}
+
+/// Forget the rate limit of the peers that disconnect.
+fn forget_terrain_edit_limits(
+    trigger: Trigger<OnRemove, Connected>,
+    q_connected: Query<&RemoteId>,
+    mut limits: ResMut<TerrainEditRateLimits>,
+) {
+    if let Ok(RemoteId(peer)) = q_connected.get(trigger.target()) {
+        limits.0.remove(peer);
+    }
+}
+
+fn seed_command(
+    In(ctx): In<CommandContext>,
+    seed: Res<TerrainGenerationSeed>,
+    preset: Res<TerrainGenerationPreset>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    ev_reply.write(ctx.reply(format!("World seed: {} ({} preset)", **seed, preset.name)));
+}
+
+fn terrain_command(
+    In(ctx): In<CommandContext>,
+    q_player: Query<(&PlayerId, &Position, &Rotation), With<PlayerControllerMarker>>,
+    storage: Res<TileMapStorage>,
+    generator: Res<ActiveTerrainGenerator>,
+    time: Res<Time>,
+    config: Res<ServerConfig>,
+    mut limits: ResMut<TerrainEditRateLimits>,
+    mut edits: ResMut<TerrainEdits>,
+    mut ev_edits: EventWriter<ToClient<ServerTerrainEditsEvent>>,
+    mut ev_reply: EventWriter<CommandReplyEvent>,
+) {
+    let Some(peer) = ctx.peer() else {
+        ev_reply.write(ctx.reply("Only players can edit the terrain"));
+        return;
+    };
+    let Some((_, position, rotation)) = q_player.iter().find(|(id, _, _)| id.0 == peer) else {
+        ev_reply.write(ctx.reply("You do not have a character"));
+        return;
+    };
+
+    let kind = match ctx.args.text(0).unwrap_or_default() {
+        "raise" => TerrainEditKind::Raise,
+        "lower" => TerrainEditKind::Lower,
+        "flatten" => TerrainEditKind::Flatten,
+        other => {
+            ev_reply.write(ctx.reply(format!(
+                "Unknown terrain edit '{other}', use raise, lower or flatten"
+            )));
+            return;
+        }
+    };
+
+    let config = &config.terrain_edits;
+    let now = time.elapsed_secs_f64();
+    let bucket = limits
+        .0
+        .entry(peer)
+        .or_insert_with(|| TokenBucket::new(config.burst, now));
+    if !bucket.try_take(now, config.edits_per_second, config.burst) {
+        ev_reply.write(ctx.reply("You are editing the terrain too fast"));
+        return;
+    }
+
+    let radius = ctx
+        .args
+        .int(1)
+        .unwrap_or(0)
+        .clamp(0, MAX_TERRAIN_EDIT_RADIUS) as i32;
+
+    let forward = (rotation.0 * Vec3::NEG_Z).xz().normalize_or_zero();
+    let target = storage.world_pos_to_tile(position.0.xz() + forward * TERRAIN_EDIT_DISTANCE);
+
+    let tiles = (-radius..=radius)
+        .flat_map(|x| (-radius..=radius).map(move |y| target + IVec2::new(x, y)))
+        .collect_vec();
+    let chunks = tiles
+        .iter()
+        .filter_map(|tile| {
+            edits.apply(
+                &storage,
+                generator.0.as_ref(),
+                &TerrainEdit { tile: *tile, kind },
+            )
+        })
+        .unique()
+        .collect_vec();
+
+    if !chunks.is_empty() {
+        debug!(
+            "Peer {:?} applied {:?} to {} tiles around {:?}",
+            peer,
+            kind,
+            tiles.len(),
+            target
+        );
+
+        ev_edits.write(ToClient {
+            target: NetworkTarget::All,
+            event: ServerTerrainEditsEvent {
+                chunks: chunks
+                    .into_iter()
+                    .map(|center| (center, edits.chunk(center).cloned().unwrap_or_default()))
+                    .collect(),
+            },
+        });
+    }
+
+    ev_reply.write(ctx.reply(format!(
+        "Applied {:?} to {} tile(s) around {} {}",
+        kind,
+        tiles.len(),
+        target.x,
+        target.y
+    )));
+}